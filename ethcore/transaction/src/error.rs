@@ -16,9 +16,180 @@
 
 use std::{error, fmt};
 
-use ethereum_types::U256;
+use crate::transaction::tx_builders::TxBuilderError;
+use ethereum_types::{Address, U256};
+use rlp::DecoderError;
 use unexpected::OutOfBounds;
 
+/// Transaction decoding failure with the location of the failure.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TxDecodeError {
+    /// Type byte of the transaction, `None` for legacy transactions or if it's unknown.
+    pub tx_type: Option<u8>,
+    /// Name of the field which failed to decode.
+    pub field: Option<&'static str>,
+    /// Index of the field in the transaction list.
+    pub index: Option<usize>,
+    /// Byte offset of the field in the encoded transaction.
+    pub offset: Option<usize>,
+    /// Underlying RLP error.
+    pub rlp: DecoderError,
+}
+
+impl TxDecodeError {
+    pub fn new(rlp: DecoderError) -> Self {
+        TxDecodeError {
+            tx_type: None,
+            field: None,
+            index: None,
+            offset: None,
+            rlp,
+        }
+    }
+
+    /// Sets the type byte if it's not known yet.
+    pub fn with_tx_type(mut self, tx_type: u8) -> Self {
+        self.tx_type.get_or_insert(tx_type);
+        self
+    }
+}
+
+impl From<DecoderError> for TxDecodeError {
+    fn from(err: DecoderError) -> Self { TxDecodeError::new(err) }
+}
+
+impl From<TxDecodeError> for DecoderError {
+    fn from(err: TxDecodeError) -> Self { err.rlp }
+}
+
+impl fmt::Display for TxDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.rlp)?;
+        if let Some(tx_type) = self.tx_type {
+            write!(f, " in tx type {:#04x}", tx_type)?;
+        }
+        match (self.field, self.index) {
+            (Some(field), Some(index)) => write!(f, " at field {} (index {})", field, index)?,
+            (Some(field), None) => write!(f, " at field {}", field)?,
+            (None, Some(index)) => write!(f, " at index {}", index)?,
+            (None, None) => {},
+        }
+        if let Some(offset) = self.offset {
+            write!(f, ", byte offset {}", offset)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for TxDecodeError {
+    fn description(&self) -> &str { "Transaction decoding error" }
+}
+
+/// Chain spec file loading failure.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ChainSpecError {
+    /// File is not valid JSON
+    InvalidJson(String),
+    /// Required field is missing
+    MissingField(&'static str),
+    /// Field has a value of unexpected type or out of range
+    InvalidField(String),
+    /// Fork keys unknown to this crate, the fork schedule would be wrong without them
+    UnknownForkKeys(Vec<String>),
+}
+
+impl fmt::Display for ChainSpecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::ChainSpecError::*;
+        match *self {
+            InvalidJson(ref err) => write!(f, "Chain spec is not valid JSON: {}", err),
+            MissingField(field) => write!(f, "Chain spec field {} is missing", field),
+            InvalidField(ref field) => write!(f, "Chain spec field {} is invalid", field),
+            UnknownForkKeys(ref keys) => write!(f, "Chain spec has unknown fork keys: {}", keys.join(", ")),
+        }
+    }
+}
+
+impl error::Error for ChainSpecError {
+    fn description(&self) -> &str { "Chain spec error" }
+}
+
+/// Ban list file loading failure.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum BanListError {
+    /// File could not be read
+    Io(String),
+    /// File is not valid JSON
+    InvalidJson(String),
+    /// Entry at the index of the JSON array or at the line of the CSV file, counted from one, is invalid
+    InvalidEntry { index: usize, reason: String },
+}
+
+impl fmt::Display for BanListError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::BanListError::*;
+        match *self {
+            Io(ref err) => write!(f, "Ban list could not be read: {}", err),
+            InvalidJson(ref err) => write!(f, "Ban list is not valid JSON: {}", err),
+            InvalidEntry { index, ref reason } => write!(f, "Ban list entry {} is invalid: {}", index, reason),
+        }
+    }
+}
+
+impl error::Error for BanListError {
+    fn description(&self) -> &str { "Ban list error" }
+}
+
+/// Nonce reservation lost once its lease expired, the nonce may have been handed out again.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LeaseExpired {
+    pub sender: Address,
+    pub nonce: U256,
+}
+
+impl fmt::Display for LeaseExpired {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Lease of nonce {} of {:?} expired", self.nonce, self.sender)
+    }
+}
+
+impl error::Error for LeaseExpired {
+    fn description(&self) -> &str { "Nonce lease expired" }
+}
+
+/// Transaction request filling failure.
+#[derive(Debug, PartialEq, Clone)]
+pub enum FillError {
+    /// Provider call failed
+    Provider(String),
+    /// Field is required and no filler sets it
+    MissingField(&'static str),
+    /// Transaction type is not valid on the chain
+    UnsupportedTxType(u8),
+    /// Filled request does not make a valid transaction
+    Builder(TxBuilderError),
+}
+
+impl From<TxBuilderError> for FillError {
+    fn from(err: TxBuilderError) -> Self { FillError::Builder(err) }
+}
+
+impl fmt::Display for FillError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::FillError::*;
+        match *self {
+            Provider(ref err) => write!(f, "Provider call failed: {}", err),
+            MissingField(field) => write!(f, "Transaction request field {} is missing", field),
+            UnsupportedTxType(tx_type) => write!(f, "Transaction type {} is not supported by the chain", tx_type),
+            Builder(ref err) => write!(f, "Transaction request is invalid: {}", err),
+        }
+    }
+}
+
+impl error::Error for FillError {
+    fn description(&self) -> &str { "Transaction request fill error" }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 /// Errors concerning transaction processing.
 pub enum Error {
@@ -76,7 +247,28 @@ pub enum Error {
     /// Transaction too big
     TooBig,
     /// Invalid RLP encoding
-    InvalidRlp(String),
+    InvalidRlp(TxDecodeError),
+    /// Blob sidecar does not match the blob transaction
+    InvalidBlobSidecar(String),
+    /// Blob KZG proofs verification failed
+    InvalidKzgProof(String),
+    /// Type byte can't be used by a typed transaction
+    InvalidTxType(u8),
+    /// Contract bytecode can't be hashed for zkSync
+    InvalidBytecode(String),
+    /// Typed transaction decoder is already registered for the type byte
+    TxTypeAlreadyRegistered(u8),
+    /// Typed transaction decoder is one of this crate and can't be unregistered
+    BuiltinTxType(u8),
+    /// Transaction type is not enabled on the chain at this fork
+    TxTypeNotEnabled(u8),
+    /// Contract creation initcode is larger than allowed (EIP-3860)
+    InitcodeTooLarge {
+        /// Max initcode size
+        limit: usize,
+        /// Transaction initcode size
+        got: usize,
+    },
 }
 
 impl From<ethkey::Error> for Error {
@@ -84,7 +276,11 @@ impl From<ethkey::Error> for Error {
 }
 
 impl From<rlp::DecoderError> for Error {
-    fn from(err: rlp::DecoderError) -> Self { Error::InvalidRlp(format!("{}", err)) }
+    fn from(err: rlp::DecoderError) -> Self { Error::InvalidRlp(err.into()) }
+}
+
+impl From<TxDecodeError> for Error {
+    fn from(err: TxDecodeError) -> Self { Error::InvalidRlp(err) }
 }
 
 impl fmt::Display for Error {
@@ -119,6 +315,14 @@ impl fmt::Display for Error {
             NotAllowed => "Sender does not have permissions to execute this type of transction".into(),
             TooBig => "Transaction too big".into(),
             InvalidRlp(ref err) => format!("Transaction has invalid RLP structure: {}.", err),
+            InvalidBlobSidecar(ref err) => format!("Transaction has invalid blob sidecar: {}.", err),
+            InvalidKzgProof(ref err) => format!("Blob KZG proof verification failed: {}.", err),
+            InvalidTxType(tx_type) => format!("Invalid transaction type {:#04x}.", tx_type),
+            InvalidBytecode(ref err) => format!("Invalid contract bytecode: {}.", err),
+            TxTypeAlreadyRegistered(tx_type) => format!("Transaction type {:#04x} is already registered.", tx_type),
+            BuiltinTxType(tx_type) => format!("Transaction type {:#04x} is built in.", tx_type),
+            TxTypeNotEnabled(tx_type) => format!("Transaction type {:#04x} is not enabled on this chain.", tx_type),
+            InitcodeTooLarge { limit, got } => format!("Initcode too large. Max={}, Given={}", limit, got),
         };
 
         f.write_fmt(format_args!("Transaction error ({})", msg))
@@ -16,15 +16,20 @@
 
 //! Ethereum Transactions
 
+#[cfg(feature = "kzg")]
+extern crate c_kzg;
 extern crate ethereum_types;
 extern crate ethkey;
+extern crate fs2;
 extern crate keccak_hash as hash;
 extern crate rlp;
 extern crate rustc_hex;
+extern crate serde_json;
+extern crate sha2;
 extern crate unexpected;
 
 mod error;
 mod transaction;
 
-pub use error::Error;
+pub use error::{BanListError, ChainSpecError, Error, FillError, LeaseExpired, TxDecodeError};
 pub use transaction::*;
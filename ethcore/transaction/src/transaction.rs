@@ -18,26 +18,79 @@
 
 //! Transaction data structure.
 
-use crate::{error, Error};
+use crate::{error, Error, TxDecodeError};
 use ethereum_types::{Address, H160, H256, U256};
 use ethkey::{self, public_to_address, recover, Public, Secret, Signature};
 use hash::keccak;
 use rlp::{self, DecoderError, Rlp, RlpStream};
-use std::ops::Deref;
+use std::{convert::TryFrom, ops::Deref};
 
 mod legacy;
 pub mod tx_builders;
 pub use self::legacy::{LegacyTransaction, UnverifiedLegacyTransaction, eip155_methods};
-pub use self::tx_builders::TransactionWrapperBuilder;
+pub use self::tx_builders::{bump_fee, TransactionWrapperBuilder, MIN_BLOB_REPLACEMENT_BUMP_PERCENT,
+                            MIN_REPLACEMENT_BUMP_PERCENT};
 mod eip2930;
 pub use self::eip2930::{AccessList, AccessListItem, Eip2930Transaction, UnverifiedEip2930Transaction};
 mod eip1559;
 pub use self::eip1559::{Eip1559Transaction, UnverifiedEip1559Transaction};
+mod eip4844;
+pub use self::eip4844::{blob_base_fee, fake_exponential, kzg_to_versioned_hash, BlobTransactionSidecar,
+                        Eip4844Transaction, UnverifiedEip4844Transaction, UnverifiedEip4844TransactionWithSidecar,
+                        BLOB_BASE_FEE_UPDATE_FRACTION_CANCUN, BLOB_BASE_FEE_UPDATE_FRACTION_PRAGUE, BYTES_PER_BLOB,
+                        BYTES_PER_COMMITMENT, BYTES_PER_PROOF, GAS_PER_BLOB, MIN_BASE_FEE_PER_BLOB_GAS,
+                        VERSIONED_HASH_VERSION_KZG};
+#[cfg(feature = "kzg")]
+pub use self::eip4844::{load_trusted_setup, KzgSettings};
+mod eip7702;
+pub use self::eip7702::{Eip7702Transaction, SignedAuthorization, UnverifiedEip7702Transaction};
+mod cip64;
+pub use self::cip64::{Cip64Transaction, UnverifiedCip64Transaction};
+mod zksync;
+pub use self::zksync::{hash_bytecode, PaymasterParams, UnverifiedZkSyncEip712Transaction, ZkSyncEip712Transaction,
+                       DEFAULT_GAS_PER_PUBDATA_LIMIT};
+mod registry;
+pub use self::registry::{register_typed_transaction, unregister_typed_transaction, CustomTransaction,
+                         CustomTypedTransaction, DecodeMode, SenderPolicy, TypedTransactionDecoder,
+                         TypedTransactionRegistry, MAX_TX_TYPE};
+mod intrinsic_gas;
+pub use self::intrinsic_gas::{intrinsic_gas, Fork, ACCESS_LIST_ADDRESS_GAS, ACCESS_LIST_STORAGE_KEY_GAS,
+                              INITCODE_WORD_GAS, PER_EMPTY_ACCOUNT_COST, TOKENS_PER_NON_ZERO_BYTE,
+                              TOTAL_COST_FLOOR_PER_TOKEN, TX_CREATE_GAS, TX_DATA_NON_ZERO_GAS_EIP2028,
+                              TX_DATA_NON_ZERO_GAS_FRONTIER, TX_DATA_ZERO_GAS, TX_GAS};
+mod chain_spec;
+pub use self::chain_spec::{ChainRules, ChainSpec, FeeParams, ForkActivation, BASE_FEE_MAX_CHANGE_DENOMINATOR,
+                           ELASTICITY_MULTIPLIER, INITIAL_BASE_FEE, MAX_INITCODE_SIZE, MAX_TX_GAS};
+mod ban_list;
+mod chain_config;
+pub use self::ban_list::{BanKey, BanList};
+mod permissions;
+pub use self::permissions::{AllowMapPermissions, Permissions, StaticPermissions, TransactionKind,
+                            TransactionPermissions};
+mod pool;
+pub use self::pool::{PoolOptions, TransactionPool};
+mod journal;
+pub use self::journal::TransactionJournal;
+mod scheduler;
+pub use self::scheduler::TransactionScheduler;
+mod nonce_manager;
+pub use self::nonce_manager::{NonceManager, NonceReservation, DEFAULT_NONCE_LEASE};
+mod request;
+pub use self::request::{ChainIdFiller, FeeFiller, FillPipeline, GasFiller, MockProvider, NonceFiller, Provider,
+                        TransactionRequest, TxFiller, TxTypeFiller};
+mod verifier;
+pub use self::verifier::{AccountDetails, TransactionVerifier};
+mod op_deposit;
+pub use self::op_deposit::DepositTransaction;
+mod arbitrum;
+pub use self::arbitrum::{ArbitrumContractTransaction, ArbitrumDepositTransaction, ArbitrumInternalTransaction,
+                         ArbitrumRetryTransaction, ArbitrumSubmitRetryableTransaction, ArbitrumTransaction,
+                         ArbitrumTransactionKind, ArbitrumUnsignedTransaction, ARBOS_ADDRESS, ARB_RETRYABLE_TX_ADDRESS};
 
 type BlockNumber = u64;
 type Bytes = Vec<u8>;
 
-#[derive(Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TxType {
     /// Legacy non-typed transaction
     Legacy,
@@ -45,16 +98,49 @@ pub enum TxType {
     Type1 = 1,
     /// Transaction with EIP-2718 type field set to 2 according to EIP-1559
     Type2 = 2,
-    /// Indicates we could not parse transaction type correctly
-    Invalid,
+    /// Transaction with EIP-2718 type field set to 3 according to EIP-4844
+    Type3 = 3,
+    /// Transaction with EIP-2718 type field set to 4 according to EIP-7702
+    Type4 = 4,
+    /// Arbitrum L1 to L2 deposit
+    ArbitrumDeposit = 0x64,
+    /// Arbitrum L1 originated unsigned call
+    ArbitrumUnsigned = 0x65,
+    /// Arbitrum L1 originated contract call
+    ArbitrumContract = 0x66,
+    /// Arbitrum retryable ticket redemption
+    ArbitrumRetry = 0x68,
+    /// Arbitrum retryable ticket submission
+    ArbitrumSubmitRetryable = 0x69,
+    /// Arbitrum internal ArbOS transaction
+    ArbitrumInternal = 0x6a,
+    /// zkSync Era transaction signed according to EIP-712
+    ZkSyncEip712 = 0x71,
+    /// Celo transaction paying gas in an ERC-20 token according to CIP-64
+    Cip64 = 0x7b,
+    /// OP-Stack deposit transaction, derived from L1 and not signed
+    Deposit = 0x7e,
 }
 
-impl From<u8> for TxType {
-    fn from(v: u8) -> Self {
+impl TryFrom<u8> for TxType {
+    type Error = TxDecodeError;
+
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
         match v {
-            1 => TxType::Type1,
-            2 => TxType::Type2,
-            _ => TxType::Invalid,
+            1 => Ok(TxType::Type1),
+            2 => Ok(TxType::Type2),
+            3 => Ok(TxType::Type3),
+            4 => Ok(TxType::Type4),
+            0x64 => Ok(TxType::ArbitrumDeposit),
+            0x65 => Ok(TxType::ArbitrumUnsigned),
+            0x66 => Ok(TxType::ArbitrumContract),
+            0x68 => Ok(TxType::ArbitrumRetry),
+            0x69 => Ok(TxType::ArbitrumSubmitRetryable),
+            0x6a => Ok(TxType::ArbitrumInternal),
+            0x71 => Ok(TxType::ZkSyncEip712),
+            0x7b => Ok(TxType::Cip64),
+            0x7e => Ok(TxType::Deposit),
+            _ => Err(TxDecodeError::new(DecoderError::Custom("unsupported tx version")).with_tx_type(v)),
         }
     }
 }
@@ -108,6 +194,55 @@ pub enum Condition {
     Number(BlockNumber),
     /// Valid at this unix time or later.
     Timestamp(u64),
+    /// Valid while the base fee is below this value.
+    BaseFeeBelow(U256),
+    /// Valid when all the conditions are.
+    And(Vec<Condition>),
+    /// Valid when any of the conditions is.
+    Or(Vec<Condition>),
+}
+
+impl Condition {
+    /// Whether the condition holds at a block with the given number, timestamp and base fee,
+    /// the base fee being unknown before London.
+    pub fn is_met(&self, block: BlockNumber, timestamp: u64, base_fee: Option<U256>) -> bool {
+        match *self {
+            Condition::Number(number) => block >= number,
+            Condition::Timestamp(time) => timestamp >= time,
+            Condition::BaseFeeBelow(max) => base_fee.map_or(false, |base_fee| base_fee < max),
+            Condition::And(ref conditions) => conditions.iter().all(|c| c.is_met(block, timestamp, base_fee)),
+            Condition::Or(ref conditions) => conditions.iter().any(|c| c.is_met(block, timestamp, base_fee)),
+        }
+    }
+}
+
+impl rlp::Encodable for Condition {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(2);
+        match *self {
+            Condition::Number(number) => s.append(&0u8).append(&number),
+            Condition::Timestamp(time) => s.append(&1u8).append(&time),
+            Condition::BaseFeeBelow(ref max) => s.append(&2u8).append(max),
+            Condition::And(ref conditions) => s.append(&3u8).append_list(conditions),
+            Condition::Or(ref conditions) => s.append(&4u8).append_list(conditions),
+        };
+    }
+}
+
+impl rlp::Decodable for Condition {
+    fn decode(d: &Rlp) -> Result<Self, DecoderError> {
+        if d.item_count()? != 2 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+        match d.val_at::<u8>(0)? {
+            0 => Ok(Condition::Number(d.val_at(1)?)),
+            1 => Ok(Condition::Timestamp(d.val_at(1)?)),
+            2 => Ok(Condition::BaseFeeBelow(d.val_at(1)?)),
+            3 => Ok(Condition::And(d.list_at(1)?)),
+            4 => Ok(Condition::Or(d.list_at(1)?)),
+            _ => Err(DecoderError::Custom("Unknown condition kind")),
+        }
+    }
 }
 
 type TransactionSharedRet = dyn TransactionShared + Send + Sync + 'static;
@@ -128,12 +263,27 @@ pub trait TransactionShared {
 }
 
 /// Methods common all signed tx versions
+///
+/// The `Encodable` implementation of a signed transaction appends its raw encoding, see
+/// `encode_raw`. The envelope of a typed transaction is not a single RLP item, transactions
+/// are appended into a list with `UnverifiedTransactionWrapper::rlp_append_block_item`.
 pub trait SignedTransactionShared {
+    /// Append object with a signature into RLP stream, a typed transaction as its raw
+    /// `type || payload` envelope.
+    fn rlp_append_sealed_transaction(&self, s: &mut RlpStream);
+
+    /// Raw encoding as taken by `eth_sendRawTransaction`, the transaction hash is the hash of it.
+    fn encode_raw(&self) -> Vec<u8> {
+        let mut s = RlpStream::new();
+        self.rlp_append_sealed_transaction(&mut s);
+        s.out().to_vec()
+    }
+
     fn compute_hash(mut self) -> Self
     where
-        Self: rlp::Encodable + Sized,
+        Self: Sized,
     {
-        let hash = keccak(&*self.rlp_bytes());
+        let hash = keccak(self.encode_raw());
         self.set_hash(hash);
         self
     }
@@ -141,11 +291,21 @@ pub trait SignedTransactionShared {
     fn set_hash(&mut self, hash: H256);
 }
 
+/// Unsigned transaction, to be signed with `sign` or `with_signature`.
+///
+/// It has no RLP encoding of its own: an unsigned transaction is not sent nor stored, and its
+/// signing payload is not a stable serialization, as the one of a legacy transaction depends on
+/// the chain id given at signing and the one of a zkSync transaction is an EIP-712 hash. Use
+/// `TransactionRequest` to pass an unsigned transaction around.
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum TransactionWrapper {
     Legacy(LegacyTransaction),
     Eip2930(Eip2930Transaction),
     Eip1559(Eip1559Transaction),
+    Eip4844(Eip4844Transaction),
+    Eip7702(Eip7702Transaction),
+    Cip64(Cip64Transaction),
+    ZkSyncEip712(ZkSyncEip712Transaction),
 }
 
 impl TransactionWrapper {
@@ -155,6 +315,10 @@ impl TransactionWrapper {
             TransactionWrapper::Legacy(tx) => tx.message_hash(chain_id),
             TransactionWrapper::Eip2930(tx) => tx.message_hash(None),
             TransactionWrapper::Eip1559(tx) => tx.message_hash(None),
+            TransactionWrapper::Eip4844(tx) => tx.message_hash(None),
+            TransactionWrapper::Eip7702(tx) => tx.message_hash(None),
+            TransactionWrapper::Cip64(tx) => tx.message_hash(None),
+            TransactionWrapper::ZkSyncEip712(tx) => tx.message_hash(None),
         }
     }
 
@@ -177,14 +341,107 @@ impl TransactionWrapper {
         .compute_hash())
     }
 
+    /// Add a signature checked by the sender account contract, supported by zkSync transactions only.
+    /// The sender of the result is not authenticated, see `unauthenticated_sender`.
+    pub fn with_custom_signature(self, custom_signature: Bytes) -> Result<UnverifiedTransactionWrapper, Error> {
+        match self {
+            TransactionWrapper::ZkSyncEip712(tx) => Ok(UnverifiedTransactionWrapper::ZkSyncEip712(
+                UnverifiedZkSyncEip712Transaction::with_custom_signature(tx, custom_signature)?,
+            )),
+            _ => Err(Error::InvalidSignature(
+                "custom signature is supported by zkSync transactions only".into(),
+            )),
+        }
+    }
+
     pub fn shared(&self) -> &TransactionSharedRet {
         match self {
             TransactionWrapper::Legacy(tx) => tx as &TransactionSharedRet,
             TransactionWrapper::Eip2930(tx) => tx as &TransactionSharedRet,
             TransactionWrapper::Eip1559(tx) => tx as &TransactionSharedRet,
+            TransactionWrapper::Eip4844(tx) => tx as &TransactionSharedRet,
+            TransactionWrapper::Eip7702(tx) => tx as &TransactionSharedRet,
+            TransactionWrapper::Cip64(tx) => tx as &TransactionSharedRet,
+            TransactionWrapper::ZkSyncEip712(tx) => tx as &TransactionSharedRet,
+        }
+    }
+
+    pub fn tx_type(&self) -> TxType {
+        match self {
+            TransactionWrapper::Legacy(_) => TxType::Legacy,
+            TransactionWrapper::Eip2930(_) => TxType::Type1,
+            TransactionWrapper::Eip1559(_) => TxType::Type2,
+            TransactionWrapper::Eip4844(_) => TxType::Type3,
+            TransactionWrapper::Eip7702(_) => TxType::Type4,
+            TransactionWrapper::Cip64(_) => TxType::Cip64,
+            TransactionWrapper::ZkSyncEip712(_) => TxType::ZkSyncEip712,
+        }
+    }
+
+    /// Chain id of a typed transaction, `None` for legacy ones as it is set on signing.
+    pub fn chain_id(&self) -> Option<u64> {
+        match self {
+            TransactionWrapper::Legacy(_) => None,
+            TransactionWrapper::Eip2930(tx) => Some(tx.chain_id),
+            TransactionWrapper::Eip1559(tx) => Some(tx.chain_id),
+            TransactionWrapper::Eip4844(tx) => Some(tx.chain_id),
+            TransactionWrapper::Eip7702(tx) => Some(tx.chain_id),
+            TransactionWrapper::Cip64(tx) => Some(tx.chain_id),
+            TransactionWrapper::ZkSyncEip712(tx) => Some(tx.chain_id),
+        }
+    }
+
+    /// Gas price, or max fee per gas for EIP-1559 like transactions.
+    pub fn gas_price(&self) -> U256 {
+        match self {
+            TransactionWrapper::Legacy(tx) => tx.gas_price(),
+            TransactionWrapper::Eip2930(tx) => tx.gas_price(),
+            _ => self.max_fee_per_gas(),
+        }
+    }
+
+    /// Max fee per gas, or gas price for transactions without a priority fee.
+    pub fn max_fee_per_gas(&self) -> U256 {
+        match self {
+            TransactionWrapper::Legacy(tx) => tx.gas_price(),
+            TransactionWrapper::Eip2930(tx) => tx.gas_price(),
+            TransactionWrapper::Eip1559(tx) => tx.max_fee_per_gas(),
+            TransactionWrapper::Eip4844(tx) => tx.max_fee_per_gas(),
+            TransactionWrapper::Eip7702(tx) => tx.max_fee_per_gas(),
+            TransactionWrapper::Cip64(tx) => tx.max_fee_per_gas(),
+            TransactionWrapper::ZkSyncEip712(tx) => tx.max_fee_per_gas(),
+        }
+    }
+
+    /// Max priority fee per gas, or gas price for transactions without a priority fee.
+    pub fn max_priority_fee_per_gas(&self) -> U256 {
+        match self {
+            TransactionWrapper::Legacy(tx) => tx.gas_price(),
+            TransactionWrapper::Eip2930(tx) => tx.gas_price(),
+            TransactionWrapper::Eip1559(tx) => tx.max_priority_fee_per_gas(),
+            TransactionWrapper::Eip4844(tx) => tx.max_priority_fee_per_gas(),
+            TransactionWrapper::Eip7702(tx) => tx.max_priority_fee_per_gas(),
+            TransactionWrapper::Cip64(tx) => tx.max_priority_fee_per_gas(),
+            TransactionWrapper::ZkSyncEip712(tx) => tx.max_priority_fee_per_gas(),
+        }
+    }
+
+    pub fn access_list(&self) -> Option<&AccessList> {
+        match self {
+            TransactionWrapper::Legacy(_) | TransactionWrapper::ZkSyncEip712(_) => None,
+            TransactionWrapper::Eip2930(tx) => Some(tx.access_list()),
+            TransactionWrapper::Eip1559(tx) => Some(tx.access_list()),
+            TransactionWrapper::Eip4844(tx) => Some(tx.access_list()),
+            TransactionWrapper::Eip7702(tx) => Some(tx.access_list()),
+            TransactionWrapper::Cip64(tx) => Some(tx.access_list()),
         }
     }
 
+    /// Gas price paid by the transaction in a block with `base_fee`, the max fee if `base_fee` is unknown.
+    pub fn effective_gas_price(&self, base_fee: Option<U256>) -> U256 {
+        effective_gas_price(self.max_fee_per_gas(), self.max_priority_fee_per_gas(), base_fee)
+    }
+
     /// Useful for test incorrectly signed transactions.
     #[cfg(test)]
     pub fn invalid_sign(self) -> UnverifiedTransactionWrapper {
@@ -217,29 +474,28 @@ pub enum UnverifiedTransactionWrapper {
     Legacy(UnverifiedLegacyTransaction),
     Eip2930(UnverifiedEip2930Transaction),
     Eip1559(UnverifiedEip1559Transaction),
+    Eip4844(UnverifiedEip4844Transaction),
+    Eip7702(UnverifiedEip7702Transaction),
+    Cip64(UnverifiedCip64Transaction),
+    ZkSyncEip712(UnverifiedZkSyncEip712Transaction),
+    Deposit(DepositTransaction),
+    Arbitrum(ArbitrumTransaction),
+    /// Transaction type registered outside of this crate
+    Custom(CustomTransaction),
 }
 
+/// Accepts both the raw EIP-2718 envelope and the byte string wrapped one, as found in a block body.
 impl rlp::Decodable for UnverifiedTransactionWrapper {
     fn decode(d: &Rlp) -> Result<Self, DecoderError> {
-        if is_typed_transaction(d) {
-            // first byte is tx version
-            match TxType::from(d.as_raw()[0]) {
-                TxType::Type1 => Ok(UnverifiedTransactionWrapper::Eip2930(
-                    UnverifiedEip2930Transaction::decode(d)?,
-                )),
-                TxType::Type2 => Ok(UnverifiedTransactionWrapper::Eip1559(
-                    UnverifiedEip1559Transaction::decode(d)?,
-                )),
-                _ => Err(DecoderError::Custom("unsupported tx version")),
-            }
-        } else {
-            Ok(UnverifiedTransactionWrapper::Legacy(
-                UnverifiedLegacyTransaction::decode(d)?,
-            ))
-        }
+        Ok(registry::decode_with_global_registry(d, DecodeMode::Lenient)?)
     }
 }
 
+/// Encodes the raw EIP-2718 envelope, see `rlp_append_block_item` for the block body form.
+impl rlp::Encodable for UnverifiedTransactionWrapper {
+    fn rlp_append(&self, s: &mut RlpStream) { self.rlp_append_sealed_transaction(s) }
+}
+
 impl UnverifiedTransactionWrapper {
     /// Creates new UnverifiedTransactionWrapper from TransactionWrapper and signature params.
     /// For Legacy transactions param v must be not modified for replay protection with chain_id.
@@ -255,6 +511,18 @@ impl UnverifiedTransactionWrapper {
             TransactionWrapper::Eip1559(unsigned) => Ok(UnverifiedTransactionWrapper::Eip1559(
                 UnverifiedEip1559Transaction::new(unsigned, r, s, v, hash)?,
             )),
+            TransactionWrapper::Eip4844(unsigned) => Ok(UnverifiedTransactionWrapper::Eip4844(
+                UnverifiedEip4844Transaction::new(unsigned, r, s, v, hash)?,
+            )),
+            TransactionWrapper::Eip7702(unsigned) => Ok(UnverifiedTransactionWrapper::Eip7702(
+                UnverifiedEip7702Transaction::new(unsigned, r, s, v, hash)?,
+            )),
+            TransactionWrapper::Cip64(unsigned) => Ok(UnverifiedTransactionWrapper::Cip64(
+                UnverifiedCip64Transaction::new(unsigned, r, s, v, hash)?,
+            )),
+            TransactionWrapper::ZkSyncEip712(unsigned) => Ok(UnverifiedTransactionWrapper::ZkSyncEip712(
+                UnverifiedZkSyncEip712Transaction::new(unsigned, r, s, v, hash)?,
+            )),
         }
     }
 
@@ -263,66 +531,255 @@ impl UnverifiedTransactionWrapper {
             UnverifiedTransactionWrapper::Legacy(tx) => UnverifiedTransactionWrapper::Legacy(tx.compute_hash()),
             UnverifiedTransactionWrapper::Eip2930(tx) => UnverifiedTransactionWrapper::Eip2930(tx.compute_hash()),
             UnverifiedTransactionWrapper::Eip1559(tx) => UnverifiedTransactionWrapper::Eip1559(tx.compute_hash()),
+            UnverifiedTransactionWrapper::Eip4844(tx) => UnverifiedTransactionWrapper::Eip4844(tx.compute_hash()),
+            UnverifiedTransactionWrapper::Eip7702(tx) => UnverifiedTransactionWrapper::Eip7702(tx.compute_hash()),
+            UnverifiedTransactionWrapper::Cip64(tx) => UnverifiedTransactionWrapper::Cip64(tx.compute_hash()),
+            UnverifiedTransactionWrapper::ZkSyncEip712(tx) => {
+                UnverifiedTransactionWrapper::ZkSyncEip712(tx.compute_hash())
+            },
+            UnverifiedTransactionWrapper::Deposit(tx) => UnverifiedTransactionWrapper::Deposit(tx.compute_hash()),
+            UnverifiedTransactionWrapper::Arbitrum(tx) => UnverifiedTransactionWrapper::Arbitrum(tx.compute_hash()),
+            UnverifiedTransactionWrapper::Custom(tx) => UnverifiedTransactionWrapper::Custom(tx),
         }
     }
 
-    /// Append object with a signature into RLP stream
-    fn rlp_append_sealed_transaction(&self, s: &mut RlpStream) {
+    /// Decodes a transaction, rejecting non canonical encodings in strict mode.
+    /// Accepts both the raw EIP-2718 envelope and the byte string wrapped one.
+    pub fn decode_with_mode(d: &Rlp, mode: DecodeMode) -> Result<Self, TxDecodeError> {
+        registry::decode_with_global_registry(d, mode)
+    }
+
+    /// Append object with a signature into RLP stream, a typed transaction as its raw
+    /// `type || payload` envelope, which is not a single RLP item.
+    pub fn rlp_append_sealed_transaction(&self, s: &mut RlpStream) {
         match self {
             UnverifiedTransactionWrapper::Legacy(tx) => tx.rlp_append_sealed_transaction(s),
             UnverifiedTransactionWrapper::Eip2930(tx) => tx.rlp_append_sealed_transaction(s),
             UnverifiedTransactionWrapper::Eip1559(tx) => tx.rlp_append_sealed_transaction(s),
+            UnverifiedTransactionWrapper::Eip4844(tx) => tx.rlp_append_sealed_transaction(s),
+            UnverifiedTransactionWrapper::Eip7702(tx) => tx.rlp_append_sealed_transaction(s),
+            UnverifiedTransactionWrapper::Cip64(tx) => tx.rlp_append_sealed_transaction(s),
+            UnverifiedTransactionWrapper::ZkSyncEip712(tx) => tx.rlp_append_sealed_transaction(s),
+            UnverifiedTransactionWrapper::Deposit(tx) => tx.rlp_append_sealed_transaction(s),
+            UnverifiedTransactionWrapper::Arbitrum(tx) => tx.rlp_append_sealed_transaction(s),
+            UnverifiedTransactionWrapper::Custom(tx) => tx.rlp_append_sealed_transaction(s),
         };
     }
 
+    /// Append object into RLP stream in the form it takes in a block body:
+    /// legacy transaction as a list, typed transaction envelope as a byte string.
+    pub fn rlp_append_block_item(&self, s: &mut RlpStream) {
+        match self {
+            UnverifiedTransactionWrapper::Legacy(tx) => tx.rlp_append_sealed_transaction(s),
+            _ => {
+                s.append(&self.encode_raw());
+            },
+        }
+    }
+
+    /// Raw encoding as taken by `eth_sendRawTransaction`: the RLP list of a legacy transaction,
+    /// the `type || payload` envelope of a typed one. The transaction hash is the hash of it.
+    pub fn encode_raw(&self) -> Vec<u8> {
+        let mut s = RlpStream::new();
+        self.rlp_append_sealed_transaction(&mut s);
+        s.out().to_vec()
+    }
+
     pub fn unsigned(&self) -> &TransactionSharedRet {
         match self {
             UnverifiedTransactionWrapper::Legacy(tx) => tx.deref() as &TransactionSharedRet,
             UnverifiedTransactionWrapper::Eip2930(tx) => tx.deref() as &TransactionSharedRet,
             UnverifiedTransactionWrapper::Eip1559(tx) => tx.deref() as &TransactionSharedRet,
+            UnverifiedTransactionWrapper::Eip4844(tx) => tx.deref() as &TransactionSharedRet,
+            UnverifiedTransactionWrapper::Eip7702(tx) => tx.deref() as &TransactionSharedRet,
+            UnverifiedTransactionWrapper::Cip64(tx) => tx.deref() as &TransactionSharedRet,
+            UnverifiedTransactionWrapper::ZkSyncEip712(tx) => tx.deref() as &TransactionSharedRet,
+            UnverifiedTransactionWrapper::Deposit(tx) => tx as &TransactionSharedRet,
+            UnverifiedTransactionWrapper::Arbitrum(tx) => tx as &TransactionSharedRet,
+            UnverifiedTransactionWrapper::Custom(tx) => tx as &TransactionSharedRet,
+        }
+    }
+
+    /// Transaction type, `None` for a custom type registered outside of this crate.
+    pub fn tx_type(&self) -> Option<TxType> {
+        match self {
+            UnverifiedTransactionWrapper::Legacy(_) => Some(TxType::Legacy),
+            UnverifiedTransactionWrapper::Eip2930(_) => Some(TxType::Type1),
+            UnverifiedTransactionWrapper::Eip1559(_) => Some(TxType::Type2),
+            UnverifiedTransactionWrapper::Eip4844(_) => Some(TxType::Type3),
+            UnverifiedTransactionWrapper::Eip7702(_) => Some(TxType::Type4),
+            UnverifiedTransactionWrapper::Cip64(_) => Some(TxType::Cip64),
+            UnverifiedTransactionWrapper::ZkSyncEip712(_) => Some(TxType::ZkSyncEip712),
+            UnverifiedTransactionWrapper::Deposit(_) => Some(TxType::Deposit),
+            UnverifiedTransactionWrapper::Arbitrum(tx) => Some(tx.tx_type()),
+            UnverifiedTransactionWrapper::Custom(_) => None,
+        }
+    }
+
+    /// EIP-2718 type byte, 0 for legacy transactions.
+    pub fn type_byte(&self) -> u8 {
+        match self {
+            UnverifiedTransactionWrapper::Custom(tx) => tx.type_byte(),
+            tx => tx.tx_type().expect("only custom transactions have no known type; qed") as u8,
+        }
+    }
+
+    /// Chain id the transaction is bound to, `None` if it is valid on any chain.
+    /// For legacy transactions this is the EIP-155 chain id encoded into v.
+    pub fn chain_id(&self) -> Option<u64> {
+        match self {
+            UnverifiedTransactionWrapper::Legacy(tx) => eip155_methods::chain_id_from_v(tx.v()),
+            UnverifiedTransactionWrapper::Eip2930(tx) => Some(tx.chain_id),
+            UnverifiedTransactionWrapper::Eip1559(tx) => Some(tx.chain_id),
+            UnverifiedTransactionWrapper::Eip4844(tx) => Some(tx.chain_id),
+            UnverifiedTransactionWrapper::Eip7702(tx) => Some(tx.chain_id),
+            UnverifiedTransactionWrapper::Cip64(tx) => Some(tx.chain_id),
+            UnverifiedTransactionWrapper::ZkSyncEip712(tx) => Some(tx.chain_id),
+            UnverifiedTransactionWrapper::Deposit(_) => None,
+            UnverifiedTransactionWrapper::Arbitrum(tx) => u64::try_from(tx.chain_id()).ok(),
+            UnverifiedTransactionWrapper::Custom(tx) => tx.inner().chain_id(),
+        }
+    }
+
+    /// Gas price, or max fee per gas for EIP-1559 like transactions.
+    pub fn gas_price(&self) -> U256 {
+        match self {
+            UnverifiedTransactionWrapper::Legacy(tx) => tx.gas_price(),
+            UnverifiedTransactionWrapper::Eip2930(tx) => tx.gas_price(),
+            _ => self.max_fee_per_gas(),
+        }
+    }
+
+    /// Max fee per gas, or gas price for transactions without a priority fee.
+    /// Zero for deposits paid on L1.
+    pub fn max_fee_per_gas(&self) -> U256 {
+        match self {
+            UnverifiedTransactionWrapper::Legacy(tx) => tx.gas_price(),
+            UnverifiedTransactionWrapper::Eip2930(tx) => tx.gas_price(),
+            UnverifiedTransactionWrapper::Eip1559(tx) => tx.max_fee_per_gas(),
+            UnverifiedTransactionWrapper::Eip4844(tx) => tx.max_fee_per_gas(),
+            UnverifiedTransactionWrapper::Eip7702(tx) => tx.max_fee_per_gas(),
+            UnverifiedTransactionWrapper::Cip64(tx) => tx.max_fee_per_gas(),
+            UnverifiedTransactionWrapper::ZkSyncEip712(tx) => tx.max_fee_per_gas(),
+            UnverifiedTransactionWrapper::Deposit(_) => U256::zero(),
+            UnverifiedTransactionWrapper::Arbitrum(tx) => tx.gas_fee_cap(),
+            UnverifiedTransactionWrapper::Custom(tx) => tx.inner().max_fee_per_gas(),
+        }
+    }
+
+    /// Max priority fee per gas, or gas price for transactions without a priority fee.
+    /// Zero for deposits and Arbitrum transactions, which pay no tip.
+    pub fn max_priority_fee_per_gas(&self) -> U256 {
+        match self {
+            UnverifiedTransactionWrapper::Legacy(tx) => tx.gas_price(),
+            UnverifiedTransactionWrapper::Eip2930(tx) => tx.gas_price(),
+            UnverifiedTransactionWrapper::Eip1559(tx) => tx.max_priority_fee_per_gas(),
+            UnverifiedTransactionWrapper::Eip4844(tx) => tx.max_priority_fee_per_gas(),
+            UnverifiedTransactionWrapper::Eip7702(tx) => tx.max_priority_fee_per_gas(),
+            UnverifiedTransactionWrapper::Cip64(tx) => tx.max_priority_fee_per_gas(),
+            UnverifiedTransactionWrapper::ZkSyncEip712(tx) => tx.max_priority_fee_per_gas(),
+            UnverifiedTransactionWrapper::Deposit(_) | UnverifiedTransactionWrapper::Arbitrum(_) => U256::zero(),
+            UnverifiedTransactionWrapper::Custom(tx) => tx.inner().max_priority_fee_per_gas(),
         }
     }
 
-    /// Add eip155 fix to signature v, for legacy tx
-    pub fn standard_v(&self) -> u8 {
+    pub fn access_list(&self) -> Option<&AccessList> {
         match self {
-            UnverifiedTransactionWrapper::Legacy(tx) => tx.standard_v(),
+            UnverifiedTransactionWrapper::Eip2930(tx) => Some(tx.access_list()),
+            UnverifiedTransactionWrapper::Eip1559(tx) => Some(tx.access_list()),
+            UnverifiedTransactionWrapper::Eip4844(tx) => Some(tx.access_list()),
+            UnverifiedTransactionWrapper::Eip7702(tx) => Some(tx.access_list()),
+            UnverifiedTransactionWrapper::Cip64(tx) => Some(tx.access_list()),
+            UnverifiedTransactionWrapper::Custom(tx) => tx.inner().access_list(),
+            UnverifiedTransactionWrapper::Legacy(_)
+            | UnverifiedTransactionWrapper::ZkSyncEip712(_)
+            | UnverifiedTransactionWrapper::Deposit(_)
+            | UnverifiedTransactionWrapper::Arbitrum(_) => None,
+        }
+    }
+
+    /// Gas price paid by the transaction in a block with `base_fee`, the max fee if `base_fee` is unknown.
+    pub fn effective_gas_price(&self, base_fee: Option<U256>) -> U256 {
+        effective_gas_price(self.max_fee_per_gas(), self.max_priority_fee_per_gas(), base_fee)
+    }
+
+    /// Add eip155 fix to signature v, for legacy tx.
+    /// Fails if a legacy tx v is neither 27/28 nor a valid EIP-155 value.
+    pub fn standard_v(&self) -> Result<u8, ethkey::Error> {
+        Ok(match self {
+            UnverifiedTransactionWrapper::Legacy(tx) => tx.standard_v()?,
             UnverifiedTransactionWrapper::Eip2930(tx) => tx.standard_v(),
             UnverifiedTransactionWrapper::Eip1559(tx) => tx.standard_v(),
-        }
+            UnverifiedTransactionWrapper::Eip4844(tx) => tx.standard_v(),
+            UnverifiedTransactionWrapper::Eip7702(tx) => tx.standard_v(),
+            UnverifiedTransactionWrapper::Cip64(tx) => tx.standard_v(),
+            UnverifiedTransactionWrapper::ZkSyncEip712(tx) => tx.standard_v(),
+            UnverifiedTransactionWrapper::Deposit(_) | UnverifiedTransactionWrapper::Arbitrum(_) => 0,
+            UnverifiedTransactionWrapper::Custom(tx) => match tx.sender_policy() {
+                SenderPolicy::Signature { v, .. } => v,
+                SenderPolicy::Implied(_) => 0,
+            },
+        })
     }
 
     /// The chain ID, or `None` if this is a global transaction.
     pub fn chain_id_from_v(&self) -> Option<u64> {
         match self.v() {
-            v if self.is_unsigned() => Some(v), // v is chain_id for null signer by eip-86 (TODO: is this supported?)
-            v if v > 36 => Some((v - 35) / 2),  // encoded by eip-155
-            _ => None,
+            _ if self.implied_sender().is_some() => None,
+            // v is chain_id for null signer by eip-86 (TODO: is this supported?)
+            v if self.is_unsigned() => u64::try_from(v).ok(),
+            v => eip155_methods::chain_id_from_v(v), // encoded by eip-155
         }
     }
 
     /// Construct a signature object from the sig.
-    pub fn signature(&self) -> Signature {
+    pub fn signature(&self) -> Result<Signature, ethkey::Error> {
         let r = h256_from_u256(self.r());
         let s = h256_from_u256(self.s());
-        Signature::from_rsv(&r, &s, self.standard_v())
+        Ok(Signature::from_rsv(&r, &s, self.standard_v()?))
     }
 
     /// Checks whether the signature has a low 's' value.
     pub fn check_low_s(&self) -> Result<(), ethkey::Error> {
-        if !self.signature().is_low_s() {
+        if !self.signature()?.is_low_s() {
             Err(ethkey::Error::InvalidSignature)
         } else {
             Ok(())
         }
     }
 
+    /// Sender of a transaction type which is not signed but carries its sender explicitly.
+    pub fn implied_sender(&self) -> Option<Address> {
+        match self {
+            UnverifiedTransactionWrapper::Deposit(tx) => Some(tx.from()),
+            UnverifiedTransactionWrapper::Arbitrum(tx) => Some(tx.sender()),
+            UnverifiedTransactionWrapper::Custom(tx) => match tx.sender_policy() {
+                SenderPolicy::Implied(sender) => Some(sender),
+                SenderPolicy::Signature { .. } => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Sender of a zkSync transaction with a custom signature. The signature is checked by the
+    /// account contract on execution, so the sender is claimed and not authenticated: such a
+    /// transaction can't make a `SignedTransaction`, it is trusted once included in a block only.
+    pub fn unauthenticated_sender(&self) -> Option<Address> {
+        match self {
+            UnverifiedTransactionWrapper::ZkSyncEip712(tx) if tx.has_custom_signature() => Some(tx.from()),
+            _ => None,
+        }
+    }
+
     /// Checks is signature is empty.
     pub(crate) fn is_unsigned(&self) -> bool { self.r().is_zero() && self.s().is_zero() }
 
     /// Recovers the public key of the sender.
     pub fn recover_public(&self) -> Result<Public, ethkey::Error> {
-        recover(&self.signature(), &self.unsigned().message_hash(self.chain_id_from_v()))
+        recover(
+            &self.signature()?,
+            &self.unsigned().message_hash(self.chain_id_from_v()),
+        )
     }
 
     /// Verify basic signature params. Does not attempt sender recovery.
@@ -332,6 +789,15 @@ impl UnverifiedTransactionWrapper {
         chain_id: Option<u64>,
         allow_empty_signature: bool,
     ) -> Result<(), error::Error> {
+        // no signature to check, the sender is set by the protocol
+        if self.implied_sender().is_some() {
+            return Ok(());
+        }
+        if self.unauthenticated_sender().is_some() {
+            return Err(error::Error::InvalidSignature(
+                "custom signature is checked by the account contract".into(),
+            ));
+        }
         if check_low_s && !(allow_empty_signature && self.is_unsigned()) {
             self.check_low_s()?;
         }
@@ -343,6 +809,10 @@ impl UnverifiedTransactionWrapper {
         if allow_empty_signature && self.is_unsigned() && self.validate_empty_sig() {
             return Err(ethkey::Error::InvalidSignature.into());
         }
+        // v with a chain id not fitting into u64 must not pass as a global tx
+        if !self.is_unsigned() {
+            self.standard_v()?;
+        }
         match (self.chain_id_from_v(), chain_id) {
             (None, _) => {},
             (Some(n), Some(m)) if n == m => {},
@@ -357,25 +827,55 @@ impl UnverifiedTransactionWrapper {
         false
     }
 
-    fn r(&self) -> U256 {
+    /// The R field of the signature, zero for transactions without one.
+    pub fn r(&self) -> U256 {
         match self {
             UnverifiedTransactionWrapper::Legacy(tx) => tx.r(),
             UnverifiedTransactionWrapper::Eip2930(tx) => tx.r(),
             UnverifiedTransactionWrapper::Eip1559(tx) => tx.r(),
+            UnverifiedTransactionWrapper::Eip4844(tx) => tx.r(),
+            UnverifiedTransactionWrapper::Eip7702(tx) => tx.r(),
+            UnverifiedTransactionWrapper::Cip64(tx) => tx.r(),
+            UnverifiedTransactionWrapper::ZkSyncEip712(tx) => tx.r(),
+            UnverifiedTransactionWrapper::Deposit(_) | UnverifiedTransactionWrapper::Arbitrum(_) => U256::zero(),
+            UnverifiedTransactionWrapper::Custom(tx) => match tx.sender_policy() {
+                SenderPolicy::Signature { r, .. } => r,
+                SenderPolicy::Implied(_) => U256::zero(),
+            },
         }
     }
-    fn s(&self) -> U256 {
+    /// The S field of the signature, zero for transactions without one.
+    pub fn s(&self) -> U256 {
         match self {
             UnverifiedTransactionWrapper::Legacy(tx) => tx.s(),
             UnverifiedTransactionWrapper::Eip2930(tx) => tx.s(),
             UnverifiedTransactionWrapper::Eip1559(tx) => tx.s(),
+            UnverifiedTransactionWrapper::Eip4844(tx) => tx.s(),
+            UnverifiedTransactionWrapper::Eip7702(tx) => tx.s(),
+            UnverifiedTransactionWrapper::Cip64(tx) => tx.s(),
+            UnverifiedTransactionWrapper::ZkSyncEip712(tx) => tx.s(),
+            UnverifiedTransactionWrapper::Deposit(_) | UnverifiedTransactionWrapper::Arbitrum(_) => U256::zero(),
+            UnverifiedTransactionWrapper::Custom(tx) => match tx.sender_policy() {
+                SenderPolicy::Signature { s, .. } => s,
+                SenderPolicy::Implied(_) => U256::zero(),
+            },
         }
     }
-    fn v(&self) -> u64 {
+    /// The V field of the signature as encoded, EIP-155 chain id included for legacy transactions.
+    pub fn v(&self) -> U256 {
         match self {
             UnverifiedTransactionWrapper::Legacy(tx) => tx.v(),
-            UnverifiedTransactionWrapper::Eip2930(tx) => tx.v(),
-            UnverifiedTransactionWrapper::Eip1559(tx) => tx.v(),
+            UnverifiedTransactionWrapper::Eip2930(tx) => tx.v().into(),
+            UnverifiedTransactionWrapper::Eip1559(tx) => tx.v().into(),
+            UnverifiedTransactionWrapper::Eip4844(tx) => tx.v().into(),
+            UnverifiedTransactionWrapper::Eip7702(tx) => tx.v().into(),
+            UnverifiedTransactionWrapper::Cip64(tx) => tx.v().into(),
+            UnverifiedTransactionWrapper::ZkSyncEip712(tx) => tx.v().into(),
+            UnverifiedTransactionWrapper::Deposit(_) | UnverifiedTransactionWrapper::Arbitrum(_) => U256::zero(),
+            UnverifiedTransactionWrapper::Custom(tx) => match tx.sender_policy() {
+                SenderPolicy::Signature { v, .. } => v.into(),
+                SenderPolicy::Implied(_) => U256::zero(),
+            },
         }
     }
 
@@ -385,6 +885,13 @@ impl UnverifiedTransactionWrapper {
             UnverifiedTransactionWrapper::Legacy(tx) => tx.hash(),
             UnverifiedTransactionWrapper::Eip2930(tx) => tx.hash(),
             UnverifiedTransactionWrapper::Eip1559(tx) => tx.hash(),
+            UnverifiedTransactionWrapper::Eip4844(tx) => tx.hash(),
+            UnverifiedTransactionWrapper::Eip7702(tx) => tx.hash(),
+            UnverifiedTransactionWrapper::Cip64(tx) => tx.hash(),
+            UnverifiedTransactionWrapper::ZkSyncEip712(tx) => tx.hash(),
+            UnverifiedTransactionWrapper::Deposit(tx) => tx.hash(),
+            UnverifiedTransactionWrapper::Arbitrum(tx) => tx.hash(),
+            UnverifiedTransactionWrapper::Custom(tx) => tx.hash(),
         }
     }
 }
@@ -401,6 +908,13 @@ impl rlp::Encodable for SignedTransaction {
     fn rlp_append(&self, s: &mut RlpStream) { self.transaction.rlp_append_sealed_transaction(s) }
 }
 
+/// Decodes either form `UnverifiedTransactionWrapper` accepts and recovers the sender.
+impl rlp::Decodable for SignedTransaction {
+    fn decode(d: &Rlp) -> Result<Self, DecoderError> {
+        SignedTransaction::new(d.as_val()?).map_err(|_| DecoderError::Custom("invalid signature"))
+    }
+}
+
 impl Deref for SignedTransaction {
     type Target = UnverifiedTransactionWrapper;
     fn deref(&self) -> &Self::Target { &self.transaction }
@@ -413,7 +927,15 @@ impl From<SignedTransaction> for UnverifiedTransactionWrapper {
 impl SignedTransaction {
     /// Try to verify transaction and recover sender.
     pub fn new(transaction: UnverifiedTransactionWrapper) -> Result<Self, ethkey::Error> {
-        if transaction.is_unsigned() {
+        if let Some(sender) = transaction.implied_sender() {
+            Ok(SignedTransaction {
+                transaction,
+                sender,
+                public: None,
+            })
+        } else if transaction.unauthenticated_sender().is_some() {
+            Err(ethkey::Error::InvalidSignature)
+        } else if transaction.is_unsigned() {
             Ok(SignedTransaction {
                 transaction,
                 sender: UNSIGNED_SENDER,
@@ -422,6 +944,11 @@ impl SignedTransaction {
         } else {
             let public = transaction.recover_public()?;
             let sender = public_to_address(&public);
+            if let UnverifiedTransactionWrapper::ZkSyncEip712(tx) = &transaction {
+                if tx.from() != sender {
+                    return Err(ethkey::Error::InvalidSignature);
+                }
+            }
             Ok(SignedTransaction {
                 transaction,
                 sender,
@@ -435,6 +962,13 @@ impl SignedTransaction {
             UnverifiedTransactionWrapper::Legacy(tx) => tx.deref() as &TransactionSharedRet,
             UnverifiedTransactionWrapper::Eip2930(tx) => tx.deref() as &TransactionSharedRet,
             UnverifiedTransactionWrapper::Eip1559(tx) => tx.deref() as &TransactionSharedRet,
+            UnverifiedTransactionWrapper::Eip4844(tx) => tx.deref() as &TransactionSharedRet,
+            UnverifiedTransactionWrapper::Eip7702(tx) => tx.deref() as &TransactionSharedRet,
+            UnverifiedTransactionWrapper::Cip64(tx) => tx.deref() as &TransactionSharedRet,
+            UnverifiedTransactionWrapper::ZkSyncEip712(tx) => tx.deref() as &TransactionSharedRet,
+            UnverifiedTransactionWrapper::Deposit(tx) => tx as &TransactionSharedRet,
+            UnverifiedTransactionWrapper::Arbitrum(tx) => tx as &TransactionSharedRet,
+            UnverifiedTransactionWrapper::Custom(tx) => tx as &TransactionSharedRet,
         }
     }
 
@@ -475,6 +1009,13 @@ impl LocalizedTransaction {
         if let Some(sender) = self.cached_sender {
             return sender;
         }
+        if let Some(sender) = self.implied_sender() {
+            return sender;
+        }
+        // the account contract accepted the custom signature for the block to include it
+        if let Some(sender) = self.unauthenticated_sender() {
+            return sender;
+        }
         if self.is_unsigned() {
             return UNSIGNED_SENDER;
         }
@@ -525,6 +1066,38 @@ impl From<SignedTransaction> for PendingTransaction {
     }
 }
 
+/// Transactions list of a block body.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockTransactions(pub Vec<UnverifiedTransactionWrapper>);
+
+impl rlp::Decodable for BlockTransactions {
+    fn decode(d: &Rlp) -> Result<Self, DecoderError> {
+        if !d.is_list() {
+            return Err(DecoderError::RlpExpectedToBeList);
+        }
+        Ok(BlockTransactions(
+            d.iter().map(|tx| tx.as_val()).collect::<Result<_, _>>()?,
+        ))
+    }
+}
+
+impl rlp::Encodable for BlockTransactions {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(self.0.len());
+        for tx in self.0.iter() {
+            tx.rlp_append_block_item(s);
+        }
+    }
+}
+
+/// Priority fee is capped by `max_fee_per_gas - base_fee`, for legacy transactions both fees are the gas price.
+fn effective_gas_price(max_fee_per_gas: U256, max_priority_fee_per_gas: U256, base_fee: Option<U256>) -> U256 {
+    match base_fee {
+        Some(base_fee) => max_fee_per_gas.min(base_fee.saturating_add(max_priority_fee_per_gas)),
+        None => max_fee_per_gas,
+    }
+}
+
 /// Reproduces the same conversion as it was in the previous `ethereum-types-0.4` version:
 /// https://docs.rs/ethereum-types/0.4.0/src/ethereum_types/hash.rs.html#32-38
 fn h256_from_u256(num: U256) -> H256 {
@@ -533,16 +1106,139 @@ fn h256_from_u256(num: U256) -> H256 {
     H256::from(bytes)
 }
 
+/// Fields list of an encoded transaction, reporting the failed field with its location.
+pub(crate) struct TxFields<'a> {
+    tx_type: Option<u8>,
+    /// Encoded transaction, the envelope of a typed one
+    raw: &'a [u8],
+    list: Rlp<'a>,
+    /// Offset of the list in the encoded transaction
+    list_offset: usize,
+}
+
+impl<'a> TxFields<'a> {
+    /// Fields of a legacy transaction, or of a list nested into a transaction.
+    pub(crate) fn new(list: Rlp<'a>, tx_type: Option<u8>, list_offset: usize) -> Self {
+        TxFields {
+            tx_type,
+            raw: list.as_raw(),
+            list,
+            list_offset,
+        }
+    }
+
+    /// Fields of a typed transaction envelope, which type byte must be `tx_type`.
+    pub(crate) fn typed(d: &Rlp<'a>, tx_type: TxType) -> Result<Self, TxDecodeError> {
+        let fields = TxFields::envelope(d)?;
+        if fields.tx_type != Some(tx_type as u8) {
+            return Err(fields.envelope_error(DecoderError::Custom("bad tx version")));
+        }
+        Ok(fields)
+    }
+
+    /// Fields of a typed transaction envelope of any type, raw or wrapped into a byte string.
+    pub(crate) fn envelope(d: &Rlp<'a>) -> Result<Self, TxDecodeError> {
+        let raw = if is_wrapped_typed_transaction(d) {
+            d.data().map_err(TxDecodeError::new)?
+        } else {
+            d.as_raw()
+        };
+        if raw.len() < 2 {
+            let err = TxDecodeError::new(DecoderError::RlpIsTooShort);
+            return Err(match raw.first() {
+                Some(tx_type) => err.with_tx_type(*tx_type),
+                None => err,
+            });
+        }
+        Ok(TxFields {
+            raw,
+            ..TxFields::new(Rlp::new(&raw[1..]), Some(raw[0]), 1)
+        })
+    }
+
+    /// Hash of the encoded transaction.
+    pub(crate) fn hash(&self) -> H256 { keccak(self.raw) }
+
+    pub(crate) fn tx_type(&self) -> Option<u8> { self.tx_type }
+
+    pub(crate) fn item_count(&self) -> Result<usize, TxDecodeError> {
+        self.list.item_count().map_err(|e| self.envelope_error(e))
+    }
+
+    /// Field at `index` with its offset in the encoded transaction.
+    pub(crate) fn at(&self, index: usize, field: &'static str) -> Result<(Rlp<'a>, usize), TxDecodeError> {
+        self.list
+            .at_with_offset(index)
+            .map(|(item, offset)| (item, self.list_offset + offset))
+            .map_err(|e| self.error(index, field, e))
+    }
+
+    pub(crate) fn val_at<T: rlp::Decodable>(&self, index: usize, field: &'static str) -> Result<T, TxDecodeError> {
+        self.at(index, field)?
+            .0
+            .as_val()
+            .map_err(|e| self.error(index, field, e))
+    }
+
+    pub(crate) fn list_at<T: rlp::Decodable>(
+        &self,
+        index: usize,
+        field: &'static str,
+    ) -> Result<Vec<T>, TxDecodeError> {
+        self.at(index, field)?
+            .0
+            .as_list()
+            .map_err(|e| self.error(index, field, e))
+    }
+
+    /// Fields of the list at `index`.
+    pub(crate) fn nested(&self, index: usize, field: &'static str) -> Result<TxFields<'a>, TxDecodeError> {
+        let (list, offset) = self.at(index, field)?;
+        Ok(TxFields::new(list, self.tx_type, offset))
+    }
+
+    /// Failure of the field at `index`.
+    pub(crate) fn error(&self, index: usize, field: &'static str, rlp: DecoderError) -> TxDecodeError {
+        TxDecodeError {
+            tx_type: self.tx_type,
+            field: Some(field),
+            index: Some(index),
+            offset: self
+                .list
+                .at_with_offset(index)
+                .ok()
+                .map(|(_, offset)| self.list_offset + offset),
+            rlp,
+        }
+    }
+
+    /// Failure of the whole transaction.
+    pub(crate) fn envelope_error(&self, rlp: DecoderError) -> TxDecodeError {
+        TxDecodeError {
+            tx_type: self.tx_type,
+            field: None,
+            index: None,
+            offset: None,
+            rlp,
+        }
+    }
+}
+
 /// Returns true if serialized tx has type as in eip-2718
 fn is_typed_transaction(d: &Rlp) -> bool { !d.is_list() && !d.as_raw().is_empty() && d.as_raw()[0] < 0x7f }
 
+/// Returns true if serialized tx is a byte string, which wraps a typed tx in a block body
+fn is_wrapped_typed_transaction(d: &Rlp) -> bool { d.is_data() && !d.as_raw().is_empty() && d.as_raw()[0] >= 0x80 }
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{BanListError, ChainSpecError, FillError, LeaseExpired};
     use ethereum_types::U256;
     use ethkey::KeyPair;
     use hash::keccak;
     use std::str::FromStr;
+    use unexpected::OutOfBounds;
 
     #[test]
     fn legacy_sender_test() {
@@ -628,6 +1324,54 @@ mod tests {
         assert_eq!(t.chain_id_from_v(), Some(69));
     }
 
+    #[test]
+    fn legacy_large_chain_id() {
+        let key = KeyPair::from_secret_slice(&[
+            128, 148, 101, 177, 125, 10, 77, 219, 62, 76, 105, 232, 242, 60, 44, 171, 173, 134, 143, 81, 248, 190, 213,
+            199, 101, 173, 29, 101, 22, 195, 48, 111,
+        ])
+        .unwrap();
+        let unsigned = LegacyTransaction {
+            action: Action::Create,
+            nonce: U256::from(42),
+            gas_price: U256::from(3000),
+            gas: U256::from(50_000),
+            value: U256::from(1),
+            data: b"Hello!".to_vec(),
+        };
+        let t = TransactionWrapper::Legacy(unsigned.clone())
+            .sign(key.secret(), Some(u64::MAX))
+            .expect("sign transaction okay");
+        assert_eq!(Address::from(keccak(key.public())), t.sender());
+        assert_eq!(t.chain_id_from_v(), Some(u64::MAX));
+        assert!(t.v() > U256::from(u64::MAX));
+        assert!(t.verify_basic(true, Some(u64::MAX), false).is_ok());
+
+        let decoded: UnverifiedTransactionWrapper = rlp::decode(&rlp::encode(&t.transaction)).unwrap();
+        assert_eq!(decoded.chain_id_from_v(), Some(u64::MAX));
+        assert_eq!(SignedTransaction::new(decoded).unwrap().sender(), t.sender());
+
+        // neither 27/28 nor eip-155, or eip-155 with a chain id above u64::MAX
+        let too_large_v = (U256::from(u64::MAX) + 1) * 2 + 35;
+        for v in [U256::zero(), U256::from(30), too_large_v] {
+            let invalid = UnverifiedTransactionWrapper::Legacy(
+                UnverifiedLegacyTransaction::new_with_network_v(unsigned.clone(), t.r(), t.s(), v, H256::zero())
+                    .unwrap(),
+            )
+            .compute_hash();
+            assert!(matches!(invalid.standard_v(), Err(ethkey::Error::InvalidSignature)));
+            assert_eq!(invalid.chain_id_from_v(), None);
+            assert_eq!(
+                invalid.verify_basic(true, None, false),
+                Err(Error::from(ethkey::Error::InvalidSignature))
+            );
+            assert!(matches!(
+                SignedTransaction::new(invalid),
+                Err(ethkey::Error::InvalidSignature)
+            ));
+        }
+    }
+
     #[test]
     fn legacy_should_agree_with_vitalik() {
         use rustc_hex::FromHex;
@@ -710,4 +1454,2332 @@ mod tests {
 		"0xae2Fc483527B8EF99EB5D9B44875F005ba1FaE13",
 		"0x256c91a7934b7584c1f8f28a6b3b8cacf13419637532896fc1e1119aa7fa32ba");
     }
+
+    #[test]
+    fn wrapper_fee_accessors() {
+        let key = KeyPair::from_secret_slice(&[
+            128, 148, 101, 177, 125, 10, 77, 219, 62, 76, 105, 232, 242, 60, 44, 171, 173, 134, 143, 81, 248, 190, 213,
+            199, 101, 173, 29, 101, 22, 195, 48, 111,
+        ])
+        .unwrap();
+        let access_list = AccessList(vec![AccessListItem {
+            address: Address::repeat_byte(0x11),
+            storage_keys: vec![H256::repeat_byte(0x22)],
+        }]);
+        let builder = |tx_type| {
+            TransactionWrapperBuilder::new(
+                tx_type,
+                U256::from(42),
+                U256::from(50_000),
+                Action::Call(Address::repeat_byte(0x33)),
+                U256::from(1),
+                vec![],
+            )
+        };
+
+        let legacy = builder(TxType::Legacy).with_gas_price(100.into()).build().unwrap();
+        assert_eq!(legacy.tx_type(), TxType::Legacy);
+        assert_eq!(legacy.chain_id(), None);
+        assert_eq!(legacy.access_list(), None);
+        assert_eq!(legacy.max_priority_fee_per_gas(), 100.into());
+        assert_eq!(legacy.effective_gas_price(Some(30.into())), 100.into());
+        let signed = legacy.sign(key.secret(), Some(69)).unwrap();
+        assert_eq!(signed.tx_type(), Some(TxType::Legacy));
+        assert_eq!(signed.chain_id(), Some(69));
+        assert_eq!(signed.gas_price(), 100.into());
+        assert_eq!(signed.v(), U256::from(69 * 2 + 35) + signed.standard_v().unwrap());
+        assert!(!signed.r().is_zero() && !signed.s().is_zero());
+
+        let eip2930 = builder(TxType::Type1)
+            .with_chain_id(1)
+            .with_gas_price(100.into())
+            .with_access_list(access_list.clone())
+            .build()
+            .unwrap();
+        let signed = eip2930.sign(key.secret(), None).unwrap();
+        assert_eq!(signed.tx_type(), Some(TxType::Type1));
+        assert_eq!(signed.chain_id(), Some(1));
+        assert_eq!(signed.access_list(), Some(&access_list));
+        assert_eq!(signed.max_fee_per_gas(), 100.into());
+        assert_eq!(signed.effective_gas_price(None), 100.into());
+
+        let eip1559 = builder(TxType::Type2)
+            .with_chain_id(1)
+            .with_priority_fee_per_gas(100.into(), 10.into())
+            .build()
+            .unwrap();
+        assert_eq!(eip1559.tx_type(), TxType::Type2);
+        assert_eq!(eip1559.chain_id(), Some(1));
+        assert_eq!(eip1559.access_list(), Some(&AccessList::default()));
+        let signed = eip1559.sign(key.secret(), None).unwrap();
+        assert_eq!(signed.gas_price(), 100.into());
+        assert_eq!(signed.max_fee_per_gas(), 100.into());
+        assert_eq!(signed.max_priority_fee_per_gas(), 10.into());
+        assert_eq!(signed.effective_gas_price(None), 100.into());
+        assert_eq!(signed.effective_gas_price(Some(30.into())), 40.into());
+        // priority fee is capped by max fee
+        assert_eq!(signed.effective_gas_price(Some(95.into())), 100.into());
+    }
+
+    #[test]
+    fn intrinsic_gas_by_fork() {
+        let call = Action::Call(Address::repeat_byte(0x33));
+        assert_eq!(intrinsic_gas(&[], &call, None, 0, Fork::Frontier), 21_000);
+        assert_eq!(intrinsic_gas(&[], &call, None, 0, Fork::LATEST), 21_000);
+
+        let initcode = [vec![0u8; 10], vec![1u8; 10]].concat();
+        assert_eq!(
+            intrinsic_gas(&initcode, &Action::Create, None, 0, Fork::Frontier),
+            21_720
+        );
+        assert_eq!(
+            intrinsic_gas(&initcode, &Action::Create, None, 0, Fork::Homestead),
+            53_720
+        );
+        assert_eq!(
+            intrinsic_gas(&initcode, &Action::Create, None, 0, Fork::Istanbul),
+            53_200
+        );
+        // one initcode word
+        assert_eq!(
+            intrinsic_gas(&initcode, &Action::Create, None, 0, Fork::Shanghai),
+            53_202
+        );
+        assert_eq!(intrinsic_gas(&initcode, &Action::Create, None, 0, Fork::Prague), 53_202);
+
+        let access_list = AccessList(vec![AccessListItem {
+            address: Address::repeat_byte(0x11),
+            storage_keys: vec![H256::repeat_byte(0x22), H256::repeat_byte(0x23)],
+        }]);
+        assert_eq!(intrinsic_gas(&[], &call, Some(&access_list), 0, Fork::Istanbul), 21_000);
+        assert_eq!(intrinsic_gas(&[], &call, Some(&access_list), 0, Fork::Berlin), 27_200);
+        assert_eq!(intrinsic_gas(&[], &call, None, 2, Fork::Cancun), 21_000);
+        assert_eq!(intrinsic_gas(&[], &call, None, 2, Fork::Prague), 71_000);
+
+        // calldata floor of eip-7623 exceeds the standard cost
+        let calldata = vec![1u8; 1000];
+        assert_eq!(intrinsic_gas(&calldata, &call, None, 0, Fork::Cancun), 37_000);
+        assert_eq!(intrinsic_gas(&calldata, &call, None, 0, Fork::Prague), 61_000);
+
+        let builder = || {
+            TransactionWrapperBuilder::new(
+                TxType::Type2,
+                U256::zero(),
+                U256::from(50_000),
+                call.clone(),
+                U256::zero(),
+                calldata.clone(),
+            )
+            .with_chain_id(1)
+            .with_priority_fee_per_gas(100.into(), 10.into())
+        };
+        let tx = builder().with_fork(Fork::Cancun).build().unwrap();
+        assert_eq!(tx.intrinsic_gas(Fork::Cancun), 37_000);
+        assert_eq!(tx.check_intrinsic_gas(Fork::Cancun), Ok(()));
+        assert_eq!(
+            tx.check_intrinsic_gas(Fork::Prague),
+            Err(Error::InsufficientGas {
+                minimal: 61_000.into(),
+                got: 50_000.into(),
+            })
+        );
+        assert_eq!(
+            builder().with_fork(Fork::Prague).build(),
+            Err(tx_builders::TxBuilderError::InsufficientGas {
+                minimal: 61_000.into(),
+                got: 50_000.into(),
+            })
+        );
+    }
+
+    #[test]
+    fn chain_spec_fork_schedule() {
+        let mainnet = ChainSpec::mainnet();
+        assert_eq!(mainnet.fork_at(0, 0), Fork::Frontier);
+        assert_eq!(mainnet.fork_at(12_964_999, 0), Fork::Berlin);
+        assert_eq!(mainnet.fork_at(15_537_394, 1_681_338_454), Fork::Paris);
+        assert_eq!(mainnet.fork_at(15_537_394, 1_681_338_455), Fork::Shanghai);
+        assert!(mainnet.is_active(Fork::Frontier, 0, 0));
+        assert!(!mainnet.is_active(Fork::London, 12_964_999, u64::MAX));
+
+        let berlin = mainnet.rules_at(12_244_000, 0);
+        assert!(berlin.allows_tx_type(TxType::Type1));
+        assert!(!berlin.allows_tx_type(TxType::Type2));
+        let latest = mainnet.rules_at(u64::MAX, u64::MAX);
+        assert_eq!(latest.fork, Fork::Osaka);
+        assert!((0..=4).all(|t| latest.allows_type_byte(t)));
+        assert!(!latest.allows_tx_type(TxType::Deposit));
+        let celo = ChainSpec::new("celo", 42_220)
+            .with_extra_tx_type(TxType::Cip64 as u8)
+            .rules_at(0, 0);
+        assert!(celo.allows_tx_type(TxType::Cip64));
+        assert!(celo.allows_type_byte(0x7b));
+        assert!(!celo.allows_tx_type(TxType::Deposit));
+        assert_eq!(latest.max_initcode_size(), Some(MAX_INITCODE_SIZE));
+        assert_eq!(latest.max_tx_gas(), Some(MAX_TX_GAS));
+
+        let polygon = ChainSpec::from_chain_id(137).unwrap().rules_at(u64::MAX, u64::MAX);
+        assert_eq!(polygon.fork, Fork::Prague);
+        assert!(!polygon.allows_tx_type(TxType::Type3));
+        assert!(polygon.allows_tx_type(TxType::Type4));
+        assert_eq!(ChainSpec::avalanche().fork_at(0, 1_734_368_400), Fork::Cancun);
+        assert_eq!(ChainSpec::from_chain_id(97).unwrap().name, "chapel");
+        assert_eq!(ChainSpec::from_chain_id(123_456), None);
+    }
+
+    #[test]
+    fn chain_rules_verify_basic() {
+        let key = KeyPair::from_secret_slice(&[
+            128, 148, 101, 177, 125, 10, 77, 219, 62, 76, 105, 232, 242, 60, 44, 171, 173, 134, 143, 81, 248, 190, 213,
+            199, 101, 173, 29, 101, 22, 195, 48, 111,
+        ])
+        .unwrap();
+        let mainnet = ChainSpec::mainnet();
+        let latest = mainnet.rules_at(u64::MAX, u64::MAX);
+        let tx = |tx_type, gas: u64, action, data| {
+            TransactionWrapperBuilder::new(tx_type, U256::zero(), gas.into(), action, U256::zero(), data)
+                .with_chain_id(1)
+                .with_gas_price(100.into())
+                .with_priority_fee_per_gas(100.into(), 10.into())
+                .build()
+                .unwrap()
+        };
+        let call = Action::Call(Address::repeat_byte(0x33));
+
+        let legacy = tx(TxType::Legacy, 21_000, call.clone(), vec![]);
+        let protected = legacy.clone().sign(key.secret(), Some(1)).unwrap();
+        assert_eq!(latest.verify_basic(&protected), Ok(()));
+        // eip-155 is not active yet
+        assert_eq!(
+            mainnet.rules_at(1, 0).verify_basic(&protected),
+            Err(Error::InvalidChainId)
+        );
+        let unprotected = legacy.sign(key.secret(), None).unwrap();
+        assert_eq!(latest.verify_basic(&unprotected), Err(Error::InvalidChainId));
+        let mut relaxed = mainnet.clone();
+        relaxed.eip155_required = false;
+        assert_eq!(relaxed.rules_at(u64::MAX, u64::MAX).verify_basic(&unprotected), Ok(()));
+
+        let eip1559 = tx(TxType::Type2, 21_000, call.clone(), vec![])
+            .sign(key.secret(), None)
+            .unwrap();
+        assert_eq!(latest.verify_basic(&eip1559), Ok(()));
+        assert_eq!(
+            mainnet.rules_at(12_244_000, 0).verify_basic(&eip1559),
+            Err(Error::TxTypeNotEnabled(2))
+        );
+        assert_eq!(
+            ChainSpec::bsc().rules_at(u64::MAX, u64::MAX).verify_basic(&eip1559),
+            Err(Error::InvalidChainId)
+        );
+
+        let create = tx(TxType::Type2, 21_000, Action::Create, vec![1; 10])
+            .sign(key.secret(), None)
+            .unwrap();
+        assert_eq!(
+            latest.verify_basic(&create),
+            Err(Error::InsufficientGas {
+                minimal: 53_162.into(),
+                got: 21_000.into(),
+            })
+        );
+        let initcode = vec![1; MAX_INITCODE_SIZE + 1];
+        let create = tx(TxType::Type2, 10_000_000, Action::Create, initcode)
+            .sign(key.secret(), None)
+            .unwrap();
+        assert_eq!(
+            latest.verify_basic(&create),
+            Err(Error::InitcodeTooLarge {
+                limit: MAX_INITCODE_SIZE,
+                got: MAX_INITCODE_SIZE + 1,
+            })
+        );
+        let too_much_gas = tx(TxType::Type2, MAX_TX_GAS + 1, call, vec![])
+            .sign(key.secret(), None)
+            .unwrap();
+        assert_eq!(
+            latest.verify_basic(&too_much_gas),
+            Err(Error::GasLimitExceeded {
+                limit: MAX_TX_GAS.into(),
+                got: (MAX_TX_GAS + 1).into(),
+            })
+        );
+        assert_eq!(
+            mainnet.rules_at(u64::MAX, 1_764_798_550).verify_basic(&too_much_gas),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn chain_spec_from_geth_genesis() {
+        let genesis = r#"{
+            "config": {
+                "chainId": 11155111,
+                "homesteadBlock": 0,
+                "eip150Block": 0,
+                "eip155Block": 0,
+                "eip158Block": 0,
+                "byzantiumBlock": 0,
+                "constantinopleBlock": 0,
+                "petersburgBlock": 0,
+                "istanbulBlock": 0,
+                "muirGlacierBlock": 0,
+                "berlinBlock": 0,
+                "londonBlock": 0,
+                "mergeNetsplitBlock": 1735371,
+                "shanghaiTime": 1677557088,
+                "cancunTime": 1706655072,
+                "pragueTime": 1741159776,
+                "osakaTime": null,
+                "terminalTotalDifficulty": 17000000000000000,
+                "blobSchedule": {
+                    "cancun": { "target": 3, "max": 6, "baseFeeUpdateFraction": 3338477 },
+                    "prague": { "target": 6, "max": 9, "baseFeeUpdateFraction": 5007716 }
+                }
+            },
+            "baseFeePerGas": "0x3b9aca00",
+            "gasLimit": "0x1c9c380"
+        }"#;
+        let spec = ChainSpec::from_geth_genesis(genesis).unwrap();
+        assert_eq!(spec.name, "sepolia");
+        assert_eq!(spec.chain_id, 11_155_111);
+        assert_eq!(spec.forks.get(&Fork::Paris), Some(&ForkActivation::Block(1_735_371)));
+        assert_eq!(spec.fork_at(u64::MAX, 1_741_159_776), Fork::Prague);
+        assert!(!spec.forks.contains_key(&Fork::Osaka));
+        assert_eq!(spec.fee_params.initial_base_fee, INITIAL_BASE_FEE.into());
+        assert_eq!(spec.fee_params.blob_update_fraction(Fork::London), None);
+        assert_eq!(
+            spec.fee_params.blob_update_fraction(Fork::Osaka),
+            Some(BLOB_BASE_FEE_UPDATE_FRACTION_PRAGUE)
+        );
+
+        let bor = r#"{ "config": { "chainId": 80002, "londonBlock": 73100, "shanghaiBlock": 73100, "bor": {} } }"#;
+        let spec = ChainSpec::from_geth_genesis(bor).unwrap();
+        assert_eq!(spec.forks.get(&Fork::Shanghai), Some(&ForkActivation::Block(73_100)));
+        assert!(!spec.blob_transactions);
+        assert!(spec.extra_tx_types.is_empty());
+
+        // OP Mainnet config of op-geth
+        let op = r#"{
+            "config": {
+                "chainId": 10,
+                "homesteadBlock": 0,
+                "eip150Block": 0,
+                "eip155Block": 0,
+                "eip158Block": 0,
+                "byzantiumBlock": 0,
+                "constantinopleBlock": 0,
+                "petersburgBlock": 0,
+                "istanbulBlock": 0,
+                "muirGlacierBlock": 0,
+                "berlinBlock": 3950000,
+                "londonBlock": 105235063,
+                "arrowGlacierBlock": 105235063,
+                "grayGlacierBlock": 105235063,
+                "mergeNetsplitBlock": 105235063,
+                "shanghaiTime": 1704992401,
+                "cancunTime": 1710374401,
+                "pragueTime": 1746806401,
+                "bedrockBlock": 105235063,
+                "regolithTime": 0,
+                "canyonTime": 1704992401,
+                "ecotoneTime": 1710374401,
+                "fjordTime": 1720627201,
+                "graniteTime": 1726070401,
+                "holoceneTime": 1736445601,
+                "isthmusTime": 1746806401,
+                "terminalTotalDifficulty": 0,
+                "terminalTotalDifficultyPassed": true,
+                "optimism": {
+                    "eip1559Elasticity": 6,
+                    "eip1559Denominator": 50,
+                    "eip1559DenominatorCanyon": 250
+                }
+            },
+            "gasLimit": "0x1c9c380"
+        }"#;
+        let spec = ChainSpec::from_geth_genesis(op).unwrap();
+        assert_eq!(spec.chain_id, 10);
+        assert_eq!(spec.forks.get(&Fork::London), Some(&ForkActivation::Block(105_235_063)));
+        assert_eq!(spec.fork_at(u64::MAX, 1_746_806_401), Fork::Prague);
+        assert_eq!(spec.fee_params.elasticity_multiplier, 6);
+        assert_eq!(spec.fee_params.base_fee_max_change_denominator, 50);
+        let rules = spec.rules_at(u64::MAX, u64::MAX);
+        assert!(rules.allows_tx_type(TxType::Deposit));
+        assert!(!rules.allows_tx_type(TxType::Type3));
+
+        // C-Chain config of coreth
+        let avalanche = r#"{
+            "config": {
+                "chainId": 43114,
+                "homesteadBlock": 0,
+                "daoForkBlock": 0,
+                "daoForkSupport": true,
+                "eip150Block": 0,
+                "eip155Block": 0,
+                "eip158Block": 0,
+                "byzantiumBlock": 0,
+                "constantinopleBlock": 0,
+                "petersburgBlock": 0,
+                "istanbulBlock": 0,
+                "muirGlacierBlock": 0,
+                "apricotPhase1BlockTimestamp": 1617199200,
+                "apricotPhase2BlockTimestamp": 1620644400,
+                "apricotPhase3BlockTimestamp": 1629813600,
+                "apricotPhase4BlockTimestamp": 1632772800,
+                "apricotPhase5BlockTimestamp": 1638468000,
+                "apricotPhasePre6BlockTimestamp": 1662037200,
+                "apricotPhase6BlockTimestamp": 1662040800,
+                "apricotPhasePost6BlockTimestamp": 1662044400,
+                "banffBlockTimestamp": 1666191600,
+                "cortinaBlockTimestamp": 1682434800,
+                "durangoBlockTimestamp": 1709740800,
+                "etnaTimestamp": 1734368400,
+                "fortunaTimestamp": 1744124400
+            },
+            "gasLimit": "0x5f5e100"
+        }"#;
+        assert_eq!(ChainSpec::from_geth_genesis(avalanche), Ok(ChainSpec::avalanche()));
+
+        let unknown = r#"{ "config": { "chainId": 1, "amsterdamTime": 1, "verkleBlock": 2, "keplerTime": 3, "heliconTimestamp": 4, "graniteTimestamp": 5 } }"#;
+        assert_eq!(
+            ChainSpec::from_geth_genesis(unknown),
+            Err(ChainSpecError::UnknownForkKeys(vec![
+                "config.amsterdamTime".into(),
+                "config.heliconTimestamp".into(),
+                "config.verkleBlock".into()
+            ]))
+        );
+        assert_eq!(
+            ChainSpec::from_geth_genesis(r#"{ "config": {} }"#),
+            Err(ChainSpecError::MissingField("config.chainId"))
+        );
+        assert_eq!(
+            ChainSpec::from_geth_genesis(r#"{ "config": { "chainId": 1, "londonBlock": "soon" } }"#),
+            Err(ChainSpecError::InvalidField("config.londonBlock".into()))
+        );
+        assert!(matches!(
+            ChainSpec::from_geth_genesis("{ \"config\": "),
+            Err(ChainSpecError::InvalidJson(_))
+        ));
+    }
+
+    #[test]
+    fn chain_spec_from_parity_spec() {
+        let spec = r#"{
+            "name": "Morden",
+            "engine": { "Ethash": { "params": { "minimumDifficulty": "0x020000", "homesteadTransition": "0x789b0" } } },
+            "params": {
+                "networkID": "0x2",
+                "chainID": "0x3e",
+                "eip150Transition": "0x1b34d8",
+                "eip155Transition": 1885000,
+                "eip160Transition": 1885000,
+                "eip161abcTransition": 1885000,
+                "eip161dTransition": 1885000,
+                "eip1559Transition": "0x2000000",
+                "eip1559BaseFeeMaxChangeDenominator": "0x32",
+                "eip1559ElasticityMultiplier": "0x6",
+                "eip1559BaseFeeInitialValue": "0x7",
+                "eip4844TransitionTimestamp": "0x65156994",
+                "maxCodeSize": 24576
+            }
+        }"#;
+        let spec = ChainSpec::from_parity_spec(spec).unwrap();
+        assert_eq!(spec.name, "Morden");
+        assert_eq!(spec.chain_id, 62);
+        assert_eq!(spec.forks.get(&Fork::Homestead), Some(&ForkActivation::Block(494_000)));
+        assert_eq!(
+            spec.forks.get(&Fork::SpuriousDragon),
+            Some(&ForkActivation::Block(1_885_000))
+        );
+        assert_eq!(
+            spec.forks.get(&Fork::Cancun),
+            Some(&ForkActivation::Timestamp(1_695_902_100))
+        );
+        assert_eq!(spec.fork_at(1_885_000, 0), Fork::SpuriousDragon);
+        assert_eq!(spec.fee_params.base_fee_max_change_denominator, 50);
+        assert_eq!(spec.fee_params.elasticity_multiplier, 6);
+        assert_eq!(spec.fee_params.initial_base_fee, 7.into());
+
+        let authority_round = r#"{ "engine": { "authorityRound": {} }, "params": { "networkID": 77 } }"#;
+        let spec = ChainSpec::from_parity_spec(authority_round).unwrap();
+        assert_eq!(spec.chain_id, 77);
+        assert_eq!(spec.fork_at(0, 0), Fork::Homestead);
+        assert_eq!(spec.fee_params, FeeParams::default());
+
+        let unknown = r#"{ "params": { "networkID": 1, "eip9999Transition": 5, "eip7702TransitionTimestamp": 6 } }"#;
+        assert_eq!(
+            ChainSpec::from_parity_spec(unknown),
+            Err(ChainSpecError::UnknownForkKeys(vec!["params.eip9999Transition".into()]))
+        );
+        assert_eq!(
+            ChainSpec::from_parity_spec(r#"{ "params": {} }"#),
+            Err(ChainSpecError::MissingField("params.networkID"))
+        );
+    }
+
+    #[test]
+    fn transaction_verifier() {
+        let key = KeyPair::from_secret_slice(&[
+            128, 148, 101, 177, 125, 10, 77, 219, 62, 76, 105, 232, 242, 60, 44, 171, 173, 134, 143, 81, 248, 190, 213,
+            199, 101, 173, 29, 101, 22, 195, 48, 111,
+        ])
+        .unwrap();
+        let tx = |gas: u64, data| {
+            TransactionWrapperBuilder::new(
+                TxType::Type2,
+                5.into(),
+                gas.into(),
+                Action::Call(Address::repeat_byte(0x33)),
+                1_000.into(),
+                data,
+            )
+            .with_chain_id(1)
+            .with_priority_fee_per_gas(100.into(), 10.into())
+            .build()
+            .unwrap()
+            .sign(key.secret(), None)
+            .unwrap()
+        };
+        let verifier = TransactionVerifier::new(50.into(), 30_000_000.into(), 128 * 1024)
+            .with_rules(ChainSpec::mainnet().rules_at(u64::MAX, u64::MAX))
+            .with_account(5.into(), 10_000_000.into());
+        let signed = tx(21_000, vec![]);
+        assert_eq!(verifier.verify(&signed), Ok(()));
+        assert_eq!(signed.max_cost(), (21_000 * 100 + 1_000).into());
+
+        let big = tx(10_000_000, vec![1; 1024]);
+        let mut small = verifier.clone();
+        small.max_tx_size = 1024;
+        assert_eq!(small.verify(&big), Err(Error::TooBig));
+        assert_eq!(
+            verifier.clone().with_tx_gas_limit(1_000_000.into()).verify(&big),
+            Err(Error::InvalidGasLimit(OutOfBounds {
+                min: Some(61_960.into()),
+                max: Some(1_000_000.into()),
+                found: 10_000_000.into(),
+            }))
+        );
+        assert_eq!(
+            verifier.verify(&tx(40_000_000, vec![])),
+            Err(Error::GasLimitExceeded {
+                limit: 30_000_000.into(),
+                got: 40_000_000.into(),
+            })
+        );
+        assert_eq!(
+            verifier.verify(&tx(21_000, vec![1])),
+            Err(Error::InsufficientGas {
+                minimal: 21_040.into(),
+                got: 21_000.into(),
+            })
+        );
+        assert_eq!(
+            verifier.clone().with_base_fee(30.into()).verify(&signed),
+            Err(Error::InsufficientGasPrice {
+                minimal: 50.into(),
+                got: 40.into(),
+            })
+        );
+        assert_eq!(
+            verifier.clone().with_base_fee(200.into()).verify(&signed),
+            Err(Error::InsufficientGasPrice {
+                minimal: 200.into(),
+                got: 100.into(),
+            })
+        );
+        assert_eq!(
+            verifier
+                .clone()
+                .with_rules(ChainSpec::bsc().rules_at(u64::MAX, u64::MAX))
+                .verify(&signed),
+            Err(Error::InvalidChainId)
+        );
+        // without chain rules any chain id is accepted, an EIP-155 one included
+        let legacy = TransactionWrapperBuilder::new(
+            TxType::Legacy,
+            5.into(),
+            21_000.into(),
+            Action::Call(Address::repeat_byte(0x33)),
+            1_000.into(),
+            vec![],
+        )
+        .with_gas_price(100.into())
+        .build()
+        .unwrap()
+        .sign(key.secret(), Some(56))
+        .unwrap();
+        let no_rules = TransactionVerifier::new(50.into(), 30_000_000.into(), 128 * 1024);
+        assert_eq!(no_rules.verify(&legacy), Ok(()));
+        assert_eq!(no_rules.verify(&signed), Ok(()));
+        assert_eq!(
+            verifier
+                .clone()
+                .with_account(6.into(), 10_000_000.into())
+                .verify(&signed),
+            Err(Error::Old)
+        );
+        let unverified = UnverifiedTransactionWrapper::from(signed);
+        assert_eq!(
+            verifier.with_account(0.into(), 100.into()).verify(&unverified),
+            Err(Error::InsufficientBalance {
+                balance: 100.into(),
+                cost: 2_101_000.into(),
+            })
+        );
+    }
+
+    #[test]
+    fn ban_list() {
+        let key = KeyPair::from_secret_slice(&[
+            128, 148, 101, 177, 125, 10, 77, 219, 62, 76, 105, 232, 242, 60, 44, 171, 173, 134, 143, 81, 248, 190, 213,
+            199, 101, 173, 29, 101, 22, 195, 48, 111,
+        ])
+        .unwrap();
+        let tx = |action, data: Vec<u8>| {
+            TransactionWrapperBuilder::new(TxType::Type2, 0.into(), 1_000_000.into(), action, 0.into(), data)
+                .with_chain_id(1)
+                .with_priority_fee_per_gas(100.into(), 10.into())
+                .build()
+                .unwrap()
+                .sign(key.secret(), None)
+                .unwrap()
+        };
+        let scam = Address::repeat_byte(0x66);
+        let call = tx(Action::Call(scam), vec![]);
+        let create = tx(Action::Create, vec![0x60, 0x00]);
+
+        let mut bans = BanList::new();
+        bans.ban(BanKey::Recipient(scam), Some(100));
+        assert_eq!(bans.verify_at(&call, 99), Err(Error::RecipientBanned));
+        assert_eq!(bans.verify_at(&call, 100), Ok(()));
+        bans.ban(BanKey::Recipient(scam), None);
+        bans.ban(BanKey::Recipient(scam), Some(50));
+        assert_eq!(bans.verify_at(&call, u64::MAX), Err(Error::RecipientBanned));
+        bans.ban(BanKey::Code(keccak([0x60, 0x00])), None);
+        assert_eq!(bans.verify_at(&create, 0), Err(Error::CodeBanned));
+        bans.ban(BanKey::Sender(key.address()), Some(10));
+        assert_eq!(bans.verify_sender_at(&key.address(), 0), Err(Error::SenderBanned));
+        bans.prune(10);
+        assert_eq!(bans.len(), 2);
+        assert!(bans.unban(&BanKey::Recipient(scam)));
+        assert_eq!(bans.verify_at(&call, 0), Ok(()));
+
+        let json = format!(
+            r#"[{{ "kind": "sender", "value": "{:x}" }}, {{ "kind": "recipient", "value": "{:x}", "until": 4102444800 }}]"#,
+            key.address(),
+            scam
+        );
+        let bans = BanList::from_json(&json).unwrap();
+        let csv = format!(
+            "kind,value,until\n# sanctioned\nsender,{:x},\n\nrecipient,{:x}, 4102444800\n",
+            key.address(),
+            scam
+        );
+        assert_eq!(BanList::from_csv(&csv), Ok(bans.clone()));
+        assert_eq!(
+            BanList::from_csv("sender,0x1234"),
+            Err(BanListError::InvalidEntry {
+                index: 1,
+                reason: "invalid sender 0x1234".into(),
+            })
+        );
+        assert_eq!(
+            BanList::from_csv("kind,value,until\n# sanctioned\nsender"),
+            Err(BanListError::InvalidEntry {
+                index: 3,
+                reason: "missing value".into(),
+            })
+        );
+        assert_eq!(
+            BanList::from_json(r#"[{ "kind": "author", "value": "0x00" }]"#),
+            Err(BanListError::InvalidEntry {
+                index: 0,
+                reason: "unknown ban kind author".into(),
+            })
+        );
+        let path = std::env::temp_dir().join(format!("ban_list_{}.json", std::process::id()));
+        std::fs::write(&path, &json).unwrap();
+        assert_eq!(BanList::load(&path), Ok(bans.clone()));
+        std::fs::remove_file(&path).unwrap();
+
+        let verifier = TransactionVerifier::new(0.into(), 30_000_000.into(), 128 * 1024).with_ban_list(bans);
+        assert_eq!(verifier.verify(&call), Err(Error::RecipientBanned));
+        assert_eq!(verifier.verify(&create), Ok(()));
+        assert_eq!(verifier.verify_signed(&create), Err(Error::SenderBanned));
+    }
+
+    #[test]
+    fn transaction_permissions() {
+        let key = KeyPair::from_secret_slice(&[
+            128, 148, 101, 177, 125, 10, 77, 219, 62, 76, 105, 232, 242, 60, 44, 171, 173, 134, 143, 81, 248, 190, 213,
+            199, 101, 173, 29, 101, 22, 195, 48, 111,
+        ])
+        .unwrap();
+        let tx = |action| {
+            TransactionWrapperBuilder::new(TxType::Legacy, 0.into(), 100_000.into(), action, 0.into(), vec![])
+                .with_gas_price(1.into())
+                .build()
+                .unwrap()
+                .sign(key.secret(), Some(1))
+                .unwrap()
+        };
+        let call = tx(Action::Call(Address::repeat_byte(0x33)));
+        let create = tx(Action::Create);
+
+        let public = StaticPermissions(Permissions::ALL);
+        assert_eq!(public.verify(&create, true), Ok(()));
+        let no_deploy = StaticPermissions(Permissions::CALL);
+        assert_eq!(no_deploy.verify(&call, false), Ok(()));
+        assert_eq!(no_deploy.verify(&create, false), Err(Error::NotAllowed));
+        assert_eq!(no_deploy.verify(&call, true), Err(Error::NotAllowed));
+
+        let mut consortium = AllowMapPermissions::new();
+        assert_eq!(consortium.verify(&call, false), Err(Error::NotAllowed));
+        consortium.allow(key.address(), Permissions {
+            call: true,
+            create: false,
+            private: true,
+        });
+        assert_eq!(consortium.verify(&call, true), Ok(()));
+        assert_eq!(consortium.verify(&create, true), Err(Error::NotAllowed));
+        assert_eq!(consortium.revoke(&key.address()).map(|p| p.private), Some(true));
+        assert_eq!(consortium.verify(&call, false), Err(Error::NotAllowed));
+        let open = AllowMapPermissions::with_default(Permissions::CALL);
+        assert!(open.is_allowed(&key.address(), TransactionKind::Call));
+        assert!(!open.is_allowed(&key.address(), TransactionKind::Create));
+    }
+
+    #[test]
+    fn transaction_pool() {
+        let alice = KeyPair::from_secret_slice(&[
+            128, 148, 101, 177, 125, 10, 77, 219, 62, 76, 105, 232, 242, 60, 44, 171, 173, 134, 143, 81, 248, 190, 213,
+            199, 101, 173, 29, 101, 22, 195, 48, 111,
+        ])
+        .unwrap();
+        let bob = KeyPair::from_secret_slice(&[1; 32]).unwrap();
+        let tx = |key: &KeyPair, nonce: u64, max_fee: u64, max_priority_fee: u64| -> PendingTransaction {
+            TransactionWrapperBuilder::new(
+                TxType::Type2,
+                nonce.into(),
+                21_000.into(),
+                Action::Call(Address::repeat_byte(0x33)),
+                0.into(),
+                vec![],
+            )
+            .with_chain_id(1)
+            .with_priority_fee_per_gas(max_fee.into(), max_priority_fee.into())
+            .build()
+            .unwrap()
+            .sign(key.secret(), None)
+            .unwrap()
+            .into()
+        };
+        let nonces = |txs: Vec<&PendingTransaction>| -> Vec<(Address, u64)> {
+            txs.into_iter()
+                .map(|tx| (tx.sender, tx.unsigned().nonce().as_u64()))
+                .collect()
+        };
+        let (a, b) = (alice.address(), bob.address());
+
+        let mut pool = TransactionPool::new(PoolOptions {
+            max_count: 5,
+            max_per_sender: 3,
+            fee_bump_percent: 10,
+        });
+        pool.set_account_nonce(a, 1.into());
+        assert_eq!(pool.import(tx(&alice, 0, 100, 10)), Err(Error::Old));
+        assert_eq!(pool.import(tx(&alice, 1, 200, 10)), Ok(vec![]));
+        assert_eq!(pool.import(tx(&alice, 1, 200, 10)), Err(Error::AlreadyImported));
+        assert_eq!(pool.import(tx(&alice, 2, 100, 5)), Ok(vec![]));
+        assert_eq!(pool.import(tx(&alice, 4, 100, 50)), Ok(vec![]));
+        assert_eq!(pool.import(tx(&alice, 5, 100, 50)), Err(Error::LimitReached));
+        assert_eq!(pool.next_nonce(&a), Some(3.into()));
+        assert_eq!(nonces(pool.future(&a)), vec![(a, 4)]);
+        assert_eq!(pool.import(tx(&bob, 7, 100, 7)), Ok(vec![]));
+        assert_eq!(pool.next_nonce(&b), Some(8.into()));
+
+        // both fees must be bumped by 10%
+        assert_eq!(pool.import(tx(&alice, 2, 105, 50)), Err(Error::TooCheapToReplace));
+        assert_eq!(pool.import(tx(&alice, 2, 200, 4)), Err(Error::TooCheapToReplace));
+        let replacement = tx(&alice, 2, 110, 6);
+        let replaced = pool.import(replacement.clone()).unwrap();
+        assert_eq!(nonces(replaced.iter().collect()), vec![(a, 2)]);
+        assert_eq!(pool.get(&replacement.tx_hash()), Some(&replacement));
+
+        pool.set_base_fee(90.into());
+        assert_eq!(pool.effective_tip(&replacement), 6.into());
+        assert_eq!(nonces(pool.pending(0, 0)), vec![(a, 1), (b, 7), (a, 2)]);
+
+        assert_eq!(pool.import(tx(&bob, 8, 100, 8)), Ok(vec![]));
+        assert_eq!(pool.len(), 5);
+        assert_eq!(pool.import(tx(&bob, 9, 100, 1)), Err(Error::LimitReached));
+        let evicted = pool.import(tx(&bob, 9, 100, 9)).unwrap();
+        assert_eq!(nonces(evicted.iter().collect()), vec![(b, 8)]);
+
+        let evicted = pool.set_base_fee(105.into());
+        assert_eq!(evicted.len(), 3);
+        assert_eq!(pool.len(), 2);
+        assert_eq!(nonces(pool.pending(0, 0)), vec![(a, 1), (a, 2)]);
+        assert_eq!(
+            pool.import(tx(&bob, 7, 100, 7)),
+            Err(Error::InsufficientGasPrice {
+                minimal: 105.into(),
+                got: 100.into(),
+            })
+        );
+
+        let culled = pool.set_account_nonce(a, 2.into());
+        assert_eq!(nonces(culled.iter().collect()), vec![(a, 1)]);
+        assert_eq!(pool.remove(&replacement.tx_hash()), Some(replacement));
+        assert!(pool.is_empty());
+
+        // a blob transaction must double all its fees, the blob one included
+        let blob = |nonce: u64, max_fee: u64, max_priority_fee: u64, max_blob_fee: u64| -> PendingTransaction {
+            TransactionWrapperBuilder::new(
+                TxType::Type3,
+                nonce.into(),
+                21_000.into(),
+                Action::Call(Address::repeat_byte(0x33)),
+                0.into(),
+                vec![],
+            )
+            .with_chain_id(1)
+            .with_priority_fee_per_gas(max_fee.into(), max_priority_fee.into())
+            .with_blobs(max_blob_fee.into(), vec![kzg_to_versioned_hash(&[0; 48])])
+            .build()
+            .unwrap()
+            .sign(alice.secret(), None)
+            .unwrap()
+            .into()
+        };
+        assert_eq!(pool.import(blob(2, 200, 10, 10)), Ok(vec![]));
+        assert_eq!(pool.import(blob(2, 300, 15, 20)), Err(Error::TooCheapToReplace));
+        assert_eq!(pool.import(blob(2, 400, 20, 19)), Err(Error::TooCheapToReplace));
+        assert_eq!(pool.import(tx(&alice, 2, 400, 20)), Err(Error::TooCheapToReplace));
+        let replacement = blob(2, 400, 20, 20);
+        assert_eq!(
+            nonces(pool.import(replacement.clone()).unwrap().iter().collect()),
+            vec![(a, 2)]
+        );
+        assert!(pool.remove(&replacement.tx_hash()).is_some());
+
+        let mut later = tx(&bob, 0, 200, 20);
+        later.condition = Some(Condition::Number(10));
+        pool.import(later).unwrap();
+        assert!(pool.pending(9, 0).is_empty());
+        assert_eq!(nonces(pool.pending(10, 0)), vec![(b, 0)]);
+        // senders paying the same tip are ordered by address
+        pool.import(tx(&alice, 2, 200, 20)).unwrap();
+        let mut tied = vec![(a, 2), (b, 0)];
+        tied.sort();
+        assert_eq!(nonces(pool.pending(10, 0)), tied);
+    }
+
+    #[test]
+    fn transaction_journal() {
+        use std::io::Write;
+
+        let alice = KeyPair::from_secret_slice(&[
+            128, 148, 101, 177, 125, 10, 77, 219, 62, 76, 105, 232, 242, 60, 44, 171, 173, 134, 143, 81, 248, 190, 213,
+            199, 101, 173, 29, 101, 22, 195, 48, 111,
+        ])
+        .unwrap();
+        let bob = KeyPair::from_secret_slice(&[1; 32]).unwrap();
+        let tx = |key: &KeyPair, tx_type, nonce: u64, gas_price: u64, condition| {
+            let signed = TransactionWrapperBuilder::new(
+                tx_type,
+                nonce.into(),
+                21_000.into(),
+                Action::Call(Address::repeat_byte(0x33)),
+                0.into(),
+                vec![],
+            )
+            .with_chain_id(1)
+            .with_gas_price(gas_price.into())
+            .with_priority_fee_per_gas(gas_price.into(), 1.into())
+            .build()
+            .unwrap()
+            .sign(key.secret(), Some(1))
+            .unwrap();
+            PendingTransaction::new(signed, condition)
+        };
+        let mined = tx(&alice, TxType::Legacy, 0, 10, None);
+        let queued = tx(&alice, TxType::Type2, 1, 10, Some(Condition::Number(100)));
+        let replacement = tx(&alice, TxType::Legacy, 1, 20, Some(Condition::Timestamp(1_700_000_000)));
+        let other = tx(&bob, TxType::Type1, 5, 10, None);
+        let later = tx(&bob, TxType::Type1, 6, 10, None);
+
+        let path = std::env::temp_dir().join(format!("transaction_journal_{}.rlp", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut journal = TransactionJournal::open(&path).unwrap();
+        for tx in vec![&mined, &queued, &other, &replacement] {
+            journal.insert(tx).unwrap();
+        }
+        // a record cut by a crash
+        let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&replacement.transaction.encode_raw()[..20]).unwrap();
+
+        let account_nonce = |sender: &Address| {
+            if *sender == alice.address() {
+                Some(1.into())
+            } else {
+                None
+            }
+        };
+        let mut journal = TransactionJournal::open(&path).unwrap();
+        assert_eq!(journal.load(|_| None).unwrap().len(), 3);
+        // the truncated record is cut off on opening, so a record appended after it is read back
+        journal.insert(&later).unwrap();
+        let journal = TransactionJournal::open(&path).unwrap();
+        assert_eq!(journal.load(|_| None).unwrap().len(), 4);
+        assert_eq!(journal.load(account_nonce).unwrap(), vec![
+            other.clone(),
+            replacement.clone(),
+            later.clone()
+        ]);
+
+        let mut journal = journal;
+        let size = std::fs::metadata(&path).unwrap().len();
+        assert_eq!(journal.compact(account_nonce).unwrap(), 3);
+        assert!(std::fs::metadata(&path).unwrap().len() < size);
+        assert_eq!(journal.load(|_| None).unwrap(), vec![
+            other.clone(),
+            replacement,
+            later.clone()
+        ]);
+        journal.insert(&queued).unwrap();
+        assert_eq!(journal.load(|_| None).unwrap(), vec![other.clone(), later, queued]);
+
+        journal.rotate(vec![&other]).unwrap();
+        assert_eq!(journal.load(|_| None).unwrap(), vec![other]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn condition_scheduler() {
+        let alice = KeyPair::from_secret_slice(&[
+            128, 148, 101, 177, 125, 10, 77, 219, 62, 76, 105, 232, 242, 60, 44, 171, 173, 134, 143, 81, 248, 190, 213,
+            199, 101, 173, 29, 101, 22, 195, 48, 111,
+        ])
+        .unwrap();
+        let bob = KeyPair::from_secret_slice(&[1; 32]).unwrap();
+        let tx = |key: &KeyPair, nonce: u64, condition| {
+            let signed = TransactionWrapperBuilder::new(
+                TxType::Legacy,
+                nonce.into(),
+                21_000.into(),
+                Action::Call(Address::repeat_byte(0x33)),
+                0.into(),
+                vec![],
+            )
+            .with_gas_price(1.into())
+            .build()
+            .unwrap()
+            .sign(key.secret(), Some(1))
+            .unwrap();
+            PendingTransaction::new(signed, condition)
+        };
+        // refund after the swap lock time, once fees are cheap or a day later at worst
+        let refund = Condition::And(vec![
+            Condition::Timestamp(1_000),
+            Condition::Or(vec![
+                Condition::BaseFeeBelow(20.into()),
+                Condition::Timestamp(1_000 + 86_400),
+            ]),
+        ]);
+        assert!(!refund.is_met(0, 999, Some(1.into())));
+        assert!(!refund.is_met(0, 1_000, Some(20.into())));
+        assert!(!refund.is_met(0, 1_000, None));
+        assert!(refund.is_met(0, 1_000, Some(19.into())));
+        assert!(refund.is_met(0, 87_400, Some(100.into())));
+        assert_eq!(rlp::decode::<Condition>(&rlp::encode(&refund)), Ok(refund.clone()));
+
+        let mut scheduler = TransactionScheduler::new();
+        let refund = tx(&alice, 0, Some(refund));
+        let later = tx(&alice, 1, Some(Condition::Number(10)));
+        let now = tx(&alice, 2, None);
+        let other = tx(&bob, 0, None);
+        scheduler.schedule(refund.clone());
+        scheduler.schedule(later.clone());
+        scheduler.schedule(now.clone());
+        scheduler.schedule(other.clone());
+        // nonces of alice wait for the refund, the ones of bob don't
+        assert_eq!(scheduler.on_new_head(5, 500, Some(1.into())), vec![other]);
+        assert!(scheduler.on_new_head(10, 1_000, Some(30.into())).is_empty());
+        assert_eq!(scheduler.len(), 3);
+        assert_eq!(scheduler.on_new_head(11, 1_012, Some(10.into())), vec![
+            refund.clone(),
+            later.clone(),
+            now
+        ]);
+        assert!(scheduler.is_empty());
+
+        scheduler.schedule(later.clone());
+        assert_eq!(scheduler.cancel(&refund.tx_hash()), None);
+        assert_eq!(scheduler.cancel(&later.tx_hash()), Some(later));
+        assert!(scheduler.on_new_head(100, 100_000, None).is_empty());
+    }
+
+    #[test]
+    fn replacement_builders() {
+        let key = KeyPair::from_secret_slice(&[
+            128, 148, 101, 177, 125, 10, 77, 219, 62, 76, 105, 232, 242, 60, 44, 171, 173, 134, 143, 81, 248, 190, 213,
+            199, 101, 173, 29, 101, 22, 195, 48, 111,
+        ])
+        .unwrap();
+        let builder = |tx_type| {
+            TransactionWrapperBuilder::new(
+                tx_type,
+                U256::from(7),
+                U256::from(60_000),
+                Action::Call(Address::repeat_byte(0x33)),
+                U256::from(1000),
+                vec![1, 2, 3],
+            )
+        };
+        assert_eq!(bump_fee(100.into(), 10), 110.into());
+        assert_eq!(bump_fee(5.into(), 10), 6.into());
+
+        let legacy = builder(TxType::Legacy)
+            .with_gas_price(100.into())
+            .build()
+            .unwrap()
+            .sign(key.secret(), Some(69))
+            .unwrap();
+        let faster = TransactionWrapperBuilder::speed_up(&legacy, 5).unwrap();
+        assert_eq!(faster.tx_type(), TxType::Legacy);
+        assert_eq!(faster.shared().nonce(), 7.into());
+        assert_eq!(faster.gas_price(), 110.into());
+        assert_eq!(faster.shared().value(), 1000.into());
+        assert_eq!(faster.shared().data(), &vec![1, 2, 3]);
+        let faster = TransactionWrapperBuilder::speed_up(&legacy, 25).unwrap();
+        assert_eq!(faster.gas_price(), 125.into());
+        let signed = faster.sign(key.secret(), Some(69)).unwrap();
+        assert_eq!(signed.sender, legacy.sender);
+        assert_eq!(signed.chain_id(), Some(69));
+
+        let eip1559 = builder(TxType::Type2)
+            .with_chain_id(1)
+            .with_priority_fee_per_gas(55.into(), 5.into())
+            .build()
+            .unwrap()
+            .sign(key.secret(), None)
+            .unwrap();
+        let faster = TransactionWrapperBuilder::speed_up(&eip1559, 0).unwrap();
+        assert_eq!(faster.tx_type(), TxType::Type2);
+        assert_eq!(faster.chain_id(), Some(1));
+        assert_eq!(faster.max_fee_per_gas(), 61.into());
+        assert_eq!(faster.max_priority_fee_per_gas(), 6.into());
+
+        let cancel = TransactionWrapperBuilder::cancel(&eip1559, 10).unwrap();
+        assert_eq!(cancel.tx_type(), TxType::Type2);
+        assert_eq!(cancel.shared().nonce(), 7.into());
+        assert_eq!(cancel.shared().gas(), 21_000.into());
+        assert_eq!(cancel.shared().action(), &Action::Call(eip1559.sender));
+        assert!(cancel.shared().value().is_zero());
+        assert!(cancel.shared().data().is_empty());
+        assert_eq!(cancel.max_fee_per_gas(), 61.into());
+        assert_eq!(cancel.max_priority_fee_per_gas(), 6.into());
+        let signed = cancel.sign(key.secret(), None).unwrap();
+        let mut pool = TransactionPool::new(PoolOptions::default());
+        pool.import(PendingTransaction::new(eip1559.clone(), None)).unwrap();
+        assert_eq!(pool.import(PendingTransaction::new(signed, None)).unwrap().len(), 1);
+
+        let blob = builder(TxType::Type3)
+            .with_chain_id(1)
+            .with_priority_fee_per_gas(55.into(), 5.into())
+            .with_blobs(3.into(), vec![H256::repeat_byte(0x01)])
+            .build()
+            .unwrap()
+            .sign(key.secret(), None)
+            .unwrap();
+        let cancel = TransactionWrapperBuilder::cancel(&blob, 10).unwrap();
+        assert_eq!(cancel.tx_type(), TxType::Type3);
+        assert_eq!(cancel.max_fee_per_gas(), 110.into());
+        assert_eq!(cancel.max_priority_fee_per_gas(), 10.into());
+        match cancel {
+            TransactionWrapper::Eip4844(ref tx) => {
+                assert_eq!(tx.max_fee_per_blob_gas, 6.into());
+                assert_eq!(tx.blob_versioned_hashes, vec![H256::repeat_byte(0x01)]);
+            },
+            _ => panic!("expected a blob transaction"),
+        }
+
+        let deposit = UnverifiedTransactionWrapper::Deposit(DepositTransaction::default());
+        assert_eq!(
+            TransactionWrapperBuilder::speed_up(&SignedTransaction::new(deposit).unwrap(), 10),
+            Err(tx_builders::TxBuilderError::InvalidTxType)
+        );
+    }
+
+    #[test]
+    fn nonce_manager() {
+        let alice = Address::repeat_byte(0xaa);
+        let bob = Address::repeat_byte(0xbb);
+        let path = std::env::temp_dir().join(format!("nonce_manager_{}.rlp", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let manager = NonceManager::open(&path).unwrap();
+        manager.reconcile(alice, 5.into()).unwrap();
+        assert_eq!(manager.next_nonce(&alice).unwrap(), Some(5.into()));
+        assert_eq!(manager.next_nonce(&bob).unwrap(), None);
+
+        // workers of several processes, each with a manager of its own
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let manager = NonceManager::open(&path).unwrap();
+                    (0..5)
+                        .map(|_| {
+                            let reservation = manager.reserve(alice).unwrap();
+                            let nonce = reservation.nonce();
+                            reservation.commit().unwrap();
+                            nonce.low_u64()
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let mut nonces: Vec<u64> = threads.into_iter().flat_map(|t| t.join().unwrap()).collect();
+        nonces.sort();
+        assert_eq!(nonces, (5..25).collect::<Vec<_>>());
+        assert_eq!(manager.next_nonce(&alice).unwrap(), Some(25.into()));
+
+        // an abandoned signing gives its nonce back
+        let first = manager.reserve(alice).unwrap();
+        let second = manager.reserve(alice).unwrap();
+        let third = manager.reserve(alice).unwrap();
+        assert_eq!(
+            (first.nonce(), second.nonce(), third.nonce()),
+            (25.into(), 26.into(), 27.into())
+        );
+        assert_eq!(manager.reserved(&alice).unwrap(), vec![25.into(), 26.into(), 27.into()]);
+        second.release().unwrap();
+        assert_eq!(manager.reserve(alice).unwrap().nonce(), 26.into());
+        drop(first);
+        assert_eq!(manager.reserved(&alice).unwrap(), vec![27.into()]);
+        let refill = manager.reserve(alice).unwrap();
+        assert_eq!(refill.nonce(), 25.into());
+        refill.commit().unwrap();
+        // releasing the highest nonces moves the next one back
+        drop(third);
+        assert_eq!(manager.next_nonce(&alice).unwrap(), Some(26.into()));
+
+        // the chain moved on without the manager
+        let stale = manager.reserve(alice).unwrap();
+        assert_eq!(stale.nonce(), 26.into());
+        manager.reconcile(alice, 30.into()).unwrap();
+        assert!(manager.reserved(&alice).unwrap().is_empty());
+        drop(stale);
+        assert_eq!(manager.reserve(alice).unwrap().nonce(), 30.into());
+        // nothing is reserved, the pending nonce on chain is the next one
+        manager.reconcile(alice, 10.into()).unwrap();
+        assert_eq!(manager.next_nonce(&alice).unwrap(), Some(10.into()));
+
+        let reservation = manager.reserve(bob).unwrap();
+        assert_eq!(reservation.sender(), bob);
+        assert_eq!(reservation.nonce(), 0.into());
+        reservation.commit().unwrap();
+        let reopened = NonceManager::open(&path).unwrap();
+        assert_eq!(reopened.next_nonce(&bob).unwrap(), Some(1.into()));
+        assert_eq!(reopened.next_nonce(&alice).unwrap(), Some(10.into()));
+        // a crash while writing the state leaves the previous one
+        std::fs::write(path.with_extension("new"), b"partial").unwrap();
+        assert_eq!(reopened.next_nonce(&bob).unwrap(), Some(1.into()));
+        reopened.reserve(bob).unwrap().commit().unwrap();
+        assert_eq!(reopened.next_nonce(&bob).unwrap(), Some(2.into()));
+        assert!(!path.with_extension("new").exists());
+
+        // a worker which died holding its reservation
+        let short = NonceManager::open(&path)
+            .unwrap()
+            .with_lease(std::time::Duration::from_secs(0));
+        let lost = short.reserve(bob).unwrap();
+        assert_eq!(lost.nonce(), 2.into());
+        let held = reopened.reserve(bob).unwrap();
+        assert_eq!(held.nonce(), 2.into());
+        // the late commit of a reclaimed reservation fails and leaves the nonce to its new holder
+        let lease_expired = |err: std::io::Error| {
+            err.into_inner()
+                .and_then(|err| err.downcast::<LeaseExpired>().ok())
+                .map(|err| *err)
+        };
+        let expired = Some(LeaseExpired {
+            sender: bob,
+            nonce: 2.into(),
+        });
+        assert_eq!(lease_expired(lost.renew().unwrap_err()), expired);
+        assert_eq!(lease_expired(lost.commit().unwrap_err()), expired);
+        assert_eq!(reopened.reserved(&bob).unwrap(), vec![2.into()]);
+        held.renew().unwrap();
+        let alive = reopened.reserve(bob).unwrap();
+        assert_eq!(alive.nonce(), 3.into());
+        std::mem::forget(short.reserve(bob).unwrap());
+        reopened.reconcile(bob, 0.into()).unwrap();
+        assert_eq!(reopened.reserved(&bob).unwrap(), vec![2.into(), 3.into()]);
+        assert_eq!(reopened.next_nonce(&bob).unwrap(), Some(4.into()));
+        held.commit().unwrap();
+        alive.commit().unwrap();
+
+        std::fs::write(&path, b"garbage").unwrap();
+        assert_eq!(
+            reopened.reserve(alice).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidData
+        );
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(path.with_extension("lock")).unwrap();
+    }
+
+    #[test]
+    fn fill_transaction_request() {
+        let key = KeyPair::from_secret_slice(&[
+            128, 148, 101, 177, 125, 10, 77, 219, 62, 76, 105, 232, 242, 60, 44, 171, 173, 134, 143, 81, 248, 190, 213,
+            199, 101, 173, 29, 101, 22, 195, 48, 111,
+        ])
+        .unwrap();
+        let gwei = U256::from(1_000_000_000u64);
+        let mainnet = ChainSpec::mainnet();
+        let prague = MockProvider::new(mainnet.rules_at(22_431_084, 1_746_612_311)).with_nonce(key.address(), 9.into());
+        let transfer = TransactionRequest {
+            from: Some(key.address()),
+            to: Some(Address::repeat_byte(0x33)),
+            value: Some(1000.into()),
+            ..Default::default()
+        };
+
+        let filled = transfer.clone().fill(&prague).unwrap();
+        assert_eq!(filled.chain_id, Some(1));
+        assert_eq!(filled.nonce, Some(9.into()));
+        assert_eq!(filled.tx_type, Some(TxType::Type2));
+        assert_eq!(filled.max_priority_fee_per_gas, Some(gwei));
+        assert_eq!(filled.max_fee_per_gas, Some(gwei * 3));
+        assert_eq!(filled.gas_price, None);
+        assert_eq!(filled.gas, Some(21_000.into()));
+        let tx = filled.build().unwrap();
+        assert_eq!(tx.tx_type(), TxType::Type2);
+        assert_eq!(tx.shared().nonce(), 9.into());
+        assert_eq!(tx.shared().action(), &Action::Call(Address::repeat_byte(0x33)));
+        let signed = tx.sign(key.secret(), None).unwrap();
+        assert_eq!(signed.sender, key.address());
+        assert_eq!(signed.chain_id(), Some(1));
+
+        // fields set by the caller are kept
+        let filled = TransactionRequest {
+            nonce: Some(3.into()),
+            gas: Some(50_000.into()),
+            max_priority_fee_per_gas: Some(2.into()),
+            ..transfer.clone()
+        }
+        .fill(&prague)
+        .unwrap();
+        assert_eq!(filled.nonce, Some(3.into()));
+        assert_eq!(filled.gas, Some(50_000.into()));
+        assert_eq!(filled.max_fee_per_gas, Some(gwei * 2 + 2));
+        let legacy = TransactionRequest {
+            gas_price: Some(7.into()),
+            ..transfer.clone()
+        }
+        .fill(&prague)
+        .unwrap();
+        assert_eq!(legacy.tx_type, Some(TxType::Legacy));
+        assert_eq!(legacy.gas_price, Some(7.into()));
+        assert_eq!(legacy.max_fee_per_gas, None);
+
+        let blob = TransactionRequest {
+            blob_versioned_hashes: Some(vec![H256::repeat_byte(0x01)]),
+            ..transfer.clone()
+        }
+        .fill(&prague)
+        .unwrap();
+        assert_eq!(blob.tx_type, Some(TxType::Type3));
+        assert_eq!(blob.max_fee_per_blob_gas, Some(2.into()));
+        assert_eq!(blob.build().unwrap().tx_type(), TxType::Type3);
+
+        // the type follows chain support
+        let berlin = MockProvider::new(mainnet.rules_at(12_244_000, 0));
+        let with_access_list = TransactionRequest {
+            access_list: Some(AccessList::default()),
+            ..transfer.clone()
+        };
+        let filled = with_access_list.clone().fill(&berlin).unwrap();
+        assert_eq!(filled.tx_type, Some(TxType::Type1));
+        assert_eq!(filled.gas_price, Some(gwei));
+        assert_eq!(filled.build().unwrap().tx_type(), TxType::Type1);
+        assert_eq!(with_access_list.fill(&prague).unwrap().tx_type, Some(TxType::Type2));
+        let frontier = MockProvider::new(mainnet.rules_at(0, 0));
+        assert_eq!(transfer.clone().fill(&frontier).unwrap().tx_type, Some(TxType::Legacy));
+        let authorization = TransactionRequest {
+            authorization_list: Some(vec![SignedAuthorization::default()]),
+            ..transfer.clone()
+        };
+        assert_eq!(
+            authorization.clone().fill(&berlin),
+            Err(FillError::UnsupportedTxType(TxType::Type4 as u8))
+        );
+        assert_eq!(authorization.fill(&prague).unwrap().tx_type, Some(TxType::Type4));
+
+        // a pipeline of chosen fillers
+        let pipeline = FillPipeline::new().with_filler(ChainIdFiller).with_filler(GasFiller);
+        let partial = pipeline.fill(transfer.clone(), &prague).unwrap();
+        assert_eq!(partial.chain_id, Some(1));
+        assert_eq!(partial.nonce, None);
+        assert_eq!(partial.build(), Err(FillError::MissingField("type")));
+        let anonymous = TransactionRequest { from: None, ..transfer };
+        assert_eq!(anonymous.fill(&prague), Err(FillError::MissingField("from")));
+    }
+
+    #[test]
+    fn eip4844_sign_and_parse_tx() {
+        let key = KeyPair::from_secret_slice(&[
+            128, 148, 101, 177, 125, 10, 77, 219, 62, 76, 105, 232, 242, 60, 44, 171, 173, 134, 143, 81, 248, 190, 213,
+            199, 101, 173, 29, 101, 22, 195, 48, 111,
+        ])
+        .unwrap();
+        let blob_hash = H256::from_str("0x01b0a4cdd5f55589f5c5b4d46c76704bb6ce95c0a8c09f77f197a57808dded28").unwrap();
+        let to = Address::from_str("0x095e7baea6a6c7c4c2dfeb977efac326af552d87").unwrap();
+        let signed = TransactionWrapperBuilder::new(
+            TxType::Type3,
+            U256::from(1),
+            U256::from(21_000),
+            Action::Call(to),
+            U256::zero(),
+            Vec::new(),
+        )
+        .with_chain_id(1)
+        .with_priority_fee_per_gas(U256::from(2_000_000_000u64), U256::from(1_000_000_000u64))
+        .with_blobs(U256::from(10_000_000_000u64), vec![blob_hash])
+        .build()
+        .unwrap()
+        .sign(key.secret(), None)
+        .expect("sign transaction okay");
+        assert_eq!(Address::from(keccak(key.public())), signed.sender());
+
+        let bytes = signed.encode_raw();
+        assert_eq!(bytes[0], TxType::Type3 as u8);
+        assert_eq!(keccak(&bytes), signed.tx_hash());
+
+        let unverified: UnverifiedTransactionWrapper = rlp::decode(&bytes).expect("decoding tx data failed");
+        let decoded = SignedTransaction::new(unverified).unwrap();
+        assert_eq!(decoded, signed);
+        if let UnverifiedTransactionWrapper::Eip4844(tx) = decoded.transaction {
+            assert_eq!(tx.blob_versioned_hashes(), &[blob_hash]);
+            assert_eq!(tx.max_fee_per_blob_gas(), U256::from(10_000_000_000u64));
+            assert_eq!(tx.blob_gas(), GAS_PER_BLOB);
+        } else {
+            panic!("expected tx type 3 (eip-4844)");
+        }
+    }
+
+    #[test]
+    fn eip4844_builder_rejects_invalid_tx() {
+        let builder = |action: Action, blob_versioned_hashes: Vec<H256>| {
+            TransactionWrapperBuilder::new(
+                TxType::Type3,
+                U256::zero(),
+                U256::from(21_000),
+                action,
+                U256::zero(),
+                vec![],
+            )
+            .with_chain_id(1)
+            .with_priority_fee_per_gas(U256::one(), U256::one())
+            .with_blobs(U256::one(), blob_versioned_hashes)
+            .build()
+        };
+        assert_eq!(
+            builder(Action::Create, vec![H256::zero()]).unwrap_err(),
+            tx_builders::TxBuilderError::ContractCreationNotAllowed
+        );
+        assert_eq!(
+            builder(Action::Call(Address::zero()), vec![]).unwrap_err(),
+            tx_builders::TxBuilderError::NoBlobVersionedHashes
+        );
+    }
+
+    #[test]
+    fn eip4844_fake_exponential() {
+        let cases: &[(u64, u64, u64, u64)] = &[
+            (1, 0, 1, 1),
+            (38493, 0, 1000, 38493),
+            (0, 1234, 2345, 0),
+            (1, 2, 1, 6),
+            (1, 4, 2, 6),
+            (1, 3, 1, 16),
+            (1, 6, 2, 18),
+            (1, 4, 1, 49),
+            (1, 8, 2, 50),
+            (10, 8, 2, 542),
+            (11, 8, 2, 596),
+            (1, 5, 1, 136),
+            (1, 5, 2, 11),
+            (2, 5, 2, 23),
+            (1, 50_000_000, 2_225_652, 5_709_098_764),
+        ];
+        for &(factor, numerator, denominator, expected) in cases {
+            assert_eq!(
+                fake_exponential(factor.into(), numerator.into(), denominator.into()),
+                U256::from(expected)
+            );
+        }
+        assert_eq!(
+            blob_base_fee(0, BLOB_BASE_FEE_UPDATE_FRACTION_CANCUN),
+            U256::from(MIN_BASE_FEE_PER_BLOB_GAS)
+        );
+        assert_eq!(fake_exponential(U256::MAX, U256::one(), U256::one()), U256::MAX);
+    }
+
+    #[test]
+    fn eip4844_network_form() {
+        let key = KeyPair::from_secret_slice(&[
+            128, 148, 101, 177, 125, 10, 77, 219, 62, 76, 105, 232, 242, 60, 44, 171, 173, 134, 143, 81, 248, 190, 213,
+            199, 101, 173, 29, 101, 22, 195, 48, 111,
+        ])
+        .unwrap();
+        // commitment to the empty blob (point at infinity)
+        let mut commitment = vec![0u8; BYTES_PER_COMMITMENT];
+        commitment[0] = 0xc0;
+        let blob_hash = kzg_to_versioned_hash(&commitment);
+        assert_eq!(
+            blob_hash,
+            H256::from_str("0x010657f37554c781402a22917dee2f75def7ab966d7b770905398eba3c444014").unwrap()
+        );
+        let sidecar = BlobTransactionSidecar::new(vec![vec![0u8; BYTES_PER_BLOB]], vec![commitment.clone()], vec![
+            commitment,
+        ])
+        .unwrap();
+
+        let build_tx = |blob_versioned_hashes: Vec<H256>| {
+            let signed = TransactionWrapperBuilder::new(
+                TxType::Type3,
+                U256::zero(),
+                U256::from(21_000),
+                Action::Call(Address::from_low_u64_be(0x42)),
+                U256::zero(),
+                vec![],
+            )
+            .with_chain_id(1)
+            .with_priority_fee_per_gas(U256::from(2_000_000_000u64), U256::from(1_000_000_000u64))
+            .with_blobs(U256::one(), blob_versioned_hashes)
+            .build()
+            .unwrap()
+            .sign(key.secret(), None)
+            .unwrap();
+            match signed.transaction {
+                UnverifiedTransactionWrapper::Eip4844(tx) => tx,
+                _ => panic!("expected tx type 3 (eip-4844)"),
+            }
+        };
+
+        let tx = build_tx(vec![blob_hash]);
+        let with_sidecar = UnverifiedEip4844TransactionWithSidecar::new(tx.clone(), sidecar.clone()).unwrap();
+        let bytes = with_sidecar.encode_raw();
+        assert_eq!(rlp::encode(&with_sidecar).to_vec(), bytes);
+        assert_eq!(bytes[0], TxType::Type3 as u8);
+        let decoded: UnverifiedEip4844TransactionWithSidecar = rlp::decode(&bytes).unwrap();
+        assert_eq!(decoded, with_sidecar);
+        assert_eq!(decoded.hash(), tx.hash());
+        assert_eq!(decoded.validate_versioned_hashes(), Ok(()));
+
+        // the consensus form can't be decoded as a network form and vice versa
+        assert!(rlp::decode::<UnverifiedEip4844TransactionWithSidecar>(&tx.encode_raw()).is_err());
+        assert!(rlp::decode::<UnverifiedTransactionWrapper>(&bytes).is_err());
+
+        let wrong_tx = build_tx(vec![H256::zero()]);
+        match UnverifiedEip4844TransactionWithSidecar::new(wrong_tx, sidecar.clone()) {
+            Err(Error::InvalidBlobSidecar(_)) => {},
+            res => panic!("unexpected result: {:?}", res),
+        }
+        assert!(
+            BlobTransactionSidecar::new(vec![vec![0u8; 10]], sidecar.commitments.clone(), sidecar.proofs.clone())
+                .is_err()
+        );
+
+        // a network form whose commitments do not match the versioned hashes is rejected on decode
+        let network_form = |tx: &UnverifiedEip4844Transaction, commitments: Vec<Bytes>| {
+            let mut s = RlpStream::new();
+            s.begin_list(4);
+            tx.rlp_append_signed_payload(&mut s);
+            s.append_list::<Bytes, _>(&sidecar.blobs);
+            s.append_list::<Bytes, _>(&commitments);
+            s.append_list::<Bytes, _>(&sidecar.proofs);
+            let mut bytes = vec![TxType::Type3 as u8];
+            bytes.extend_from_slice(&s.out());
+            bytes
+        };
+        assert_eq!(network_form(&tx, sidecar.commitments.clone()), bytes.to_vec());
+        let wrong_hash = network_form(&build_tx(vec![H256::zero()]), sidecar.commitments.clone());
+        assert!(rlp::decode::<UnverifiedEip4844TransactionWithSidecar>(&wrong_hash).is_err());
+        let mut other_commitment = vec![0u8; BYTES_PER_COMMITMENT];
+        other_commitment[0] = 0xc1;
+        let wrong_commitment = network_form(&tx, vec![other_commitment]);
+        assert!(rlp::decode::<UnverifiedEip4844TransactionWithSidecar>(&wrong_commitment).is_err());
+        let extra_hash = network_form(&build_tx(vec![blob_hash, blob_hash]), sidecar.commitments.clone());
+        assert!(rlp::decode::<UnverifiedEip4844TransactionWithSidecar>(&extra_hash).is_err());
+    }
+
+    #[cfg(feature = "kzg")]
+    #[test]
+    fn eip4844_kzg_proofs() {
+        let settings = c_kzg::ethereum_kzg_settings();
+        // the empty blob commits to the point at infinity, with the same proof
+        let mut infinity = vec![0u8; BYTES_PER_COMMITMENT];
+        infinity[0] = 0xc0;
+        let empty = BlobTransactionSidecar::new(vec![vec![0u8; BYTES_PER_BLOB]], vec![infinity.clone()], vec![
+            infinity.clone(),
+        ])
+        .unwrap();
+        assert_eq!(empty.verify_kzg_proofs(settings), Ok(()));
+
+        // field elements are big endian, each below the modulus with a zero first byte
+        let mut blob = vec![0u8; BYTES_PER_BLOB];
+        for i in 0..BYTES_PER_BLOB / 32 {
+            blob[i * 32 + 31] = i as u8;
+        }
+        let kzg_blob = c_kzg::Blob::from_bytes(&blob).unwrap();
+        let commitment = c_kzg::KzgCommitment::blob_to_kzg_commitment(&kzg_blob, settings)
+            .unwrap()
+            .to_bytes();
+        let proof = c_kzg::KzgProof::compute_blob_kzg_proof(&kzg_blob, &commitment, settings)
+            .unwrap()
+            .to_bytes();
+        let sidecar = BlobTransactionSidecar::new(
+            vec![vec![0u8; BYTES_PER_BLOB], blob.clone()],
+            vec![infinity.clone(), commitment.to_vec()],
+            vec![infinity.clone(), proof.to_vec()],
+        )
+        .unwrap();
+        assert_eq!(sidecar.verify_kzg_proofs(settings), Ok(()));
+
+        let key = KeyPair::from_secret_slice(&[
+            128, 148, 101, 177, 125, 10, 77, 219, 62, 76, 105, 232, 242, 60, 44, 171, 173, 134, 143, 81, 248, 190, 213,
+            199, 101, 173, 29, 101, 22, 195, 48, 111,
+        ])
+        .unwrap();
+        let signed = TransactionWrapperBuilder::new(
+            TxType::Type3,
+            U256::zero(),
+            U256::from(21_000),
+            Action::Call(Address::from_low_u64_be(0x42)),
+            U256::zero(),
+            vec![],
+        )
+        .with_chain_id(1)
+        .with_priority_fee_per_gas(U256::from(2_000_000_000u64), U256::from(1_000_000_000u64))
+        .with_blobs(U256::one(), sidecar.versioned_hashes())
+        .build()
+        .unwrap()
+        .sign(key.secret(), None)
+        .unwrap();
+        let tx = match signed.transaction {
+            UnverifiedTransactionWrapper::Eip4844(tx) => tx,
+            _ => panic!("expected tx type 3 (eip-4844)"),
+        };
+        let with_sidecar = UnverifiedEip4844TransactionWithSidecar::new(tx, sidecar).unwrap();
+        assert_eq!(with_sidecar.validate_blobs(settings), Ok(()));
+
+        // proofs swapped between the blobs
+        let swapped = BlobTransactionSidecar::new(
+            vec![vec![0u8; BYTES_PER_BLOB], blob.clone()],
+            vec![infinity.clone(), commitment.to_vec()],
+            vec![proof.to_vec(), infinity.clone()],
+        )
+        .unwrap();
+        match swapped.verify_kzg_proofs(settings) {
+            Err(Error::InvalidKzgProof(_)) => {},
+            res => panic!("unexpected result: {:?}", res),
+        }
+        // blob changed after the commitment
+        blob[31] = 1;
+        let tampered =
+            BlobTransactionSidecar::new(vec![blob], vec![commitment.to_vec()], vec![proof.to_vec()]).unwrap();
+        match tampered.verify_kzg_proofs(settings) {
+            Err(Error::InvalidKzgProof(_)) => {},
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+
+    #[cfg(feature = "kzg")]
+    #[test]
+    fn eip4844_trusted_setup_file() {
+        // mainnet setup of the consensus specs
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("res/trusted_setup.txt");
+        let settings = load_trusted_setup(&path).unwrap();
+        let mut blob = vec![0u8; BYTES_PER_BLOB];
+        for i in 0..BYTES_PER_BLOB / 32 {
+            blob[i * 32 + 31] = i as u8;
+        }
+        let kzg_blob = c_kzg::Blob::from_bytes(&blob).unwrap();
+        let commitment = c_kzg::KzgCommitment::blob_to_kzg_commitment(&kzg_blob, &settings)
+            .unwrap()
+            .to_bytes();
+        let proof = c_kzg::KzgProof::compute_blob_kzg_proof(&kzg_blob, &commitment, &settings)
+            .unwrap()
+            .to_bytes();
+        let sidecar = BlobTransactionSidecar::new(vec![blob], vec![commitment.to_vec()], vec![proof.to_vec()]).unwrap();
+        assert_eq!(sidecar.verify_kzg_proofs(&settings), Ok(()));
+        assert_eq!(sidecar.verify_kzg_proofs(c_kzg::ethereum_kzg_settings()), Ok(()));
+
+        match load_trusted_setup(std::path::Path::new("/nonexistent/trusted_setup.txt")) {
+            Err(Error::InvalidKzgProof(_)) => {},
+            res => panic!("unexpected result: {:?}", res.map(|_| ())),
+        }
+        // a setup cut after its first points
+        let truncated = std::env::temp_dir().join(format!("trusted_setup_{}.txt", std::process::id()));
+        let setup = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&truncated, setup.lines().take(10).collect::<Vec<_>>().join("\n")).unwrap();
+        let loaded = load_trusted_setup(&truncated);
+        std::fs::remove_file(&truncated).unwrap();
+        match loaded {
+            Err(Error::InvalidKzgProof(_)) => {},
+            res => panic!("unexpected result: {:?}", res.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn eip7702_sign_and_parse_tx() {
+        let key = KeyPair::from_secret_slice(&[
+            128, 148, 101, 177, 125, 10, 77, 219, 62, 76, 105, 232, 242, 60, 44, 171, 173, 134, 143, 81, 248, 190, 213,
+            199, 101, 173, 29, 101, 22, 195, 48, 111,
+        ])
+        .unwrap();
+        let authority = KeyPair::from_secret_slice(&[0x46; 32]).unwrap();
+        let delegate = Address::from_str("0x63c0c19a282a1b52b07dd5a65b58948a07dae32b").unwrap();
+        let authorization =
+            SignedAuthorization::sign(ethkey::Authorization::new(U256::one(), delegate, 0), authority.secret())
+                .unwrap();
+        assert_eq!(authorization.authority().unwrap(), authority.address());
+
+        let signed = TransactionWrapperBuilder::new(
+            TxType::Type4,
+            U256::from(3),
+            U256::from(100_000),
+            Action::Call(authority.address()),
+            U256::zero(),
+            Vec::new(),
+        )
+        .with_chain_id(1)
+        .with_priority_fee_per_gas(U256::from(2_000_000_000u64), U256::from(1_000_000_000u64))
+        .with_authorization_list(vec![authorization.clone()])
+        .build()
+        .unwrap()
+        .sign(key.secret(), None)
+        .expect("sign transaction okay");
+        assert_eq!(Address::from(keccak(key.public())), signed.sender());
+
+        let bytes = signed.encode_raw();
+        assert_eq!(bytes[0], TxType::Type4 as u8);
+        assert_eq!(keccak(&bytes), signed.tx_hash());
+
+        let unverified: UnverifiedTransactionWrapper = rlp::decode(&bytes).expect("decoding tx data failed");
+        let decoded = SignedTransaction::new(unverified).unwrap();
+        assert_eq!(decoded, signed);
+        if let UnverifiedTransactionWrapper::Eip7702(tx) = decoded.transaction {
+            assert_eq!(tx.authorization_list(), &[authorization]);
+            assert_eq!(tx.authorization_list()[0].authority().unwrap(), authority.address());
+        } else {
+            panic!("expected tx type 4 (eip-7702)");
+        }
+
+        let no_authorizations = TransactionWrapperBuilder::new(
+            TxType::Type4,
+            U256::zero(),
+            U256::from(100_000),
+            Action::Call(delegate),
+            U256::zero(),
+            Vec::new(),
+        )
+        .with_chain_id(1)
+        .with_priority_fee_per_gas(U256::one(), U256::one())
+        .build();
+        assert_eq!(
+            no_authorizations.unwrap_err(),
+            tx_builders::TxBuilderError::NoAuthorizationList
+        );
+    }
+
+    #[test]
+    fn deposit_parse_tx() {
+        use rustc_hex::FromHex;
+
+        let raw = format!(
+            "7ef852a0{}94{}94{}80018252088080",
+            "11".repeat(32),
+            "22".repeat(20),
+            "33".repeat(20)
+        );
+        let bytes: Vec<u8> = FromHex::from_hex(raw.as_str()).unwrap();
+        let unverified: UnverifiedTransactionWrapper = rlp::decode(&bytes).expect("decoding tx data failed");
+        assert_eq!(unverified.tx_hash(), keccak(&bytes));
+        assert_eq!(unverified.chain_id_from_v(), None);
+        assert_eq!(unverified.verify_basic(true, Some(10), false), Ok(()));
+
+        let expected = DepositTransaction::new(
+            H256::repeat_byte(0x11),
+            Address::repeat_byte(0x22),
+            Action::Call(Address::repeat_byte(0x33)),
+            U256::zero(),
+            U256::one(),
+            U256::from(21_000),
+            false,
+            vec![],
+        );
+        assert_eq!(unverified, UnverifiedTransactionWrapper::Deposit(expected));
+
+        let signed = SignedTransaction::new(unverified).unwrap();
+        assert_eq!(signed.sender(), Address::repeat_byte(0x22));
+        assert_eq!(signed.public_key(), None);
+        assert_eq!(signed.encode_raw(), bytes);
+    }
+
+    #[test]
+    fn arbitrum_parse_tx() {
+        use rustc_hex::FromHex;
+
+        // internal tx, chain id 42161
+        let bytes: Vec<u8> = FromHex::from_hex("6ac882a4b18401020304").unwrap();
+        let unverified: UnverifiedTransactionWrapper = rlp::decode(&bytes).expect("decoding tx data failed");
+        assert_eq!(unverified.tx_hash(), keccak(&bytes));
+        let signed = SignedTransaction::new(unverified).unwrap();
+        assert_eq!(signed.sender(), ARBOS_ADDRESS);
+        assert_eq!(signed.encode_raw(), bytes);
+        if let UnverifiedTransactionWrapper::Arbitrum(ref tx) = signed.transaction {
+            assert_eq!(tx.chain_id(), U256::from(42161));
+            assert_eq!(tx.data(), &vec![1, 2, 3, 4]);
+        } else {
+            panic!("expected arbitrum internal tx");
+        }
+
+        let from = Address::repeat_byte(0x11);
+        let to = Action::Call(Address::repeat_byte(0x22));
+        let kinds = vec![
+            ArbitrumTransactionKind::Deposit(ArbitrumDepositTransaction {
+                chain_id: 42161.into(),
+                l1_request_id: H256::repeat_byte(1),
+                from,
+                to: to.clone(),
+                value: 1000.into(),
+            }),
+            ArbitrumTransactionKind::Unsigned(ArbitrumUnsignedTransaction {
+                chain_id: 42161.into(),
+                from,
+                nonce: 7.into(),
+                gas_fee_cap: 100_000_000.into(),
+                gas: 50_000.into(),
+                to: to.clone(),
+                value: 0.into(),
+                data: vec![0xaa],
+            }),
+            ArbitrumTransactionKind::Contract(ArbitrumContractTransaction {
+                chain_id: 42161.into(),
+                request_id: H256::repeat_byte(2),
+                from,
+                gas_fee_cap: 100_000_000.into(),
+                gas: 50_000.into(),
+                to: Action::Create,
+                value: 0.into(),
+                data: vec![0x60, 0x00],
+            }),
+            ArbitrumTransactionKind::Retry(ArbitrumRetryTransaction {
+                chain_id: 42161.into(),
+                nonce: 0.into(),
+                from,
+                gas_fee_cap: 100_000_000.into(),
+                gas: 80_000.into(),
+                to: to.clone(),
+                value: 5.into(),
+                data: vec![],
+                ticket_id: H256::repeat_byte(3),
+                refund_to: Address::repeat_byte(0x33),
+                max_refund: 1_000_000.into(),
+                submission_fee_refund: 10.into(),
+            }),
+            ArbitrumTransactionKind::SubmitRetryable(ArbitrumSubmitRetryableTransaction {
+                chain_id: 42161.into(),
+                request_id: H256::repeat_byte(4),
+                from,
+                l1_base_fee: 30_000_000_000u64.into(),
+                deposit_value: 1_000_000.into(),
+                gas_fee_cap: 100_000_000.into(),
+                gas: 80_000.into(),
+                retry_to: Action::Create,
+                retry_value: 5.into(),
+                beneficiary: Address::repeat_byte(0x44),
+                max_submission_fee: 100.into(),
+                fee_refund_address: Address::repeat_byte(0x55),
+                retry_data: vec![1, 2, 3],
+            }),
+        ];
+        for kind in kinds {
+            let tx = ArbitrumTransaction::new(kind);
+            let bytes = tx.encode_raw();
+            assert_eq!(bytes[0], tx.tx_type() as u8);
+            assert_eq!(tx.hash(), keccak(&bytes));
+            let unverified: UnverifiedTransactionWrapper = rlp::decode(&bytes).expect("decoding tx data failed");
+            assert_eq!(unverified, UnverifiedTransactionWrapper::Arbitrum(tx));
+            assert_eq!(SignedTransaction::new(unverified).unwrap().sender(), from);
+        }
+    }
+
+    #[test]
+    fn cip64_sign_and_parse_tx() {
+        let key = KeyPair::from_secret_slice(&[
+            128, 148, 101, 177, 125, 10, 77, 219, 62, 76, 105, 232, 242, 60, 44, 171, 173, 134, 143, 81, 248, 190, 213,
+            199, 101, 173, 29, 101, 22, 195, 48, 111,
+        ])
+        .unwrap();
+        // cUSD on Celo mainnet
+        let fee_currency = Address::from_str("0x765de816845861e75a25fca122bb6898b8b1282a").unwrap();
+        let build = |fee_currency: Address| {
+            TransactionWrapperBuilder::new(
+                TxType::Cip64,
+                U256::from(5),
+                U256::from(100_000),
+                Action::Call(Address::repeat_byte(0x35)),
+                U256::from(1_000_000_000u64),
+                vec![],
+            )
+            .with_chain_id(42220)
+            .with_priority_fee_per_gas(U256::from(30_000_000_000u64), U256::from(1_000_000_000u64))
+            .with_fee_currency(fee_currency)
+            .build()
+            .unwrap()
+        };
+        let unsigned = build(fee_currency);
+        assert_ne!(unsigned.message_hash(None), build(Address::zero()).message_hash(None));
+
+        let signed = unsigned.sign(key.secret(), None).expect("sign transaction okay");
+        assert_eq!(Address::from(keccak(key.public())), signed.sender());
+        let celo = ChainSpec::new("celo", 42220).with_fork(Fork::London, ForkActivation::Block(0));
+        assert_eq!(
+            celo.rules_at(0, 0).verify_basic(&signed),
+            Err(Error::TxTypeNotEnabled(TxType::Cip64 as u8))
+        );
+        let celo = celo.with_extra_tx_type(TxType::Cip64 as u8);
+        assert_eq!(celo.rules_at(0, 0).verify_basic(&signed), Ok(()));
+        let bytes = signed.encode_raw();
+        assert_eq!(bytes[0], TxType::Cip64 as u8);
+        assert_eq!(keccak(&bytes), signed.tx_hash());
+
+        let unverified: UnverifiedTransactionWrapper = rlp::decode(&bytes).expect("decoding tx data failed");
+        let decoded = SignedTransaction::new(unverified).unwrap();
+        assert_eq!(decoded, signed);
+        if let UnverifiedTransactionWrapper::Cip64(tx) = decoded.transaction {
+            assert_eq!(tx.fee_currency(), fee_currency);
+            assert_eq!(tx.chain_id, 42220);
+        } else {
+            panic!("expected tx type 0x7b (cip-64)");
+        }
+    }
+
+    #[test]
+    fn zksync_sign_and_parse_tx() {
+        let key = KeyPair::from_secret_slice(&[
+            128, 148, 101, 177, 125, 10, 77, 219, 62, 76, 105, 232, 242, 60, 44, 171, 173, 134, 143, 81, 248, 190, 213,
+            199, 101, 173, 29, 101, 22, 195, 48, 111,
+        ])
+        .unwrap();
+        let from = Address::from(keccak(key.public()));
+        let paymaster = PaymasterParams {
+            paymaster: Address::repeat_byte(0x77),
+            paymaster_input: vec![0x8c, 0x5a, 0x34, 0x45],
+        };
+        let build = |from: Address, paymaster: Option<PaymasterParams>| {
+            TransactionWrapperBuilder::new(
+                TxType::ZkSyncEip712,
+                U256::from(3),
+                U256::from(300_000),
+                Action::Call(Address::repeat_byte(0x35)),
+                U256::from(1_000_000_000u64),
+                vec![],
+            )
+            .with_chain_id(324)
+            .with_priority_fee_per_gas(U256::from(250_000_000u64), U256::zero())
+            .with_zksync_params(from, None, vec![vec![0; 32]], paymaster)
+            .build()
+            .unwrap()
+        };
+        let unsigned = build(from, Some(paymaster.clone()));
+        assert_ne!(unsigned.message_hash(None), build(from, None).message_hash(None));
+
+        let signed = unsigned
+            .clone()
+            .sign(key.secret(), None)
+            .expect("sign transaction okay");
+        assert_eq!(from, signed.sender());
+        assert!(signed.public_key().is_some());
+        let bytes = signed.encode_raw();
+        assert_eq!(bytes[0], TxType::ZkSyncEip712 as u8);
+
+        let unverified: UnverifiedTransactionWrapper = rlp::decode(&bytes).expect("decoding tx data failed");
+        let decoded = SignedTransaction::new(unverified).unwrap();
+        assert_eq!(decoded, signed);
+        if let UnverifiedTransactionWrapper::ZkSyncEip712(tx) = decoded.transaction {
+            assert_eq!(tx.paymaster_params(), Some(&paymaster));
+            assert_eq!(tx.gas_per_pubdata(), DEFAULT_GAS_PER_PUBDATA_LIMIT.into());
+            assert_eq!(tx.custom_signature().len(), 65);
+            let mut message = tx.message_hash(None).as_bytes().to_vec();
+            message.extend_from_slice(keccak(tx.custom_signature()).as_bytes());
+            assert_eq!(tx.hash(), keccak(message));
+        } else {
+            panic!("expected tx type 0x71 (zksync eip-712)");
+        }
+
+        // signer must be the sender account
+        let forged = build(Address::repeat_byte(0x11), None).sign(key.secret(), None);
+        assert!(forged.is_err());
+
+        // account abstraction: the signature is checked by the account contract, so the sender
+        // is not authenticated and any `from` can be claimed
+        let custom = unsigned.with_custom_signature(vec![0xab; 100]).unwrap();
+        let bytes = custom.encode_raw();
+        let unverified: UnverifiedTransactionWrapper = rlp::decode(&bytes).expect("decoding tx data failed");
+        assert_eq!(unverified, custom);
+        assert_eq!(unverified.unauthenticated_sender(), Some(from));
+        assert_eq!(unverified.implied_sender(), None);
+        assert!(unverified.verify_basic(true, Some(324), true).is_err());
+        match SignedTransaction::new(unverified) {
+            Err(ethkey::Error::InvalidSignature) => {},
+            res => panic!("unexpected result: {:?}", res),
+        }
+        let forged = build(Address::repeat_byte(0x11), None)
+            .with_custom_signature(vec![0xab; 100])
+            .unwrap();
+        assert!(forged.verify_basic(true, Some(324), false).is_err());
+        assert!(SignedTransaction::new(forged.clone()).is_err());
+        assert_eq!(signed.unauthenticated_sender(), None);
+        let mut localized = LocalizedTransaction {
+            signed: forged,
+            block_number: 1,
+            block_hash: H256::zero(),
+            transaction_index: 0,
+            cached_sender: None,
+        };
+        assert_eq!(localized.sender(), Address::repeat_byte(0x11));
+    }
+
+    #[test]
+    fn zksync_hash_bytecode() {
+        assert_eq!(
+            hash_bytecode(&[0; 32]).unwrap(),
+            H256::from_str("01000001f862bd776c8fc18b8e9f8e20089714856ee233b3902a591d0d5f2925").unwrap()
+        );
+        assert_eq!(hash_bytecode(&[0; 96]).unwrap()[..4], [1, 0, 0, 3]);
+        // the length in words must be odd
+        assert!(hash_bytecode(&[0; 64]).is_err());
+        assert!(hash_bytecode(&[]).is_err());
+        assert!(hash_bytecode(&[0; 33]).is_err());
+
+        let build = |factory_deps: Vec<Bytes>| {
+            TransactionWrapperBuilder::new(
+                TxType::ZkSyncEip712,
+                U256::zero(),
+                U256::from(300_000),
+                Action::Call(Address::repeat_byte(0x35)),
+                U256::zero(),
+                vec![],
+            )
+            .with_chain_id(324)
+            .with_priority_fee_per_gas(U256::from(250_000_000u64), U256::zero())
+            .with_zksync_params(Address::repeat_byte(0x11), None, factory_deps, None)
+            .build()
+        };
+        assert_eq!(
+            build(vec![vec![0; 32], vec![0; 64]]),
+            Err(tx_builders::TxBuilderError::InvalidFactoryDep(1))
+        );
+        // a transaction with an invalid factory dependency is rejected on decode
+        let tx = build(vec![vec![0; 32]])
+            .unwrap()
+            .with_custom_signature(vec![0xab; 65])
+            .unwrap();
+        let bytes = tx.encode_raw();
+        let list = Rlp::new(&bytes[1..]);
+        let mut s = RlpStream::new_list(list.item_count().unwrap());
+        for (index, item) in list.iter().enumerate() {
+            if index == 13 {
+                s.append_list::<Bytes, _>(&[vec![0u8; 64]]);
+            } else {
+                s.append_raw(item.as_raw(), 1);
+            }
+        }
+        let mut invalid = vec![TxType::ZkSyncEip712 as u8];
+        invalid.extend_from_slice(&s.out());
+        assert!(rlp::decode::<UnverifiedTransactionWrapper>(&invalid).is_err());
+    }
+
+    #[test]
+    fn zksync_eip712_known_vector() {
+        // vector of the zkSync Era EIP-712 transaction test of alloy-zksync
+        let from = Address::from_str("e30f4fb40666753a7596d315f2f1f1d140d1508b").unwrap();
+        let unsigned = TransactionWrapperBuilder::new(
+            TxType::ZkSyncEip712,
+            U256::from(1),
+            U256::from(12),
+            Action::Call(Address::from_str("82112600a140ceaa9d7da373bb65453f7d99af4b").unwrap()),
+            U256::from(10),
+            vec![0x01, 0x02, 0x03],
+        )
+        .with_chain_id(270)
+        .with_priority_fee_per_gas(U256::from(11), U256::zero())
+        .with_zksync_params(from, Some(U256::from(4)), vec![vec![2; 32]], None)
+        .build()
+        .unwrap();
+        assert_eq!(
+            unsigned.message_hash(None),
+            H256::from_str("fc76820a67d9b1b351f2ac661e6d2bcca1c67508ae4930e036f540fa135875fe").unwrap()
+        );
+        let signature = Signature::from_rsv(
+            &H256::from_str("3faf83b5451ad3001f96f577b0bb5dfcaa7769ab11908f281dc6b15c45a3986f").unwrap(),
+            &H256::from_str("0325197832aac9a7ab2f5a83873834d457e0d22c1e72377d45364c6968f8ac3b").unwrap(),
+            1,
+        );
+        let signed = SignedTransaction::new(unsigned.with_signature(signature, None).unwrap()).unwrap();
+        assert_eq!(signed.sender(), from);
+        assert_eq!(
+            signed.tx_hash(),
+            H256::from_str("b85668399db249d62d06bbc59eace82e01364602fb7159e161ca810ff6ddbbf4").unwrap()
+        );
+        let decoded: UnverifiedTransactionWrapper = rlp::decode(&rlp::encode(&signed)).unwrap();
+        assert_eq!(decoded.tx_hash(), signed.tx_hash());
+    }
+
+    #[derive(Debug)]
+    struct SystemTransaction {
+        from: Address,
+        nonce: U256,
+        data: Bytes,
+    }
+
+    impl TransactionShared for SystemTransaction {
+        fn nonce(&self) -> U256 { self.nonce }
+        fn action(&self) -> &Action { &Action::Create }
+        fn value(&self) -> U256 { U256::zero() }
+        fn gas(&self) -> U256 { U256::zero() }
+        fn data(&self) -> &Bytes { &self.data }
+        fn message_hash(&self, _chain_id: Option<u64>) -> H256 { self.hash() }
+    }
+
+    impl CustomTypedTransaction for SystemTransaction {
+        fn type_byte(&self) -> u8 { 0x42 }
+        fn rlp_append_sealed_transaction(&self, s: &mut RlpStream) {
+            s.append(&self.type_byte());
+            s.begin_list(3);
+            s.append(&self.from);
+            s.append(&self.nonce);
+            s.append(&self.data);
+        }
+        fn hash(&self) -> H256 {
+            let mut stream = RlpStream::new();
+            self.rlp_append_sealed_transaction(&mut stream);
+            keccak(stream.as_raw())
+        }
+        fn sender_policy(&self) -> SenderPolicy { SenderPolicy::Implied(self.from) }
+    }
+
+    fn decode_system_tx(d: &Rlp) -> Result<UnverifiedTransactionWrapper, TxDecodeError> {
+        let list = Rlp::new(&d.as_raw()[1..]);
+        Ok(UnverifiedTransactionWrapper::Custom(CustomTransaction::new(
+            SystemTransaction {
+                from: list.val_at(0)?,
+                nonce: list.val_at(1)?,
+                data: list.val_at(2)?,
+            },
+        )))
+    }
+
+    #[test]
+    fn registry_custom_tx_type() {
+        let tx = UnverifiedTransactionWrapper::Custom(CustomTransaction::new(SystemTransaction {
+            from: Address::repeat_byte(0x42),
+            nonce: U256::from(7),
+            data: vec![1, 2, 3],
+        }));
+        let bytes = SignedTransaction::new(tx.clone()).unwrap().encode_raw();
+        assert_eq!(bytes[0], 0x42);
+        assert_eq!(tx.tx_type(), None);
+        assert_eq!(tx.type_byte(), 0x42);
+
+        let mut registry = TypedTransactionRegistry::default();
+        assert!(registry.decode(&Rlp::new(&bytes)).is_err());
+        assert_eq!(
+            registry.register(TxType::Type2 as u8, decode_system_tx),
+            Err(Error::TxTypeAlreadyRegistered(2))
+        );
+        assert_eq!(
+            registry.register(0xc0, decode_system_tx),
+            Err(Error::InvalidTxType(0xc0))
+        );
+        registry.register(0x42, decode_system_tx).unwrap();
+        let decoded = registry.decode(&Rlp::new(&bytes)).unwrap();
+        assert_eq!(decoded, tx);
+        assert_eq!(decoded.tx_hash(), keccak(&bytes));
+        let signed = SignedTransaction::new(decoded).unwrap();
+        assert_eq!(signed.sender(), Address::repeat_byte(0x42));
+        assert_eq!(signed.unsigned().nonce(), U256::from(7));
+
+        assert_eq!(registry.unregister(0x42), Ok(true));
+        assert_eq!(registry.unregister(0x42), Ok(false));
+        assert!(registry.decode(&Rlp::new(&bytes)).is_err());
+        for tx_type in [TxType::Type1, TxType::Type4, TxType::Deposit].iter() {
+            assert_eq!(
+                registry.unregister(*tx_type as u8),
+                Err(Error::BuiltinTxType(*tx_type as u8))
+            );
+            assert!(registry.is_registered(*tx_type as u8));
+        }
+        let mut empty = TypedTransactionRegistry::empty();
+        empty.register(TxType::Type2 as u8, decode_system_tx).unwrap();
+        assert_eq!(empty.unregister(TxType::Type2 as u8), Ok(true));
+
+        // the global registry is shared by the whole process, the type is removed once checked
+        register_typed_transaction(0x42, decode_system_tx).unwrap();
+        let unverified = rlp::decode::<UnverifiedTransactionWrapper>(&bytes);
+        assert_eq!(unregister_typed_transaction(0x42), Ok(true));
+        assert_eq!(unregister_typed_transaction(2), Err(Error::BuiltinTxType(2)));
+        assert_eq!(unverified.expect("decoding tx data failed"), tx);
+        assert!(rlp::decode::<UnverifiedTransactionWrapper>(&bytes).is_err());
+    }
+
+    #[test]
+    fn block_body_round_trip() {
+        let key = KeyPair::from_secret_slice(&[
+            128, 148, 101, 177, 125, 10, 77, 219, 62, 76, 105, 232, 242, 60, 44, 171, 173, 134, 143, 81, 248, 190, 213,
+            199, 101, 173, 29, 101, 22, 195, 48, 111,
+        ])
+        .unwrap();
+        let build = |tx_type: TxType| {
+            TransactionWrapperBuilder::new(
+                tx_type,
+                U256::from(1),
+                U256::from(21_000),
+                Action::Call(Address::repeat_byte(0x35)),
+                U256::from(10),
+                vec![],
+            )
+            .with_chain_id(1)
+            .with_gas_price(U256::from(1_000))
+            .with_priority_fee_per_gas(U256::from(1_000), U256::from(1))
+            .build()
+            .unwrap()
+            .sign(key.secret(), Some(1))
+            .unwrap()
+            .transaction
+        };
+        let legacy = build(TxType::Legacy);
+        let eip1559 = build(TxType::Type2);
+
+        // raw envelope encoding is the same as for signed transaction
+        let raw = eip1559.encode_raw();
+        assert_eq!(raw, SignedTransaction::new(eip1559.clone()).unwrap().encode_raw());
+        assert_eq!(raw[0], TxType::Type2 as u8);
+        assert_eq!(rlp::decode::<UnverifiedTransactionWrapper>(&raw).unwrap(), eip1559);
+
+        // typed transaction is wrapped into a byte string in a block body
+        let mut stream = RlpStream::new();
+        eip1559.rlp_append_block_item(&mut stream);
+        let wrapped = stream.out().to_vec();
+        assert_eq!(Rlp::new(&wrapped).data().unwrap(), &raw[..]);
+        let decoded: UnverifiedTransactionWrapper = rlp::decode(&wrapped).unwrap();
+        assert_eq!(decoded, eip1559);
+        assert_eq!(decoded.tx_hash(), keccak(&raw));
+        assert!(rlp::decode::<UnverifiedTransactionWrapper>(&rlp::encode(&raw[1..].to_vec())).is_err());
+
+        // `Encodable` appends the raw envelope, as taken by `eth_sendRawTransaction`
+        let signed = SignedTransaction::new(eip1559.clone()).unwrap();
+        assert_eq!(rlp::encode(&signed).to_vec(), raw);
+        assert_eq!(rlp::encode(&eip1559).to_vec(), raw);
+        match eip1559 {
+            UnverifiedTransactionWrapper::Eip1559(ref tx) => assert_eq!(rlp::encode(tx).to_vec(), raw),
+            _ => panic!("expected tx type 2 (eip-1559)"),
+        }
+
+        // a block body list is appended item by item
+        let mut stream = RlpStream::new_list(3);
+        legacy.rlp_append_block_item(&mut stream);
+        signed.rlp_append_block_item(&mut stream);
+        eip1559.rlp_append_block_item(&mut stream);
+        let list = stream.out();
+        assert_eq!(Rlp::new(&list).item_count().unwrap(), 3);
+        let decoded: Vec<UnverifiedTransactionWrapper> = rlp::decode_list(&list);
+        assert_eq!(decoded, vec![legacy.clone(), eip1559.clone(), eip1559.clone()]);
+        let decoded: Vec<SignedTransaction> = rlp::decode_list(&list);
+        assert_eq!(decoded[1], signed);
+        assert_eq!(rlp::decode::<SignedTransaction>(&raw), Ok(signed));
+        // the sender can't be recovered from a signature out of range
+        let mut items: Vec<Vec<u8>> = Rlp::new(&raw[1..]).iter().map(|item| item.as_raw().to_vec()).collect();
+        items[10] = rlp::encode(&U256::MAX).to_vec();
+        let mut stream = RlpStream::new_list(items.len());
+        for item in items.iter() {
+            stream.append_raw(item, 1);
+        }
+        let mut bad_signature = vec![TxType::Type2 as u8];
+        bad_signature.extend_from_slice(&stream.out());
+        assert!(rlp::decode::<UnverifiedTransactionWrapper>(&bad_signature).is_ok());
+        assert_eq!(
+            rlp::decode::<SignedTransaction>(&bad_signature),
+            Err(DecoderError::Custom("invalid signature"))
+        );
+
+        let txs = BlockTransactions(vec![legacy.clone(), eip1559, legacy]);
+        let encoded = rlp::encode(&txs);
+        assert_eq!(Rlp::new(&encoded).item_count().unwrap(), 3);
+        assert!(Rlp::new(&encoded).at(0).unwrap().is_list());
+        assert!(Rlp::new(&encoded).at(1).unwrap().is_data());
+        let decoded: BlockTransactions = rlp::decode(&encoded).unwrap();
+        assert_eq!(decoded, txs);
+        assert_eq!(rlp::encode(&decoded), encoded);
+    }
+
+    #[test]
+    fn strict_decoding_rejects_non_canonical_tx() {
+        let key = KeyPair::from_secret_slice(&[
+            128, 148, 101, 177, 125, 10, 77, 219, 62, 76, 105, 232, 242, 60, 44, 171, 173, 134, 143, 81, 248, 190, 213,
+            199, 101, 173, 29, 101, 22, 195, 48, 111,
+        ])
+        .unwrap();
+        let tx = TransactionWrapperBuilder::new(
+            TxType::Type2,
+            U256::from(1),
+            U256::from(53_000),
+            Action::Create,
+            U256::zero(),
+            vec![0x60],
+        )
+        .with_chain_id(1)
+        .with_priority_fee_per_gas(U256::from(1_000), U256::from(1))
+        .build()
+        .unwrap()
+        .sign(key.secret(), None)
+        .unwrap()
+        .transaction;
+        let raw = tx.encode_raw();
+        let strict =
+            |bytes: &[u8]| UnverifiedTransactionWrapper::decode_with_mode(&Rlp::new(bytes), DecodeMode::Strict);
+        let lenient =
+            |bytes: &[u8]| UnverifiedTransactionWrapper::decode_with_mode(&Rlp::new(bytes), DecodeMode::Lenient);
+        assert_eq!(strict(&raw).unwrap(), tx);
+        let mut stream = RlpStream::new();
+        tx.rlp_append_block_item(&mut stream);
+        assert_eq!(strict(&stream.out()).unwrap(), tx);
+
+        // re-encodes the envelope with the fields list modified by `f`
+        let with_items = |f: &dyn Fn(&mut Vec<Vec<u8>>)| {
+            let mut items: Vec<Vec<u8>> = Rlp::new(&raw[1..]).iter().map(|item| item.as_raw().to_vec()).collect();
+            f(&mut items);
+            let mut stream = RlpStream::new_list(items.len());
+            for item in items.iter() {
+                stream.append_raw(item, 1);
+            }
+            let mut bytes = vec![TxType::Type2 as u8];
+            bytes.extend_from_slice(&stream.out());
+            bytes
+        };
+
+        let mut trailing = raw.clone();
+        trailing.push(0);
+        assert!(lenient(&trailing).is_ok());
+        assert_eq!(
+            strict(&trailing).unwrap_err().rlp,
+            DecoderError::RlpInconsistentLengthAndData
+        );
+
+        let extra_item = with_items(&|items| items.push(vec![0x80]));
+        assert!(lenient(&extra_item).is_ok());
+        assert_eq!(strict(&extra_item).unwrap_err().rlp, DecoderError::RlpIncorrectListLen);
+
+        let missing_item = with_items(&|items| {
+            items.pop();
+        });
+        assert!(lenient(&missing_item).is_err());
+        assert!(strict(&missing_item).is_err());
+
+        // contract creation encoded as an empty list instead of empty data
+        let create_as_list = with_items(&|items| items[5] = vec![0xc0]);
+        assert!(lenient(&create_as_list).is_ok());
+        assert!(strict(&create_as_list).is_err());
+
+        // nonce 1 encoded as a one byte string
+        let long_nonce = with_items(&|items| items[1] = vec![0x81, 0x01]);
+        assert!(strict(&long_nonce).is_err());
+
+        // no panics, and anything accepted in strict mode is canonical
+        let mut mutations = Vec::new();
+        for i in 0..raw.len() {
+            mutations.push(raw[..i].to_vec());
+            for delta in [0x01u8, 0x40, 0x80, 0xff].iter() {
+                let mut mutated = raw.clone();
+                mutated[i] = mutated[i].wrapping_add(*delta);
+                mutations.push(mutated);
+            }
+        }
+        for mutated in mutations {
+            let _ = lenient(&mutated);
+            if let Ok(decoded) = strict(&mutated) {
+                assert_eq!(decoded.encode_raw(), mutated);
+                assert_eq!(decoded.tx_hash(), keccak(&mutated));
+            }
+        }
+    }
+
+    #[test]
+    fn decode_error_location() {
+        let key = KeyPair::from_secret_slice(&[
+            128, 148, 101, 177, 125, 10, 77, 219, 62, 76, 105, 232, 242, 60, 44, 171, 173, 134, 143, 81, 248, 190, 213,
+            199, 101, 173, 29, 101, 22, 195, 48, 111,
+        ])
+        .unwrap();
+        let tx = TransactionWrapperBuilder::new(
+            TxType::Type2,
+            U256::from(1),
+            U256::from(21_000),
+            Action::Call(Address::repeat_byte(0x35)),
+            U256::zero(),
+            vec![],
+        )
+        .with_chain_id(1)
+        .with_priority_fee_per_gas(U256::from(1_000), U256::from(1))
+        .build()
+        .unwrap()
+        .sign(key.secret(), None)
+        .unwrap();
+        let raw = tx.encode_raw();
+        let decode = |bytes: &[u8]| {
+            UnverifiedTransactionWrapper::decode_with_mode(&Rlp::new(bytes), DecodeMode::Strict).unwrap_err()
+        };
+
+        assert_eq!(TxType::try_from(2), Ok(TxType::Type2));
+        assert_eq!(TxType::try_from(0x55).unwrap_err().tx_type, Some(0x55));
+        let mut unknown = raw.clone();
+        unknown[0] = 0x55;
+        assert_eq!(decode(&unknown).tx_type, Some(0x55));
+        assert_eq!(decode(&unknown).rlp, DecoderError::Custom("unsupported tx version"));
+
+        // y parity must be 0 or 1
+        let list = Rlp::new(&raw[1..]);
+        let (_, v_offset) = list.at_with_offset(9).unwrap();
+        let mut bad_v = raw.clone();
+        bad_v[1 + v_offset] = 0x05;
+        let err = decode(&bad_v);
+        assert_eq!(err.tx_type, Some(2));
+        assert_eq!(err.field, Some("v"));
+        assert_eq!(err.index, Some(9));
+        assert_eq!(err.offset, Some(1 + v_offset));
+        assert_eq!(err.rlp, DecoderError::Custom("invalid sig v"));
+
+        // `to` must be empty or an address
+        let (_, to_offset) = list.at_with_offset(5).unwrap();
+        let mut bad_to = raw.clone();
+        bad_to[1 + to_offset] = 0x93;
+        let err = decode(&bad_to);
+        assert_eq!((err.field, err.index), (Some("action"), Some(5)));
+
+        // structure is kept by the transaction error
+        match Error::from(err.clone()) {
+            Error::InvalidRlp(inner) => assert_eq!(inner, err),
+            other => panic!("unexpected error {:?}", other),
+        }
+        assert!(format!("{}", err).contains("in tx type 0x02 at field action (index 5)"));
+    }
 }
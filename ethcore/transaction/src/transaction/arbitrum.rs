@@ -0,0 +1,498 @@
+//! Arbitrum Nitro specific transaction types (0x64-0x6A) encoding/decoding
+
+use super::SignedTransactionShared;
+use super::{Action, Bytes, TransactionShared, TxFields, TxType};
+use crate::TxDecodeError;
+use ethereum_types::{Address, H160, H256, U256};
+use rlp::{self, DecoderError, Rlp, RlpStream};
+use std::convert::TryFrom;
+
+/// ArbOS system address, sender of the internal transactions.
+pub const ARBOS_ADDRESS: Address = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0a, 0x4b,
+    0x05,
+]);
+
+/// Address of the ArbRetryableTx precompile, target of the submit retryable transactions.
+pub const ARB_RETRYABLE_TX_ADDRESS: Address = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x6e,
+]);
+
+static ARBOS_ACTION: Action = Action::Call(ARBOS_ADDRESS);
+static EMPTY_DATA: Bytes = Vec::new();
+
+/// L1 to L2 ETH deposit (type 0x64).
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ArbitrumDepositTransaction {
+    pub chain_id: U256,
+    pub l1_request_id: H256,
+    pub from: Address,
+    /// Receiver of the deposit, always a call.
+    pub to: Action,
+    pub value: U256,
+}
+
+/// L1 originated call without signature (type 0x65).
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ArbitrumUnsignedTransaction {
+    pub chain_id: U256,
+    pub from: Address,
+    pub nonce: U256,
+    pub gas_fee_cap: U256,
+    pub gas: U256,
+    pub to: Action,
+    pub value: U256,
+    pub data: Bytes,
+}
+
+/// L1 originated call identified by its request id (type 0x66).
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ArbitrumContractTransaction {
+    pub chain_id: U256,
+    pub request_id: H256,
+    pub from: Address,
+    pub gas_fee_cap: U256,
+    pub gas: U256,
+    pub to: Action,
+    pub value: U256,
+    pub data: Bytes,
+}
+
+/// Redemption of a retryable ticket (type 0x68).
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ArbitrumRetryTransaction {
+    pub chain_id: U256,
+    pub nonce: U256,
+    pub from: Address,
+    pub gas_fee_cap: U256,
+    pub gas: U256,
+    pub to: Action,
+    pub value: U256,
+    pub data: Bytes,
+    pub ticket_id: H256,
+    pub refund_to: Address,
+    pub max_refund: U256,
+    pub submission_fee_refund: U256,
+}
+
+/// Creation of a retryable ticket from L1 (type 0x69).
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ArbitrumSubmitRetryableTransaction {
+    pub chain_id: U256,
+    pub request_id: H256,
+    pub from: Address,
+    pub l1_base_fee: U256,
+    pub deposit_value: U256,
+    pub gas_fee_cap: U256,
+    pub gas: U256,
+    pub retry_to: Action,
+    pub retry_value: U256,
+    pub beneficiary: Address,
+    pub max_submission_fee: U256,
+    pub fee_refund_address: Address,
+    pub retry_data: Bytes,
+}
+
+/// ArbOS internal state update (type 0x6A).
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ArbitrumInternalTransaction {
+    pub chain_id: U256,
+    pub data: Bytes,
+}
+
+impl rlp::Encodable for ArbitrumDepositTransaction {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(5);
+        s.append(&self.chain_id);
+        s.append(&self.l1_request_id);
+        s.append(&self.from);
+        s.append(&self.to);
+        s.append(&self.value);
+    }
+}
+
+impl rlp::Decodable for ArbitrumDepositTransaction {
+    fn decode(d: &Rlp) -> Result<Self, DecoderError> {
+        Ok(Self::decode_fields(&TxFields::new(Rlp::new(d.as_raw()), None, 0))?)
+    }
+}
+
+impl ArbitrumDepositTransaction {
+    pub(crate) fn decode_fields(list: &TxFields) -> Result<Self, TxDecodeError> {
+        Ok(ArbitrumDepositTransaction {
+            chain_id: list.val_at(0, "chain_id")?,
+            l1_request_id: list.val_at(1, "l1_request_id")?,
+            from: list.val_at(2, "from")?,
+            to: Action::Call(list.val_at(3, "to")?),
+            value: list.val_at(4, "value")?,
+        })
+    }
+}
+
+impl rlp::Encodable for ArbitrumUnsignedTransaction {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(8);
+        s.append(&self.chain_id);
+        s.append(&self.from);
+        s.append(&self.nonce);
+        s.append(&self.gas_fee_cap);
+        s.append(&self.gas);
+        s.append(&self.to);
+        s.append(&self.value);
+        s.append(&self.data);
+    }
+}
+
+impl rlp::Decodable for ArbitrumUnsignedTransaction {
+    fn decode(d: &Rlp) -> Result<Self, DecoderError> {
+        Ok(Self::decode_fields(&TxFields::new(Rlp::new(d.as_raw()), None, 0))?)
+    }
+}
+
+impl ArbitrumUnsignedTransaction {
+    pub(crate) fn decode_fields(list: &TxFields) -> Result<Self, TxDecodeError> {
+        Ok(ArbitrumUnsignedTransaction {
+            chain_id: list.val_at(0, "chain_id")?,
+            from: list.val_at(1, "from")?,
+            nonce: list.val_at(2, "nonce")?,
+            gas_fee_cap: list.val_at(3, "gas_fee_cap")?,
+            gas: list.val_at(4, "gas")?,
+            to: list.val_at(5, "to")?,
+            value: list.val_at(6, "value")?,
+            data: list.val_at(7, "data")?,
+        })
+    }
+}
+
+impl rlp::Encodable for ArbitrumContractTransaction {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(8);
+        s.append(&self.chain_id);
+        s.append(&self.request_id);
+        s.append(&self.from);
+        s.append(&self.gas_fee_cap);
+        s.append(&self.gas);
+        s.append(&self.to);
+        s.append(&self.value);
+        s.append(&self.data);
+    }
+}
+
+impl rlp::Decodable for ArbitrumContractTransaction {
+    fn decode(d: &Rlp) -> Result<Self, DecoderError> {
+        Ok(Self::decode_fields(&TxFields::new(Rlp::new(d.as_raw()), None, 0))?)
+    }
+}
+
+impl ArbitrumContractTransaction {
+    pub(crate) fn decode_fields(list: &TxFields) -> Result<Self, TxDecodeError> {
+        Ok(ArbitrumContractTransaction {
+            chain_id: list.val_at(0, "chain_id")?,
+            request_id: list.val_at(1, "request_id")?,
+            from: list.val_at(2, "from")?,
+            gas_fee_cap: list.val_at(3, "gas_fee_cap")?,
+            gas: list.val_at(4, "gas")?,
+            to: list.val_at(5, "to")?,
+            value: list.val_at(6, "value")?,
+            data: list.val_at(7, "data")?,
+        })
+    }
+}
+
+impl rlp::Encodable for ArbitrumRetryTransaction {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(12);
+        s.append(&self.chain_id);
+        s.append(&self.nonce);
+        s.append(&self.from);
+        s.append(&self.gas_fee_cap);
+        s.append(&self.gas);
+        s.append(&self.to);
+        s.append(&self.value);
+        s.append(&self.data);
+        s.append(&self.ticket_id);
+        s.append(&self.refund_to);
+        s.append(&self.max_refund);
+        s.append(&self.submission_fee_refund);
+    }
+}
+
+impl rlp::Decodable for ArbitrumRetryTransaction {
+    fn decode(d: &Rlp) -> Result<Self, DecoderError> {
+        Ok(Self::decode_fields(&TxFields::new(Rlp::new(d.as_raw()), None, 0))?)
+    }
+}
+
+impl ArbitrumRetryTransaction {
+    pub(crate) fn decode_fields(list: &TxFields) -> Result<Self, TxDecodeError> {
+        Ok(ArbitrumRetryTransaction {
+            chain_id: list.val_at(0, "chain_id")?,
+            nonce: list.val_at(1, "nonce")?,
+            from: list.val_at(2, "from")?,
+            gas_fee_cap: list.val_at(3, "gas_fee_cap")?,
+            gas: list.val_at(4, "gas")?,
+            to: list.val_at(5, "to")?,
+            value: list.val_at(6, "value")?,
+            data: list.val_at(7, "data")?,
+            ticket_id: list.val_at(8, "ticket_id")?,
+            refund_to: list.val_at(9, "refund_to")?,
+            max_refund: list.val_at(10, "max_refund")?,
+            submission_fee_refund: list.val_at(11, "submission_fee_refund")?,
+        })
+    }
+}
+
+impl rlp::Encodable for ArbitrumSubmitRetryableTransaction {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(13);
+        s.append(&self.chain_id);
+        s.append(&self.request_id);
+        s.append(&self.from);
+        s.append(&self.l1_base_fee);
+        s.append(&self.deposit_value);
+        s.append(&self.gas_fee_cap);
+        s.append(&self.gas);
+        s.append(&self.retry_to);
+        s.append(&self.retry_value);
+        s.append(&self.beneficiary);
+        s.append(&self.max_submission_fee);
+        s.append(&self.fee_refund_address);
+        s.append(&self.retry_data);
+    }
+}
+
+impl rlp::Decodable for ArbitrumSubmitRetryableTransaction {
+    fn decode(d: &Rlp) -> Result<Self, DecoderError> {
+        Ok(Self::decode_fields(&TxFields::new(Rlp::new(d.as_raw()), None, 0))?)
+    }
+}
+
+impl ArbitrumSubmitRetryableTransaction {
+    pub(crate) fn decode_fields(list: &TxFields) -> Result<Self, TxDecodeError> {
+        Ok(ArbitrumSubmitRetryableTransaction {
+            chain_id: list.val_at(0, "chain_id")?,
+            request_id: list.val_at(1, "request_id")?,
+            from: list.val_at(2, "from")?,
+            l1_base_fee: list.val_at(3, "l1_base_fee")?,
+            deposit_value: list.val_at(4, "deposit_value")?,
+            gas_fee_cap: list.val_at(5, "gas_fee_cap")?,
+            gas: list.val_at(6, "gas")?,
+            retry_to: list.val_at(7, "retry_to")?,
+            retry_value: list.val_at(8, "retry_value")?,
+            beneficiary: list.val_at(9, "beneficiary")?,
+            max_submission_fee: list.val_at(10, "max_submission_fee")?,
+            fee_refund_address: list.val_at(11, "fee_refund_address")?,
+            retry_data: list.val_at(12, "retry_data")?,
+        })
+    }
+}
+
+impl rlp::Encodable for ArbitrumInternalTransaction {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(2);
+        s.append(&self.chain_id);
+        s.append(&self.data);
+    }
+}
+
+impl rlp::Decodable for ArbitrumInternalTransaction {
+    fn decode(d: &Rlp) -> Result<Self, DecoderError> {
+        Ok(Self::decode_fields(&TxFields::new(Rlp::new(d.as_raw()), None, 0))?)
+    }
+}
+
+impl ArbitrumInternalTransaction {
+    pub(crate) fn decode_fields(list: &TxFields) -> Result<Self, TxDecodeError> {
+        Ok(ArbitrumInternalTransaction {
+            chain_id: list.val_at(0, "chain_id")?,
+            data: list.val_at(1, "data")?,
+        })
+    }
+}
+
+/// Arbitrum transaction types which are not signed by the sender.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArbitrumTransactionKind {
+    Deposit(ArbitrumDepositTransaction),
+    Unsigned(ArbitrumUnsignedTransaction),
+    Contract(ArbitrumContractTransaction),
+    Retry(ArbitrumRetryTransaction),
+    SubmitRetryable(ArbitrumSubmitRetryableTransaction),
+    Internal(ArbitrumInternalTransaction),
+}
+
+/// Arbitrum specific transaction with its hash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArbitrumTransaction {
+    /// Transaction of one of the Arbitrum types.
+    kind: ArbitrumTransactionKind,
+    /// Hash of the transaction
+    hash: H256,
+}
+
+impl ArbitrumTransaction {
+    pub fn new(kind: ArbitrumTransactionKind) -> Self {
+        ArbitrumTransaction {
+            kind,
+            hash: H256::zero(),
+        }
+        .compute_hash()
+    }
+
+    pub fn kind(&self) -> &ArbitrumTransactionKind { &self.kind }
+
+    pub fn tx_type(&self) -> TxType {
+        match self.kind {
+            ArbitrumTransactionKind::Deposit(_) => TxType::ArbitrumDeposit,
+            ArbitrumTransactionKind::Unsigned(_) => TxType::ArbitrumUnsigned,
+            ArbitrumTransactionKind::Contract(_) => TxType::ArbitrumContract,
+            ArbitrumTransactionKind::Retry(_) => TxType::ArbitrumRetry,
+            ArbitrumTransactionKind::SubmitRetryable(_) => TxType::ArbitrumSubmitRetryable,
+            ArbitrumTransactionKind::Internal(_) => TxType::ArbitrumInternal,
+        }
+    }
+
+    /// Sender implied by the transaction type, as these transactions carry no signature.
+    pub fn sender(&self) -> Address {
+        match &self.kind {
+            ArbitrumTransactionKind::Deposit(tx) => tx.from,
+            ArbitrumTransactionKind::Unsigned(tx) => tx.from,
+            ArbitrumTransactionKind::Contract(tx) => tx.from,
+            ArbitrumTransactionKind::Retry(tx) => tx.from,
+            ArbitrumTransactionKind::SubmitRetryable(tx) => tx.from,
+            ArbitrumTransactionKind::Internal(_) => ARBOS_ADDRESS,
+        }
+    }
+
+    pub fn chain_id(&self) -> U256 {
+        match &self.kind {
+            ArbitrumTransactionKind::Deposit(tx) => tx.chain_id,
+            ArbitrumTransactionKind::Unsigned(tx) => tx.chain_id,
+            ArbitrumTransactionKind::Contract(tx) => tx.chain_id,
+            ArbitrumTransactionKind::Retry(tx) => tx.chain_id,
+            ArbitrumTransactionKind::SubmitRetryable(tx) => tx.chain_id,
+            ArbitrumTransactionKind::Internal(tx) => tx.chain_id,
+        }
+    }
+
+    /// Max fee per gas, zero for types not paying for L2 gas.
+    pub fn gas_fee_cap(&self) -> U256 {
+        match &self.kind {
+            ArbitrumTransactionKind::Unsigned(tx) => tx.gas_fee_cap,
+            ArbitrumTransactionKind::Contract(tx) => tx.gas_fee_cap,
+            ArbitrumTransactionKind::Retry(tx) => tx.gas_fee_cap,
+            ArbitrumTransactionKind::SubmitRetryable(tx) => tx.gas_fee_cap,
+            ArbitrumTransactionKind::Deposit(_) | ArbitrumTransactionKind::Internal(_) => U256::zero(),
+        }
+    }
+
+    pub fn hash(&self) -> H256 { self.hash }
+}
+
+impl TransactionShared for ArbitrumTransaction {
+    fn nonce(&self) -> U256 {
+        match &self.kind {
+            ArbitrumTransactionKind::Unsigned(tx) => tx.nonce,
+            ArbitrumTransactionKind::Retry(tx) => tx.nonce,
+            _ => U256::zero(),
+        }
+    }
+    /// For submit retryable transactions this is the target of the retryable ticket.
+    fn action(&self) -> &Action {
+        match &self.kind {
+            ArbitrumTransactionKind::Deposit(tx) => &tx.to,
+            ArbitrumTransactionKind::Unsigned(tx) => &tx.to,
+            ArbitrumTransactionKind::Contract(tx) => &tx.to,
+            ArbitrumTransactionKind::Retry(tx) => &tx.to,
+            ArbitrumTransactionKind::SubmitRetryable(tx) => &tx.retry_to,
+            ArbitrumTransactionKind::Internal(_) => &ARBOS_ACTION,
+        }
+    }
+    fn value(&self) -> U256 {
+        match &self.kind {
+            ArbitrumTransactionKind::Deposit(tx) => tx.value,
+            ArbitrumTransactionKind::Unsigned(tx) => tx.value,
+            ArbitrumTransactionKind::Contract(tx) => tx.value,
+            ArbitrumTransactionKind::Retry(tx) => tx.value,
+            ArbitrumTransactionKind::SubmitRetryable(tx) => tx.retry_value,
+            ArbitrumTransactionKind::Internal(_) => U256::zero(),
+        }
+    }
+    fn gas(&self) -> U256 {
+        match &self.kind {
+            ArbitrumTransactionKind::Unsigned(tx) => tx.gas,
+            ArbitrumTransactionKind::Contract(tx) => tx.gas,
+            ArbitrumTransactionKind::Retry(tx) => tx.gas,
+            ArbitrumTransactionKind::SubmitRetryable(tx) => tx.gas,
+            ArbitrumTransactionKind::Deposit(_) | ArbitrumTransactionKind::Internal(_) => U256::zero(),
+        }
+    }
+    fn data(&self) -> &Bytes {
+        match &self.kind {
+            ArbitrumTransactionKind::Unsigned(tx) => &tx.data,
+            ArbitrumTransactionKind::Contract(tx) => &tx.data,
+            ArbitrumTransactionKind::Retry(tx) => &tx.data,
+            ArbitrumTransactionKind::SubmitRetryable(tx) => &tx.retry_data,
+            ArbitrumTransactionKind::Internal(tx) => &tx.data,
+            ArbitrumTransactionKind::Deposit(_) => &EMPTY_DATA,
+        }
+    }
+    /// These transactions are not signed, so this is the transaction hash.
+    fn message_hash(&self, _chain_id: Option<u64>) -> H256 { self.hash }
+}
+
+impl rlp::Decodable for ArbitrumTransaction {
+    fn decode(d: &Rlp) -> Result<Self, DecoderError> { Ok(Self::decode_tx(d)?) }
+}
+
+impl ArbitrumTransaction {
+    pub(crate) fn decode_tx(d: &Rlp) -> Result<Self, TxDecodeError> {
+        let list = TxFields::envelope(d)?;
+        let kind = match list.tx_type().map(TxType::try_from) {
+            Some(Ok(TxType::ArbitrumDeposit)) => {
+                ArbitrumTransactionKind::Deposit(ArbitrumDepositTransaction::decode_fields(&list)?)
+            },
+            Some(Ok(TxType::ArbitrumUnsigned)) => {
+                ArbitrumTransactionKind::Unsigned(ArbitrumUnsignedTransaction::decode_fields(&list)?)
+            },
+            Some(Ok(TxType::ArbitrumContract)) => {
+                ArbitrumTransactionKind::Contract(ArbitrumContractTransaction::decode_fields(&list)?)
+            },
+            Some(Ok(TxType::ArbitrumRetry)) => {
+                ArbitrumTransactionKind::Retry(ArbitrumRetryTransaction::decode_fields(&list)?)
+            },
+            Some(Ok(TxType::ArbitrumSubmitRetryable)) => {
+                ArbitrumTransactionKind::SubmitRetryable(ArbitrumSubmitRetryableTransaction::decode_fields(&list)?)
+            },
+            Some(Ok(TxType::ArbitrumInternal)) => {
+                ArbitrumTransactionKind::Internal(ArbitrumInternalTransaction::decode_fields(&list)?)
+            },
+            _ => return Err(list.envelope_error(DecoderError::Custom("bad tx version"))),
+        };
+        Ok(ArbitrumTransaction {
+            kind,
+            hash: list.hash(),
+        })
+    }
+}
+
+impl rlp::Encodable for ArbitrumTransaction {
+    fn rlp_append(&self, s: &mut RlpStream) { self.rlp_append_sealed_transaction(s) }
+}
+
+impl SignedTransactionShared for ArbitrumTransaction {
+    fn rlp_append_sealed_transaction(&self, s: &mut RlpStream) {
+        s.append(&(self.tx_type() as u8));
+        match &self.kind {
+            ArbitrumTransactionKind::Deposit(tx) => s.append(tx),
+            ArbitrumTransactionKind::Unsigned(tx) => s.append(tx),
+            ArbitrumTransactionKind::Contract(tx) => s.append(tx),
+            ArbitrumTransactionKind::Retry(tx) => s.append(tx),
+            ArbitrumTransactionKind::SubmitRetryable(tx) => s.append(tx),
+            ArbitrumTransactionKind::Internal(tx) => s.append(tx),
+        };
+    }
+
+    fn set_hash(&mut self, hash: H256) { self.hash = hash; }
+}
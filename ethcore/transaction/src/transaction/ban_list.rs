@@ -0,0 +1,166 @@
+//! Bans of senders, recipients and contract code, checked on transaction admission
+
+use super::{Action, UnverifiedTransactionWrapper};
+use crate::{BanListError, Error};
+use ethereum_types::{Address, H256};
+use hash::keccak;
+use serde_json::Value;
+use std::{collections::HashMap,
+          fs,
+          path::Path,
+          str::FromStr,
+          time::{SystemTime, UNIX_EPOCH}};
+
+/// What a ban applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BanKey {
+    /// Transactions sent by the address
+    Sender(Address),
+    /// Calls to the address
+    Recipient(Address),
+    /// Contract creations with init code of the keccak hash
+    Code(H256),
+}
+
+impl BanKey {
+    fn parse(kind: &str, value: &str) -> Result<Self, String> {
+        let invalid = |_| format!("invalid {} {}", kind, value);
+        match kind {
+            "sender" => Address::from_str(value).map(BanKey::Sender).map_err(invalid),
+            "recipient" => Address::from_str(value).map(BanKey::Recipient).map_err(invalid),
+            "code" => H256::from_str(value).map(BanKey::Code).map_err(invalid),
+            _ => Err(format!("unknown ban kind {}", kind)),
+        }
+    }
+}
+
+/// Set of bans, each one permanent or lasting until a unix timestamp in seconds.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BanList {
+    bans: HashMap<BanKey, Option<u64>>,
+}
+
+impl BanList {
+    pub fn new() -> Self { BanList::default() }
+
+    /// Bans the key until the unix timestamp, permanently if `None`.
+    /// A permanent ban is not shortened by a timed one.
+    pub fn ban(&mut self, key: BanKey, until: Option<u64>) {
+        let ban = self.bans.entry(key).or_insert(until);
+        *ban = match (*ban, until) {
+            (Some(current), Some(until)) => Some(current.max(until)),
+            _ => None,
+        };
+    }
+
+    /// Lifts the ban, returns whether the key was banned.
+    pub fn unban(&mut self, key: &BanKey) -> bool { self.bans.remove(key).is_some() }
+
+    pub fn is_banned(&self, key: &BanKey, now: u64) -> bool {
+        match self.bans.get(key) {
+            Some(Some(until)) => now < *until,
+            Some(None) => true,
+            None => false,
+        }
+    }
+
+    /// Removes bans expired at `now`.
+    pub fn prune(&mut self, now: u64) { self.bans.retain(|_, until| until.map_or(true, |until| now < until)); }
+
+    pub fn len(&self) -> usize { self.bans.len() }
+
+    pub fn is_empty(&self) -> bool { self.bans.is_empty() }
+
+    /// Checks the recipient and the init code of the transaction at the current time.
+    pub fn verify(&self, tx: &UnverifiedTransactionWrapper) -> Result<(), Error> { self.verify_at(tx, unix_now()) }
+
+    /// Checks the recipient and the init code of the transaction at the unix timestamp `now`.
+    pub fn verify_at(&self, tx: &UnverifiedTransactionWrapper, now: u64) -> Result<(), Error> {
+        let unsigned = tx.unsigned();
+        match unsigned.action() {
+            Action::Call(to) if self.is_banned(&BanKey::Recipient(*to), now) => Err(Error::RecipientBanned),
+            Action::Create if self.is_banned(&BanKey::Code(keccak(unsigned.data())), now) => Err(Error::CodeBanned),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the sender at the current time.
+    pub fn verify_sender(&self, sender: &Address) -> Result<(), Error> { self.verify_sender_at(sender, unix_now()) }
+
+    /// Checks the sender at the unix timestamp `now`.
+    pub fn verify_sender_at(&self, sender: &Address, now: u64) -> Result<(), Error> {
+        if self.is_banned(&BanKey::Sender(*sender), now) {
+            return Err(Error::SenderBanned);
+        }
+        Ok(())
+    }
+
+    /// Loads bans from a JSON array of `{"kind": .., "value": .., "until": ..}` objects,
+    /// `kind` being `sender`, `recipient` or `code` and `until` optional.
+    pub fn from_json(json: &str) -> Result<Self, BanListError> {
+        let entries = match json.parse::<Value>() {
+            Ok(Value::Array(entries)) => entries,
+            Ok(_) => return Err(BanListError::InvalidJson("expected an array".into())),
+            Err(err) => return Err(BanListError::InvalidJson(err.to_string())),
+        };
+        let mut list = BanList::new();
+        for (index, entry) in entries.iter().enumerate() {
+            let invalid = |reason: String| BanListError::InvalidEntry { index, reason };
+            let field = |name| entry.get(name).and_then(Value::as_str);
+            let kind = field("kind").ok_or_else(|| invalid("missing kind".into()))?;
+            let value = field("value").ok_or_else(|| invalid("missing value".into()))?;
+            let until = match entry.get("until") {
+                None | Some(Value::Null) => None,
+                Some(until) => Some(
+                    until
+                        .as_u64()
+                        .ok_or_else(|| invalid(format!("invalid until {}", until)))?,
+                ),
+            };
+            list.ban(BanKey::parse(kind, value).map_err(invalid)?, until);
+        }
+        Ok(list)
+    }
+
+    /// Loads bans from `kind,value,until` lines, `until` may be empty. A header line
+    /// starting with `kind`, empty lines and `#` comments are skipped.
+    pub fn from_csv(csv: &str) -> Result<Self, BanListError> {
+        let mut list = BanList::new();
+        for (index, line) in csv.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || (index == 0 && line.starts_with("kind")) {
+                continue;
+            }
+            // lines are numbered from one, as editors do
+            let invalid = |reason: String| BanListError::InvalidEntry {
+                index: index + 1,
+                reason,
+            };
+            let mut columns = line.split(',').map(str::trim);
+            let kind = columns.next().unwrap_or_default();
+            let value = columns.next().ok_or_else(|| invalid("missing value".into()))?;
+            let until = match columns.next() {
+                None | Some("") => None,
+                Some(until) => Some(until.parse().map_err(|_| invalid(format!("invalid until {}", until)))?),
+            };
+            list.ban(BanKey::parse(kind, value).map_err(invalid)?, until);
+        }
+        Ok(list)
+    }
+
+    /// Loads bans from a file, JSON if its extension is `json` and CSV otherwise.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, BanListError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|err| BanListError::Io(err.to_string()))?;
+        match path.extension() {
+            Some(ext) if ext == "json" => BanList::from_json(&content),
+            _ => BanList::from_csv(&content),
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
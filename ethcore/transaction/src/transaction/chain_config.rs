@@ -0,0 +1,350 @@
+//! Chain spec loading from geth `genesis.json` and legacy Parity chainspec files
+
+use super::{ChainSpec, Fork, ForkActivation, TxType};
+use crate::ChainSpecError;
+use ethereum_types::U256;
+use serde_json::{Map, Value};
+
+/// Fork keys of a geth genesis `config`. Post-Merge forks activate by timestamp
+/// on Ethereum and by block on Polygon. Avalanche upgrades activate by timestamp
+/// and bring the Ethereum forks changing transaction validity.
+const GETH_FORK_KEYS: &[(&str, Fork)] = &[
+    ("homesteadBlock", Fork::Homestead),
+    ("eip150Block", Fork::TangerineWhistle),
+    ("eip155Block", Fork::SpuriousDragon),
+    ("byzantiumBlock", Fork::Byzantium),
+    ("constantinopleBlock", Fork::Constantinople),
+    ("petersburgBlock", Fork::Petersburg),
+    ("istanbulBlock", Fork::Istanbul),
+    ("muirGlacierBlock", Fork::MuirGlacier),
+    ("berlinBlock", Fork::Berlin),
+    ("londonBlock", Fork::London),
+    ("arrowGlacierBlock", Fork::ArrowGlacier),
+    ("grayGlacierBlock", Fork::GrayGlacier),
+    ("mergeNetsplitBlock", Fork::Paris),
+    ("shanghaiTime", Fork::Shanghai),
+    ("shanghaiBlock", Fork::Shanghai),
+    ("cancunTime", Fork::Cancun),
+    ("cancunBlock", Fork::Cancun),
+    ("pragueTime", Fork::Prague),
+    ("pragueBlock", Fork::Prague),
+    ("osakaTime", Fork::Osaka),
+    ("osakaBlock", Fork::Osaka),
+    ("apricotPhase2BlockTimestamp", Fork::Berlin),
+    ("apricotPhase3BlockTimestamp", Fork::London),
+    ("durangoBlockTimestamp", Fork::Shanghai),
+    ("etnaTimestamp", Fork::Cancun),
+];
+
+/// Fork keys of a geth genesis `config` which don't change transaction validity.
+const GETH_IGNORED_FORK_KEYS: &[&str] = &[
+    "daoForkBlock",
+    // State clearing, activated along with EIP-155
+    "eip158Block",
+    // Blob parameter only forks
+    "bpo1Time",
+    "bpo2Time",
+    "bpo3Time",
+    "bpo4Time",
+    "bpo5Time",
+    // BSC Parlia forks, their EVM changes come with the Ethereum forks above
+    "ramanujanBlock",
+    "nielsBlock",
+    "mirrorSyncBlock",
+    "brunoBlock",
+    "eulerBlock",
+    "gibbsBlock",
+    "nanoBlock",
+    "moranBlock",
+    "planckBlock",
+    "lubanBlock",
+    "platoBlock",
+    "hertzBlock",
+    "hertzfixBlock",
+    "keplerTime",
+    "feynmanTime",
+    "feynmanFixTime",
+    "haberTime",
+    "haberFixTime",
+    "bohrTime",
+    "pascalTime",
+    "lorentzTime",
+    "maxwellTime",
+    "fermiTime",
+    // Avalanche upgrades changing fees, gas limits and precompiles only
+    "apricotPhase1BlockTimestamp",
+    "apricotPhase4BlockTimestamp",
+    "apricotPhase5BlockTimestamp",
+    "apricotPhasePre6BlockTimestamp",
+    "apricotPhase6BlockTimestamp",
+    "apricotPhasePost6BlockTimestamp",
+    "banffBlockTimestamp",
+    "cortinaBlockTimestamp",
+    "fortunaTimestamp",
+    "graniteTimestamp",
+    // OP-Stack upgrades, their EVM changes come with the Ethereum forks above
+    "bedrockBlock",
+    "regolithTime",
+    "canyonTime",
+    "ecotoneTime",
+    "fjordTime",
+    "graniteTime",
+    "holoceneTime",
+    "isthmusTime",
+    "jovianTime",
+];
+
+/// Keys of the geth `blobSchedule` config.
+const GETH_BLOB_SCHEDULE_KEYS: &[(&str, Fork)] = &[
+    ("cancun", Fork::Cancun),
+    ("prague", Fork::Prague),
+    ("osaka", Fork::Osaka),
+];
+
+/// Transition keys of Parity chainspec `params`, each one is the first EIP of a fork
+/// changing transaction validity or the last EIP of the fork otherwise.
+const PARITY_FORK_KEYS: &[(&str, Fork)] = &[
+    ("eip150Transition", Fork::TangerineWhistle),
+    ("eip155Transition", Fork::SpuriousDragon),
+    ("eip140Transition", Fork::Byzantium),
+    ("eip145Transition", Fork::Constantinople),
+    ("eip1283DisableTransition", Fork::Petersburg),
+    ("eip2028Transition", Fork::Istanbul),
+    ("eip2929Transition", Fork::Berlin),
+    ("eip1559Transition", Fork::London),
+    ("mergeForkIdTransition", Fork::Paris),
+    ("eip3860TransitionTimestamp", Fork::Shanghai),
+    ("eip4844TransitionTimestamp", Fork::Cancun),
+    ("eip7702TransitionTimestamp", Fork::Prague),
+    ("eip7825TransitionTimestamp", Fork::Osaka),
+];
+
+/// Transition keys of Parity chainspec `params` activated along with a key above.
+const PARITY_IGNORED_FORK_KEYS: &[&str] = &[
+    "maxCodeSizeTransition",
+    "eip98Transition",
+    "eip160Transition",
+    "eip161abcTransition",
+    "eip161dTransition",
+    "eip211Transition",
+    "eip214Transition",
+    "eip658Transition",
+    "eip1014Transition",
+    "eip1052Transition",
+    "eip1283Transition",
+    "eip1283ReenableTransition",
+    "eip1344Transition",
+    "eip1706Transition",
+    "eip1884Transition",
+    "eip2200Transition",
+    "eip2930Transition",
+    "eip3198Transition",
+    "eip3529Transition",
+    "eip3541Transition",
+    "eip3607Transition",
+    "eip1559BaseFeeMinValueTransition",
+    "eip1559FeeCollectorTransition",
+    "validateChainIdTransition",
+    "validateReceiptsTransition",
+    "dustProtectionTransition",
+    "wasmActivationTransition",
+    "kip4Transition",
+    "kip6Transition",
+    "transactionPermissionContractTransition",
+    "eip3651TransitionTimestamp",
+    "eip3855TransitionTimestamp",
+    "eip4895TransitionTimestamp",
+    "eip1153TransitionTimestamp",
+    "eip4788TransitionTimestamp",
+    "eip5656TransitionTimestamp",
+    "eip6780TransitionTimestamp",
+    "eip7516TransitionTimestamp",
+    "eip2537TransitionTimestamp",
+    "eip2935TransitionTimestamp",
+    "eip6110TransitionTimestamp",
+    "eip7002TransitionTimestamp",
+    "eip7251TransitionTimestamp",
+    "eip7623TransitionTimestamp",
+    "eip7685TransitionTimestamp",
+    "eip7691TransitionTimestamp",
+    "eip7594TransitionTimestamp",
+    "eip7823TransitionTimestamp",
+    "eip7883TransitionTimestamp",
+    "eip7918TransitionTimestamp",
+    "eip7934TransitionTimestamp",
+    "eip7939TransitionTimestamp",
+    "eip7951TransitionTimestamp",
+];
+
+impl ChainSpec {
+    /// Loads chain id, fork activations and fee parameters from a geth `genesis.json`, the name
+    /// is the one of the preset with the same chain id. Polygon Bor and Avalanche genesis files disable
+    /// blob transactions, OP-Stack ones enable deposit transactions in place of them.
+    pub fn from_geth_genesis(json: &str) -> Result<Self, ChainSpecError> {
+        let genesis = parse_json(json)?;
+        let config = genesis
+            .get("config")
+            .ok_or(ChainSpecError::MissingField("config"))?
+            .as_object()
+            .ok_or_else(|| invalid_field("config"))?;
+        let chain_id = config
+            .get("chainId")
+            .ok_or(ChainSpecError::MissingField("config.chainId"))
+            .and_then(|value| parse_u64("config.chainId", value))?;
+
+        let name = ChainSpec::from_chain_id(chain_id).map_or_else(String::new, |preset| preset.name);
+        let mut spec = ChainSpec::new(&name, chain_id);
+        let mut unknown_keys = Vec::new();
+        for (key, value) in config.iter().filter(|(_, value)| !value.is_null()) {
+            if let Some((_, fork)) = GETH_FORK_KEYS.iter().find(|(name, _)| name == key) {
+                let at = parse_u64(&format!("config.{}", key), value)?;
+                let activation = if key.ends_with("Time") || key.ends_with("Timestamp") {
+                    ForkActivation::Timestamp(at)
+                } else {
+                    ForkActivation::Block(at)
+                };
+                spec = spec.with_fork(*fork, activation);
+            } else if (key.ends_with("Block") || key.ends_with("Time") || key.ends_with("Timestamp"))
+                && !GETH_IGNORED_FORK_KEYS.contains(&key.as_str())
+            {
+                unknown_keys.push(format!("config.{}", key));
+            }
+        }
+        if config.get("bor").map_or(false, Value::is_object) || config.keys().any(|key| key.starts_with("apricotPhase"))
+        {
+            spec = spec.without_blob_transactions();
+        }
+
+        if let Some(base_fee) = genesis.get("baseFeePerGas").filter(|value| !value.is_null()) {
+            spec.fee_params.initial_base_fee = parse_u256("baseFeePerGas", base_fee)?;
+        }
+        if let Some(optimism) = config.get("optimism").and_then(Value::as_object) {
+            spec = spec
+                .without_blob_transactions()
+                .with_extra_tx_type(TxType::Deposit as u8);
+            if let Some(value) = optimism.get("eip1559Denominator") {
+                spec.fee_params.base_fee_max_change_denominator =
+                    parse_u64("config.optimism.eip1559Denominator", value)?;
+            }
+            if let Some(value) = optimism.get("eip1559Elasticity") {
+                spec.fee_params.elasticity_multiplier = parse_u64("config.optimism.eip1559Elasticity", value)?;
+            }
+        }
+        if let Some(schedule) = config.get("blobSchedule") {
+            let schedule = schedule
+                .as_object()
+                .ok_or_else(|| invalid_field("config.blobSchedule"))?;
+            for (key, params) in schedule.iter() {
+                match GETH_BLOB_SCHEDULE_KEYS.iter().find(|(name, _)| name == key) {
+                    Some((_, fork)) => {
+                        let field = format!("config.blobSchedule.{}.baseFeeUpdateFraction", key);
+                        let fraction = params
+                            .get("baseFeeUpdateFraction")
+                            .ok_or_else(|| invalid_field(&field))
+                            .and_then(|value| parse_u64(&field, value))?;
+                        spec.fee_params.blob_update_fractions.insert(*fork, fraction);
+                    },
+                    None if key.starts_with("bpo") => {},
+                    None => unknown_keys.push(format!("config.blobSchedule.{}", key)),
+                }
+            }
+        }
+
+        if !unknown_keys.is_empty() {
+            return Err(ChainSpecError::UnknownForkKeys(unknown_keys));
+        }
+        Ok(spec)
+    }
+
+    /// Loads chain id, fork activations and fee parameters from a Parity chainspec.
+    /// Homestead is taken from the Ethash engine params, other engines have it from genesis.
+    pub fn from_parity_spec(json: &str) -> Result<Self, ChainSpecError> {
+        let file = parse_json(json)?;
+        let name = file.get("name").and_then(Value::as_str).unwrap_or_default();
+        let params = file
+            .get("params")
+            .ok_or(ChainSpecError::MissingField("params"))?
+            .as_object()
+            .ok_or_else(|| invalid_field("params"))?;
+        let chain_id = params
+            .get("chainID")
+            .map(|value| ("params.chainID", value))
+            .or_else(|| params.get("networkID").map(|value| ("params.networkID", value)))
+            .ok_or(ChainSpecError::MissingField("params.networkID"))
+            .and_then(|(field, value)| parse_u64(field, value))?;
+
+        let mut spec = ChainSpec::new(name, chain_id).with_fork(Fork::Homestead, ForkActivation::Block(0));
+        let ethash_params = file
+            .get("engine")
+            .and_then(Value::as_object)
+            .and_then(|engine| engine.iter().find(|(name, _)| name.eq_ignore_ascii_case("ethash")))
+            .and_then(|(_, ethash)| ethash.get("params"));
+        if let Some(value) = ethash_params.and_then(|params| params.get("homesteadTransition")) {
+            let at = parse_u64("engine.Ethash.params.homesteadTransition", value)?;
+            spec = spec.with_fork(Fork::Homestead, ForkActivation::Block(at));
+        }
+
+        let mut unknown_keys = Vec::new();
+        for (key, value) in params.iter().filter(|(_, value)| !value.is_null()) {
+            if let Some((_, fork)) = PARITY_FORK_KEYS.iter().find(|(name, _)| name == key) {
+                let at = parse_u64(&format!("params.{}", key), value)?;
+                let activation = if key.ends_with("Timestamp") {
+                    ForkActivation::Timestamp(at)
+                } else {
+                    ForkActivation::Block(at)
+                };
+                spec = spec.with_fork(*fork, activation);
+            } else if (key.ends_with("Transition") || key.ends_with("TransitionTimestamp"))
+                && !PARITY_IGNORED_FORK_KEYS.contains(&key.as_str())
+            {
+                unknown_keys.push(format!("params.{}", key));
+            }
+        }
+        if !unknown_keys.is_empty() {
+            return Err(ChainSpecError::UnknownForkKeys(unknown_keys));
+        }
+
+        let fee_params = &mut spec.fee_params;
+        if let Some(value) = params.get("eip1559BaseFeeMaxChangeDenominator") {
+            fee_params.base_fee_max_change_denominator = parse_u64("params.eip1559BaseFeeMaxChangeDenominator", value)?;
+        }
+        if let Some(value) = params.get("eip1559ElasticityMultiplier") {
+            fee_params.elasticity_multiplier = parse_u64("params.eip1559ElasticityMultiplier", value)?;
+        }
+        if let Some(value) = params.get("eip1559BaseFeeInitialValue") {
+            fee_params.initial_base_fee = parse_u256("params.eip1559BaseFeeInitialValue", value)?;
+        }
+        Ok(spec)
+    }
+}
+
+fn parse_json(json: &str) -> Result<Map<String, Value>, ChainSpecError> {
+    match json.parse::<Value>() {
+        Ok(Value::Object(file)) => Ok(file),
+        Ok(_) => Err(ChainSpecError::InvalidJson("expected an object".into())),
+        Err(err) => Err(ChainSpecError::InvalidJson(err.to_string())),
+    }
+}
+
+fn invalid_field(field: &str) -> ChainSpecError { ChainSpecError::InvalidField(field.into()) }
+
+/// Parses a JSON number, a `0x` prefixed hex string or a decimal string.
+fn parse_u64(field: &str, value: &Value) -> Result<u64, ChainSpecError> {
+    let parsed = match value {
+        Value::Number(number) => number.as_u64(),
+        Value::String(s) if s.starts_with("0x") => u64::from_str_radix(&s[2..], 16).ok(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    };
+    parsed.ok_or_else(|| invalid_field(field))
+}
+
+fn parse_u256(field: &str, value: &Value) -> Result<U256, ChainSpecError> {
+    let parsed = match value {
+        Value::Number(number) => number.as_u64().map(U256::from),
+        Value::String(s) if s.starts_with("0x") => U256::from_str_radix(&s[2..], 16).ok(),
+        Value::String(s) => U256::from_dec_str(s).ok(),
+        _ => None,
+    };
+    parsed.ok_or_else(|| invalid_field(field))
+}
@@ -0,0 +1,425 @@
+//! Fork schedule of a chain and transaction validity rules derived from it
+
+use super::{Action, Fork, TxType, UnverifiedTransactionWrapper, BLOB_BASE_FEE_UPDATE_FRACTION_CANCUN,
+            BLOB_BASE_FEE_UPDATE_FRACTION_PRAGUE};
+use crate::Error;
+use ethereum_types::U256;
+use std::{collections::{BTreeMap, BTreeSet},
+          convert::TryFrom};
+
+/// Max initcode size of a contract creation transaction (EIP-3860).
+pub const MAX_INITCODE_SIZE: usize = 2 * 24_576;
+/// Max gas limit of a transaction (EIP-7825).
+pub const MAX_TX_GAS: u64 = 1 << 24;
+/// Bound of the base fee change between two blocks (EIP-1559).
+pub const BASE_FEE_MAX_CHANGE_DENOMINATOR: u64 = 8;
+/// Ratio of the block gas limit to the block gas target (EIP-1559).
+pub const ELASTICITY_MULTIPLIER: u64 = 2;
+/// Base fee of the first London block (EIP-1559).
+pub const INITIAL_BASE_FEE: u64 = 1_000_000_000;
+
+/// Forks activated at genesis by chains launched after London.
+const GENESIS_TO_LONDON: [Fork; 10] = [
+    Fork::Homestead,
+    Fork::TangerineWhistle,
+    Fork::SpuriousDragon,
+    Fork::Byzantium,
+    Fork::Constantinople,
+    Fork::Petersburg,
+    Fork::Istanbul,
+    Fork::MuirGlacier,
+    Fork::Berlin,
+    Fork::London,
+];
+
+/// Activation point of a fork, by block number or, since the Merge, by block timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForkActivation {
+    Block(u64),
+    Timestamp(u64),
+}
+
+impl ForkActivation {
+    fn is_active(self, block: u64, timestamp: u64) -> bool {
+        match self {
+            ForkActivation::Block(n) => block >= n,
+            ForkActivation::Timestamp(t) => timestamp >= t,
+        }
+    }
+}
+
+/// Fee market parameters of a chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeeParams {
+    pub base_fee_max_change_denominator: u64,
+    pub elasticity_multiplier: u64,
+    pub initial_base_fee: U256,
+    /// Blob base fee update fraction (EIP-4844) by the fork it applies from.
+    pub blob_update_fractions: BTreeMap<Fork, u64>,
+}
+
+impl Default for FeeParams {
+    fn default() -> Self {
+        FeeParams {
+            base_fee_max_change_denominator: BASE_FEE_MAX_CHANGE_DENOMINATOR,
+            elasticity_multiplier: ELASTICITY_MULTIPLIER,
+            initial_base_fee: INITIAL_BASE_FEE.into(),
+            blob_update_fractions: vec![
+                (Fork::Cancun, BLOB_BASE_FEE_UPDATE_FRACTION_CANCUN),
+                (Fork::Prague, BLOB_BASE_FEE_UPDATE_FRACTION_PRAGUE),
+            ]
+            .into_iter()
+            .collect(),
+        }
+    }
+}
+
+impl FeeParams {
+    /// Blob base fee update fraction under `fork` rules, `None` before blobs are introduced.
+    pub fn blob_update_fraction(&self, fork: Fork) -> Option<u64> {
+        self.blob_update_fractions
+            .range(..=fork)
+            .next_back()
+            .map(|(_, fraction)| *fraction)
+    }
+}
+
+/// Chain id and fork schedule of a network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainSpec {
+    pub name: String,
+    pub chain_id: u64,
+    /// Activation of forks enabled on the chain, forks missing here are never active.
+    pub forks: BTreeMap<Fork, ForkActivation>,
+    /// Whether legacy transactions without EIP-155 replay protection are refused,
+    /// as node RPCs do by default.
+    pub eip155_required: bool,
+    /// Whether blob transactions are accepted once Cancun is active.
+    /// Polygon and Avalanche adopted Cancun without blobs.
+    pub blob_transactions: bool,
+    /// EIP-2718 types accepted on top of the Ethereum ones, as the deposit type on OP-Stack chains.
+    pub extra_tx_types: BTreeSet<u8>,
+    pub fee_params: FeeParams,
+}
+
+impl ChainSpec {
+    /// Chain with Frontier rules only, forks are added by `with_fork`.
+    pub fn new(name: &str, chain_id: u64) -> Self {
+        ChainSpec {
+            name: name.into(),
+            chain_id,
+            forks: BTreeMap::new(),
+            eip155_required: true,
+            blob_transactions: true,
+            extra_tx_types: BTreeSet::new(),
+            fee_params: FeeParams::default(),
+        }
+    }
+
+    pub fn with_fork(mut self, fork: Fork, activation: ForkActivation) -> Self {
+        self.forks.insert(fork, activation);
+        self
+    }
+
+    fn with_forks(self, forks: &[Fork], activation: ForkActivation) -> Self {
+        forks.iter().fold(self, |spec, fork| spec.with_fork(*fork, activation))
+    }
+
+    pub fn without_blob_transactions(mut self) -> Self {
+        self.blob_transactions = false;
+        self
+    }
+
+    pub fn with_extra_tx_type(mut self, tx_type: u8) -> Self {
+        self.extra_tx_types.insert(tx_type);
+        self
+    }
+
+    pub fn with_fee_params(mut self, fee_params: FeeParams) -> Self {
+        self.fee_params = fee_params;
+        self
+    }
+
+    /// Ethereum mainnet.
+    pub fn mainnet() -> Self {
+        use self::ForkActivation::*;
+        ChainSpec::new("mainnet", 1)
+            .with_fork(Fork::Homestead, Block(1_150_000))
+            .with_fork(Fork::TangerineWhistle, Block(2_463_000))
+            .with_fork(Fork::SpuriousDragon, Block(2_675_000))
+            .with_fork(Fork::Byzantium, Block(4_370_000))
+            .with_fork(Fork::Constantinople, Block(7_280_000))
+            .with_fork(Fork::Petersburg, Block(7_280_000))
+            .with_fork(Fork::Istanbul, Block(9_069_000))
+            .with_fork(Fork::MuirGlacier, Block(9_200_000))
+            .with_fork(Fork::Berlin, Block(12_244_000))
+            .with_fork(Fork::London, Block(12_965_000))
+            .with_fork(Fork::ArrowGlacier, Block(13_773_000))
+            .with_fork(Fork::GrayGlacier, Block(15_050_000))
+            .with_fork(Fork::Paris, Block(15_537_394))
+            .with_fork(Fork::Shanghai, Timestamp(1_681_338_455))
+            .with_fork(Fork::Cancun, Timestamp(1_710_338_135))
+            .with_fork(Fork::Prague, Timestamp(1_746_612_311))
+            .with_fork(Fork::Osaka, Timestamp(1_764_798_551))
+    }
+
+    /// Ethereum Sepolia testnet.
+    pub fn sepolia() -> Self {
+        use self::ForkActivation::*;
+        ChainSpec::new("sepolia", 11_155_111)
+            .with_forks(&GENESIS_TO_LONDON, Block(0))
+            .with_fork(Fork::Paris, Block(1_735_371))
+            .with_fork(Fork::Shanghai, Timestamp(1_677_557_088))
+            .with_fork(Fork::Cancun, Timestamp(1_706_655_072))
+            .with_fork(Fork::Prague, Timestamp(1_741_159_776))
+            .with_fork(Fork::Osaka, Timestamp(1_760_427_360))
+    }
+
+    /// Ethereum Holesky testnet.
+    pub fn holesky() -> Self {
+        use self::ForkActivation::*;
+        ChainSpec::new("holesky", 17_000)
+            .with_forks(&GENESIS_TO_LONDON, Block(0))
+            .with_fork(Fork::Paris, Block(0))
+            .with_fork(Fork::Shanghai, Timestamp(1_696_000_704))
+            .with_fork(Fork::Cancun, Timestamp(1_707_305_664))
+            .with_fork(Fork::Prague, Timestamp(1_740_434_112))
+            .with_fork(Fork::Osaka, Timestamp(1_759_308_480))
+    }
+
+    /// Ethereum Hoodi testnet.
+    pub fn hoodi() -> Self {
+        use self::ForkActivation::*;
+        ChainSpec::new("hoodi", 560_048)
+            .with_forks(&GENESIS_TO_LONDON, Block(0))
+            .with_fork(Fork::Paris, Block(0))
+            .with_fork(Fork::Shanghai, Timestamp(0))
+            .with_fork(Fork::Cancun, Timestamp(0))
+            .with_fork(Fork::Prague, Timestamp(1_742_999_832))
+            .with_fork(Fork::Osaka, Timestamp(1_761_677_592))
+    }
+
+    /// BNB Smart Chain mainnet, Berlin and London came with Hertz, Shanghai with Kepler,
+    /// Cancun with Haber and Prague with Pascal.
+    pub fn bsc() -> Self {
+        use self::ForkActivation::*;
+        ChainSpec::new("bsc", 56)
+            .with_forks(&GENESIS_TO_LONDON[..8], Block(0))
+            .with_fork(Fork::Berlin, Block(31_302_048))
+            .with_fork(Fork::London, Block(31_302_048))
+            .with_fork(Fork::Shanghai, Timestamp(1_705_996_800))
+            .with_fork(Fork::Cancun, Timestamp(1_718_863_500))
+            .with_fork(Fork::Prague, Timestamp(1_742_436_600))
+    }
+
+    /// BNB Smart Chain Chapel testnet.
+    pub fn chapel() -> Self {
+        use self::ForkActivation::*;
+        ChainSpec::new("chapel", 97)
+            .with_forks(&GENESIS_TO_LONDON[..8], Block(0))
+            .with_fork(Fork::Berlin, Block(31_103_030))
+            .with_fork(Fork::London, Block(31_103_030))
+            .with_fork(Fork::Shanghai, Timestamp(1_702_972_800))
+            .with_fork(Fork::Cancun, Timestamp(1_713_330_442))
+            .with_fork(Fork::Prague, Timestamp(1_740_452_880))
+    }
+
+    /// Polygon PoS mainnet, all forks activate by block number.
+    pub fn polygon() -> Self {
+        use self::ForkActivation::*;
+        ChainSpec::new("polygon", 137)
+            .with_forks(&GENESIS_TO_LONDON[..6], Block(0))
+            .with_fork(Fork::Istanbul, Block(3_395_000))
+            .with_fork(Fork::MuirGlacier, Block(3_395_000))
+            .with_fork(Fork::Berlin, Block(14_750_000))
+            .with_fork(Fork::London, Block(23_850_000))
+            .with_fork(Fork::Shanghai, Block(50_523_000))
+            .with_fork(Fork::Cancun, Block(54_876_000))
+            .with_fork(Fork::Prague, Block(73_440_256))
+            .without_blob_transactions()
+    }
+
+    /// Polygon Amoy testnet.
+    pub fn amoy() -> Self {
+        use self::ForkActivation::*;
+        ChainSpec::new("amoy", 80_002)
+            .with_forks(&GENESIS_TO_LONDON[..9], Block(0))
+            .with_fork(Fork::London, Block(73_100))
+            .with_fork(Fork::Shanghai, Block(73_100))
+            .with_fork(Fork::Cancun, Block(5_423_600))
+            .with_fork(Fork::Prague, Block(22_765_056))
+            .without_blob_transactions()
+    }
+
+    /// Avalanche C-Chain, Berlin came with Apricot Phase 2, London with Apricot Phase 3,
+    /// Shanghai with Durango and Cancun with Etna.
+    pub fn avalanche() -> Self {
+        use self::ForkActivation::*;
+        ChainSpec::new("avalanche", 43_114)
+            .with_forks(&GENESIS_TO_LONDON[..8], Block(0))
+            .with_fork(Fork::Berlin, Timestamp(1_620_644_400))
+            .with_fork(Fork::London, Timestamp(1_629_813_600))
+            .with_fork(Fork::Shanghai, Timestamp(1_709_740_800))
+            .with_fork(Fork::Cancun, Timestamp(1_734_368_400))
+            .without_blob_transactions()
+    }
+
+    /// Avalanche Fuji testnet C-Chain.
+    pub fn fuji() -> Self {
+        use self::ForkActivation::*;
+        ChainSpec::new("fuji", 43_113)
+            .with_forks(&GENESIS_TO_LONDON[..8], Block(0))
+            .with_fork(Fork::Berlin, Timestamp(1_617_631_200))
+            .with_fork(Fork::London, Timestamp(1_629_140_400))
+            .with_fork(Fork::Shanghai, Timestamp(1_707_840_000))
+            .with_fork(Fork::Cancun, Timestamp(1_732_550_400))
+            .without_blob_transactions()
+    }
+
+    /// Preset of a network known to this crate.
+    pub fn from_chain_id(chain_id: u64) -> Option<Self> {
+        vec![
+            ChainSpec::mainnet(),
+            ChainSpec::sepolia(),
+            ChainSpec::holesky(),
+            ChainSpec::hoodi(),
+            ChainSpec::bsc(),
+            ChainSpec::chapel(),
+            ChainSpec::polygon(),
+            ChainSpec::amoy(),
+            ChainSpec::avalanche(),
+            ChainSpec::fuji(),
+        ]
+        .into_iter()
+        .find(|spec| spec.chain_id == chain_id)
+    }
+
+    pub fn is_active(&self, fork: Fork, block: u64, timestamp: u64) -> bool {
+        fork == Fork::Frontier
+            || self
+                .forks
+                .get(&fork)
+                .map_or(false, |activation| activation.is_active(block, timestamp))
+    }
+
+    /// Latest fork active at a block with the given number and timestamp.
+    pub fn fork_at(&self, block: u64, timestamp: u64) -> Fork {
+        self.forks
+            .iter()
+            .filter(|(_, activation)| activation.is_active(block, timestamp))
+            .map(|(fork, _)| *fork)
+            .max()
+            .unwrap_or(Fork::Frontier)
+    }
+
+    /// Rules applying to transactions included in a block with the given number and timestamp.
+    pub fn rules_at(&self, block: u64, timestamp: u64) -> ChainRules {
+        ChainRules {
+            chain_id: self.chain_id,
+            fork: self.fork_at(block, timestamp),
+            eip155_required: self.eip155_required,
+            blob_transactions: self.blob_transactions,
+            extra_tx_types: self.extra_tx_types.clone(),
+        }
+    }
+}
+
+/// Transaction validity rules of a chain at some block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainRules {
+    pub chain_id: u64,
+    pub fork: Fork,
+    pub eip155_required: bool,
+    pub blob_transactions: bool,
+    /// EIP-2718 types valid at any fork, see `ChainSpec::extra_tx_types`.
+    pub extra_tx_types: BTreeSet<u8>,
+}
+
+impl ChainRules {
+    /// Whether transactions of the type are valid.
+    pub fn allows_tx_type(&self, tx_type: TxType) -> bool {
+        match tx_type {
+            TxType::Legacy => true,
+            TxType::Type1 => self.fork >= Fork::Berlin,
+            TxType::Type2 => self.fork >= Fork::London,
+            TxType::Type3 => self.fork >= Fork::Cancun && self.blob_transactions,
+            TxType::Type4 => self.fork >= Fork::Prague,
+            _ => self.extra_tx_types.contains(&(tx_type as u8)),
+        }
+    }
+
+    /// Whether transactions of the EIP-2718 type byte are valid.
+    pub fn allows_type_byte(&self, type_byte: u8) -> bool {
+        match TxType::try_from(type_byte) {
+            Ok(tx_type) => self.allows_tx_type(tx_type),
+            Err(_) => type_byte == TxType::Legacy as u8 || self.extra_tx_types.contains(&type_byte),
+        }
+    }
+
+    /// Whether signatures with high 's' are invalid (EIP-2).
+    pub fn requires_low_s(&self) -> bool { self.fork >= Fork::Homestead }
+
+    /// Whether legacy transactions may carry a chain id (EIP-155).
+    pub fn allows_replay_protection(&self) -> bool { self.fork >= Fork::SpuriousDragon }
+
+    /// Whether legacy transactions must carry a chain id.
+    pub fn requires_replay_protection(&self) -> bool { self.eip155_required && self.allows_replay_protection() }
+
+    /// Max initcode size of contract creation transactions, if limited (EIP-3860).
+    pub fn max_initcode_size(&self) -> Option<usize> {
+        if self.fork >= Fork::Shanghai {
+            Some(MAX_INITCODE_SIZE)
+        } else {
+            None
+        }
+    }
+
+    /// Max gas limit of a transaction, if limited (EIP-7825).
+    pub fn max_tx_gas(&self) -> Option<u64> {
+        if self.fork >= Fork::Osaka {
+            Some(MAX_TX_GAS)
+        } else {
+            None
+        }
+    }
+
+    /// Checks the transaction is valid under these rules, its sender is not recovered.
+    pub fn verify_basic(&self, tx: &UnverifiedTransactionWrapper) -> Result<(), Error> {
+        let type_byte = tx.type_byte();
+        if !self.allows_type_byte(type_byte) {
+            return Err(Error::TxTypeNotEnabled(type_byte));
+        }
+        let legacy = tx.tx_type() == Some(TxType::Legacy);
+        match tx.chain_id() {
+            Some(chain_id) if chain_id != self.chain_id => return Err(Error::InvalidChainId),
+            Some(_) if legacy && !self.allows_replay_protection() => return Err(Error::InvalidChainId),
+            None if legacy && self.requires_replay_protection() => return Err(Error::InvalidChainId),
+            _ => {},
+        }
+        tx.verify_basic(self.requires_low_s(), Some(self.chain_id), false)?;
+
+        let unsigned = tx.unsigned();
+        if let (Action::Create, Some(limit)) = (unsigned.action(), self.max_initcode_size()) {
+            if unsigned.data().len() > limit {
+                return Err(Error::InitcodeTooLarge {
+                    limit,
+                    got: unsigned.data().len(),
+                });
+            }
+        }
+        let gas = unsigned.gas();
+        let minimal = U256::from(tx.intrinsic_gas(self.fork));
+        if gas < minimal {
+            return Err(Error::InsufficientGas { minimal, got: gas });
+        }
+        if let Some(limit) = self.max_tx_gas() {
+            if gas > U256::from(limit) {
+                return Err(Error::GasLimitExceeded {
+                    limit: limit.into(),
+                    got: gas,
+                });
+            }
+        }
+        Ok(())
+    }
+}
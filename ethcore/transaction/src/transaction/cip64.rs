@@ -0,0 +1,199 @@
+//! Celo CIP-64 (fee currency) transaction encoding/decoding and specific checks
+
+use super::AccessList;
+use super::SignedTransactionShared;
+use super::{Action, Bytes, TransactionShared, TxFields, TxType};
+use crate::{Error, TxDecodeError};
+use ethereum_types::{Address, H256, U256};
+use hash::keccak;
+use rlp::{self, DecoderError, Rlp, RlpStream};
+use std::{convert::TryInto, ops::Deref};
+
+/// A set of information describing an externally-originating message call
+/// or contract creation operation, paying gas in an ERC-20 token.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Cip64Transaction {
+    /// Simple replay attack protection
+    pub(crate) chain_id: u64,
+    /// Nonce.
+    pub(crate) nonce: U256,
+    /// Max fee per gas.
+    pub(crate) max_fee_per_gas: U256,
+    /// Max priority fee per gas.
+    pub(crate) max_priority_fee_per_gas: U256,
+    /// Gas paid up front for transaction execution.
+    pub(crate) gas: U256,
+    /// Action, can be either call or contract create.
+    pub(crate) action: Action,
+    /// Transfered value.
+    pub(crate) value: U256,
+    /// Transaction data.
+    pub(crate) data: Bytes,
+    /// Access list.
+    pub(crate) access_list: AccessList,
+    /// ERC-20 token used to pay gas.
+    pub(crate) fee_currency: Address,
+}
+
+impl Cip64Transaction {
+    const fn payload_size(&self) -> usize { 10 }
+
+    /// Append object with a without signature into RLP stream
+    fn rlp_append_unsigned_transaction(&self, s: &mut RlpStream) {
+        s.append(&(TxType::Cip64 as u8));
+        s.begin_list(self.payload_size());
+        s.append(&self.chain_id);
+        s.append(&self.nonce);
+        s.append(&self.max_priority_fee_per_gas);
+        s.append(&self.max_fee_per_gas);
+        s.append(&self.gas);
+        s.append(&self.action);
+        s.append(&self.value);
+        s.append(&self.data);
+        s.append(&self.access_list);
+        s.append(&self.fee_currency);
+    }
+
+    pub fn max_fee_per_gas(&self) -> U256 { self.max_fee_per_gas }
+
+    pub fn max_priority_fee_per_gas(&self) -> U256 { self.max_priority_fee_per_gas }
+
+    pub fn access_list(&self) -> &AccessList { &self.access_list }
+
+    pub fn fee_currency(&self) -> Address { self.fee_currency }
+}
+
+impl TransactionShared for Cip64Transaction {
+    fn nonce(&self) -> U256 { self.nonce }
+    fn action(&self) -> &Action { &self.action }
+    fn value(&self) -> U256 { self.value }
+    fn gas(&self) -> U256 { self.gas }
+    fn data(&self) -> &Bytes { &self.data }
+    /// The message hash of the transaction.
+    fn message_hash(&self, _chain_id: Option<u64>) -> H256 {
+        let mut stream = RlpStream::new();
+        self.rlp_append_unsigned_transaction(&mut stream);
+        keccak(stream.as_raw())
+    }
+}
+
+impl rlp::Decodable for Cip64Transaction {
+    fn decode(d: &Rlp) -> Result<Self, DecoderError> { Ok(Self::decode_fields(&TxFields::typed(d, TxType::Cip64)?)?) }
+}
+
+impl Cip64Transaction {
+    pub(crate) fn decode_fields(list: &TxFields) -> Result<Self, TxDecodeError> {
+        Ok(Cip64Transaction {
+            chain_id: list.val_at(0, "chain_id")?,
+            nonce: list.val_at(1, "nonce")?,
+            max_priority_fee_per_gas: list.val_at(2, "max_priority_fee_per_gas")?,
+            max_fee_per_gas: list.val_at(3, "max_fee_per_gas")?,
+            gas: list.val_at(4, "gas")?,
+            action: list.val_at(5, "action")?,
+            value: list.val_at(6, "value")?,
+            data: list.val_at(7, "data")?,
+            access_list: list.val_at(8, "access_list")?,
+            fee_currency: list.val_at(9, "fee_currency")?,
+        })
+    }
+}
+
+/// Signed transaction information without verified signature.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UnverifiedCip64Transaction {
+    /// Plain Transaction.
+    unsigned: Cip64Transaction,
+    /// The V field of the signature
+    v: u64,
+    /// The R field of the signature; helps describe the point on the curve.
+    r: U256,
+    /// The S field of the signature; helps describe the point on the curve.
+    s: U256,
+    /// Hash of the transaction
+    hash: H256,
+}
+
+impl Deref for UnverifiedCip64Transaction {
+    type Target = Cip64Transaction;
+
+    fn deref(&self) -> &Self::Target { &self.unsigned }
+}
+
+impl rlp::Decodable for UnverifiedCip64Transaction {
+    fn decode(d: &Rlp) -> Result<Self, DecoderError> { Ok(Self::decode_tx(d)?) }
+}
+
+impl UnverifiedCip64Transaction {
+    pub(crate) fn decode_tx(d: &Rlp) -> Result<Self, TxDecodeError> {
+        let list = TxFields::typed(d, TxType::Cip64)?;
+        let unsigned = Cip64Transaction::decode_fields(&list)?;
+        let hash = list.hash();
+        let offset = unsigned.payload_size();
+        let v = list.val_at(offset, "v")?;
+        if !Self::validate_v(v) {
+            return Err(list.error(offset, "v", DecoderError::Custom("invalid sig v")));
+        }
+        Ok(UnverifiedCip64Transaction {
+            unsigned,
+            v,
+            r: list.val_at(offset + 1, "r")?,
+            s: list.val_at(offset + 2, "s")?,
+            hash,
+        })
+    }
+}
+
+impl rlp::Encodable for UnverifiedCip64Transaction {
+    fn rlp_append(&self, s: &mut RlpStream) { self.rlp_append_sealed_transaction(s) }
+}
+
+impl SignedTransactionShared for UnverifiedCip64Transaction {
+    fn rlp_append_sealed_transaction(&self, s: &mut RlpStream) {
+        s.append(&(TxType::Cip64 as u8));
+        s.begin_list(self.payload_size());
+        s.append(&self.chain_id);
+        s.append(&self.nonce);
+        s.append(&self.max_priority_fee_per_gas);
+        s.append(&self.max_fee_per_gas);
+        s.append(&self.gas);
+        s.append(&self.action);
+        s.append(&self.value);
+        s.append(&self.data);
+        s.append(&self.access_list);
+        s.append(&self.fee_currency);
+        s.append(&self.v);
+        s.append(&self.r);
+        s.append(&self.s);
+    }
+
+    fn set_hash(&mut self, hash: H256) { self.hash = hash; }
+}
+
+impl UnverifiedCip64Transaction {
+    pub fn new(unsigned: Cip64Transaction, r: U256, s: U256, v: u64, hash: H256) -> Result<Self, Error> {
+        if !Self::validate_v(v) {
+            return Err(Error::InvalidSignature("invalid sig v".into()));
+        }
+        Ok(UnverifiedCip64Transaction {
+            unsigned,
+            r,
+            s,
+            v,
+            hash,
+        })
+    }
+
+    fn validate_v(v: u64) -> bool { (0..=1).contains(&v) }
+
+    /// tx list item count
+    fn payload_size(&self) -> usize { self.unsigned.payload_size() + 3 }
+
+    pub fn standard_v(&self) -> u8 {
+        self.v.try_into().expect("parity 0 or 1") // ensured that parity is 0 or 1 for tx type 0x7b
+    }
+
+    pub fn r(&self) -> U256 { self.r }
+    pub fn s(&self) -> U256 { self.s }
+    pub fn v(&self) -> u64 { self.v }
+    pub fn hash(&self) -> H256 { self.hash }
+}
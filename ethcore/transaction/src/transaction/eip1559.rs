@@ -4,8 +4,8 @@
 
 use super::AccessList;
 use super::SignedTransactionShared;
-use super::{Action, Bytes, TransactionShared, TxType};
-use crate::Error;
+use super::{Action, Bytes, TransactionShared, TxFields, TxType};
+use crate::{Error, TxDecodeError};
 use ethereum_types::{H256, U256};
 use hash::keccak;
 use rlp::{self, DecoderError, Rlp, RlpStream};
@@ -75,25 +75,21 @@ impl TransactionShared for Eip1559Transaction {
 }
 
 impl rlp::Decodable for Eip1559Transaction {
-    fn decode(d: &Rlp) -> Result<Self, DecoderError> {
-        if d.as_raw().len() < 2 {
-            return Err(DecoderError::RlpIsTooShort);
-        }
-        let version: u8 = d.as_raw()[0];
-        if TxType::from(version) != TxType::Type2 {
-            return Err(DecoderError::Custom("bad tx version"));
-        }
-        let list = Rlp::new(&d.as_raw()[1..]);
+    fn decode(d: &Rlp) -> Result<Self, DecoderError> { Ok(Self::decode_fields(&TxFields::typed(d, TxType::Type2)?)?) }
+}
+
+impl Eip1559Transaction {
+    pub(crate) fn decode_fields(list: &TxFields) -> Result<Self, TxDecodeError> {
         Ok(Eip1559Transaction {
-            chain_id: list.val_at(0)?,
-            nonce: list.val_at(1)?,
-            max_priority_fee_per_gas: list.val_at(2)?,
-            max_fee_per_gas: list.val_at(3)?,
-            gas: list.val_at(4)?,
-            action: list.val_at(5)?,
-            value: list.val_at(6)?,
-            data: list.val_at(7)?,
-            access_list: list.val_at(8)?,
+            chain_id: list.val_at(0, "chain_id")?,
+            nonce: list.val_at(1, "nonce")?,
+            max_priority_fee_per_gas: list.val_at(2, "max_priority_fee_per_gas")?,
+            max_fee_per_gas: list.val_at(3, "max_fee_per_gas")?,
+            gas: list.val_at(4, "gas")?,
+            action: list.val_at(5, "action")?,
+            value: list.val_at(6, "value")?,
+            data: list.val_at(7, "data")?,
+            access_list: list.val_at(8, "access_list")?,
         })
     }
 }
@@ -120,20 +116,24 @@ impl Deref for UnverifiedEip1559Transaction {
 }
 
 impl rlp::Decodable for UnverifiedEip1559Transaction {
-    fn decode(d: &Rlp) -> Result<Self, DecoderError> {
-        let unsigned = Eip1559Transaction::decode(d)?;
-        let hash = keccak(d.as_raw());
+    fn decode(d: &Rlp) -> Result<Self, DecoderError> { Ok(Self::decode_tx(d)?) }
+}
+
+impl UnverifiedEip1559Transaction {
+    pub(crate) fn decode_tx(d: &Rlp) -> Result<Self, TxDecodeError> {
+        let list = TxFields::typed(d, TxType::Type2)?;
+        let unsigned = Eip1559Transaction::decode_fields(&list)?;
+        let hash = list.hash();
         let offset = unsigned.payload_size();
-        let list = Rlp::new(&d.as_raw()[1..]);
-        let v = list.val_at(offset)?;
+        let v = list.val_at(offset, "v")?;
         if !Self::validate_v(v) {
-            return Err(DecoderError::Custom("invalid sig v"));
+            return Err(list.error(offset, "v", DecoderError::Custom("invalid sig v")));
         }
         Ok(UnverifiedEip1559Transaction {
             unsigned,
             v,
-            r: list.val_at(offset + 1)?,
-            s: list.val_at(offset + 2)?,
+            r: list.val_at(offset + 1, "r")?,
+            s: list.val_at(offset + 2, "s")?,
             hash,
         })
     }
@@ -144,6 +144,23 @@ impl rlp::Encodable for UnverifiedEip1559Transaction {
 }
 
 impl SignedTransactionShared for UnverifiedEip1559Transaction {
+    fn rlp_append_sealed_transaction(&self, s: &mut RlpStream) {
+        s.append(&(TxType::Type2 as u8));
+        s.begin_list(self.payload_size());
+        s.append(&self.chain_id);
+        s.append(&self.nonce);
+        s.append(&self.max_priority_fee_per_gas);
+        s.append(&self.max_fee_per_gas);
+        s.append(&self.gas);
+        s.append(&self.action);
+        s.append(&self.value);
+        s.append(&self.data);
+        s.append(&self.access_list);
+        s.append(&self.v);
+        s.append(&self.r);
+        s.append(&self.s);
+    }
+
     fn set_hash(&mut self, hash: H256) { self.hash = hash; }
 }
 
@@ -166,24 +183,6 @@ impl UnverifiedEip1559Transaction {
     /// tx list item count
     fn payload_size(&self) -> usize { self.unsigned.payload_size() + 3 }
 
-    /// Append object with a signature into RLP stream
-    pub(crate) fn rlp_append_sealed_transaction(&self, s: &mut RlpStream) {
-        s.append(&(TxType::Type2 as u8));
-        s.begin_list(self.payload_size());
-        s.append(&self.chain_id);
-        s.append(&self.nonce);
-        s.append(&self.max_priority_fee_per_gas);
-        s.append(&self.max_fee_per_gas);
-        s.append(&self.gas);
-        s.append(&self.action);
-        s.append(&self.value);
-        s.append(&self.data);
-        s.append(&self.access_list);
-        s.append(&self.v);
-        s.append(&self.r);
-        s.append(&self.s);
-    }
-
     pub fn standard_v(&self) -> u8 {
         self.v.try_into().expect("parity 0 or 1") // ensured that parity is 0 or 1 for tx type 2
     }
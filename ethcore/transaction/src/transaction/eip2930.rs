@@ -2,8 +2,8 @@
 //
 //! Eip 2930 transaction encoding/decoding and specific checks
 
-use super::{Action, Bytes, TransactionShared, TxType};
-use crate::{Error, SignedTransactionShared};
+use super::{Action, Bytes, TransactionShared, TxFields, TxType};
+use crate::{Error, SignedTransactionShared, TxDecodeError};
 use ethereum_types::{Address, H256, U256};
 use hash::keccak;
 use rlp::{self, DecoderError, Rlp, RlpStream};
@@ -124,24 +124,20 @@ impl TransactionShared for Eip2930Transaction {
 }
 
 impl rlp::Decodable for Eip2930Transaction {
-    fn decode(d: &Rlp) -> Result<Self, DecoderError> {
-        if d.as_raw().len() < 2 {
-            return Err(DecoderError::RlpIsTooShort);
-        }
-        let version: u8 = d.as_raw()[0];
-        if TxType::from(version) != TxType::Type1 {
-            return Err(DecoderError::Custom("bad tx version"));
-        }
-        let list = Rlp::new(&d.as_raw()[1..]);
+    fn decode(d: &Rlp) -> Result<Self, DecoderError> { Ok(Self::decode_fields(&TxFields::typed(d, TxType::Type1)?)?) }
+}
+
+impl Eip2930Transaction {
+    pub(crate) fn decode_fields(list: &TxFields) -> Result<Self, TxDecodeError> {
         Ok(Eip2930Transaction {
-            chain_id: list.val_at(0)?,
-            nonce: list.val_at(1)?,
-            gas_price: list.val_at(2)?,
-            gas: list.val_at(3)?,
-            action: list.val_at(4)?,
-            value: list.val_at(5)?,
-            data: list.val_at(6)?,
-            access_list: list.val_at(7)?,
+            chain_id: list.val_at(0, "chain_id")?,
+            nonce: list.val_at(1, "nonce")?,
+            gas_price: list.val_at(2, "gas_price")?,
+            gas: list.val_at(3, "gas")?,
+            action: list.val_at(4, "action")?,
+            value: list.val_at(5, "value")?,
+            data: list.val_at(6, "data")?,
+            access_list: list.val_at(7, "access_list")?,
         })
     }
 }
@@ -168,20 +164,24 @@ impl Deref for UnverifiedEip2930Transaction {
 }
 
 impl rlp::Decodable for UnverifiedEip2930Transaction {
-    fn decode(d: &Rlp) -> Result<Self, DecoderError> {
-        let unsigned = Eip2930Transaction::decode(d)?;
-        let hash = keccak(d.as_raw());
+    fn decode(d: &Rlp) -> Result<Self, DecoderError> { Ok(Self::decode_tx(d)?) }
+}
+
+impl UnverifiedEip2930Transaction {
+    pub(crate) fn decode_tx(d: &Rlp) -> Result<Self, TxDecodeError> {
+        let list = TxFields::typed(d, TxType::Type1)?;
+        let unsigned = Eip2930Transaction::decode_fields(&list)?;
+        let hash = list.hash();
         let offset = unsigned.payload_size();
-        let list = Rlp::new(&d.as_raw()[1..]);
-        let v = list.val_at(offset)?;
+        let v = list.val_at(offset, "v")?;
         if !Self::validate_v(v) {
-            return Err(DecoderError::Custom("invalid sig v"));
+            return Err(list.error(offset, "v", DecoderError::Custom("invalid sig v")));
         }
         Ok(UnverifiedEip2930Transaction {
             unsigned,
             v,
-            r: list.val_at(offset + 1)?,
-            s: list.val_at(offset + 2)?,
+            r: list.val_at(offset + 1, "r")?,
+            s: list.val_at(offset + 2, "s")?,
             hash,
         })
     }
@@ -192,6 +192,22 @@ impl rlp::Encodable for UnverifiedEip2930Transaction {
 }
 
 impl SignedTransactionShared for UnverifiedEip2930Transaction {
+    fn rlp_append_sealed_transaction(&self, s: &mut RlpStream) {
+        s.append(&(TxType::Type1 as u8));
+        s.begin_list(self.payload_size());
+        s.append(&self.chain_id);
+        s.append(&self.nonce);
+        s.append(&self.gas_price);
+        s.append(&self.gas);
+        s.append(&self.action);
+        s.append(&self.value);
+        s.append(&self.data);
+        s.append(&self.access_list);
+        s.append(&self.v);
+        s.append(&self.r);
+        s.append(&self.s);
+    }
+
     fn set_hash(&mut self, hash: H256) { self.hash = hash; }
 }
 
@@ -214,23 +230,6 @@ impl UnverifiedEip2930Transaction {
     /// tx list item count
     fn payload_size(&self) -> usize { self.unsigned.payload_size() + 3 }
 
-    /// Append object with a signature into RLP stream
-    pub(crate) fn rlp_append_sealed_transaction(&self, s: &mut RlpStream) {
-        s.append(&(TxType::Type1 as u8));
-        s.begin_list(self.payload_size());
-        s.append(&self.chain_id);
-        s.append(&self.nonce);
-        s.append(&self.gas_price);
-        s.append(&self.gas);
-        s.append(&self.action);
-        s.append(&self.value);
-        s.append(&self.data);
-        s.append(&self.access_list);
-        s.append(&self.v);
-        s.append(&self.r);
-        s.append(&self.s);
-    }
-
     pub fn standard_v(&self) -> u8 {
         self.v.try_into().expect("parity 0 or 1") // ensured that parity is 0 or 1 for tx type 1
     }
@@ -0,0 +1,490 @@
+//! Eip 4844 (blob-carrying) transaction encoding/decoding and specific checks
+
+use super::AccessList;
+use super::SignedTransactionShared;
+use super::{Action, Bytes, TransactionShared, TxFields, TxType};
+use crate::{Error, TxDecodeError};
+#[cfg(feature = "kzg")] use c_kzg::{Blob, Bytes48, KzgProof};
+use ethereum_types::{Address, H256, U256};
+use hash::keccak;
+use rlp::{self, DecoderError, Rlp, RlpStream};
+use sha2::{Digest, Sha256};
+#[cfg(feature = "kzg")] use std::path::Path;
+use std::{convert::TryInto, ops::Deref};
+
+#[cfg(feature = "kzg")] pub use c_kzg::KzgSettings;
+
+/// Gas consumed by a single blob.
+pub const GAS_PER_BLOB: u64 = 1 << 17;
+/// Minimal blob base fee, in wei.
+pub const MIN_BASE_FEE_PER_BLOB_GAS: u64 = 1;
+/// Blob base fee update fraction as defined for Cancun.
+pub const BLOB_BASE_FEE_UPDATE_FRACTION_CANCUN: u64 = 3_338_477;
+/// Blob base fee update fraction as defined for Prague (EIP-7691).
+pub const BLOB_BASE_FEE_UPDATE_FRACTION_PRAGUE: u64 = 5_007_716;
+/// Version byte of a versioned hash derived from a KZG commitment.
+pub const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+/// Size of a blob in bytes.
+pub const BYTES_PER_BLOB: usize = 131_072;
+/// Size of a KZG commitment in bytes.
+pub const BYTES_PER_COMMITMENT: usize = 48;
+/// Size of a KZG proof in bytes.
+pub const BYTES_PER_PROOF: usize = 48;
+
+/// Computes the versioned hash of a KZG commitment: `0x01 || sha256(commitment)[1..]`.
+pub fn kzg_to_versioned_hash(commitment: &[u8]) -> H256 {
+    let mut hash = H256::zero();
+    hash.as_bytes_mut().copy_from_slice(&Sha256::digest(commitment));
+    hash.as_bytes_mut()[0] = VERSIONED_HASH_VERSION_KZG;
+    hash
+}
+
+/// Loads the KZG trusted setup from a file in the format used by the consensus specs.
+#[cfg(feature = "kzg")]
+pub fn load_trusted_setup(path: &Path) -> Result<KzgSettings, Error> {
+    KzgSettings::load_trusted_setup_file(path)
+        .map_err(|e| Error::InvalidKzgProof(format!("could not load trusted setup: {:?}", e)))
+}
+
+/// Approximates `factor * e ** (numerator / denominator)` using Taylor expansion, as defined in EIP-4844.
+/// Saturates at `U256::MAX` if intermediate values do not fit into 256 bits.
+pub fn fake_exponential(factor: U256, numerator: U256, denominator: U256) -> U256 {
+    if denominator.is_zero() {
+        return U256::zero();
+    }
+    let mut i = U256::one();
+    let mut output = U256::zero();
+    let mut numerator_accum = match factor.checked_mul(denominator) {
+        Some(accum) => accum,
+        None => return U256::MAX,
+    };
+    while !numerator_accum.is_zero() {
+        output = match output.checked_add(numerator_accum) {
+            Some(output) => output,
+            None => return U256::MAX,
+        };
+        numerator_accum = match (numerator_accum.checked_mul(numerator), denominator.checked_mul(i)) {
+            (Some(accum), Some(divisor)) => accum / divisor,
+            _ => return U256::MAX,
+        };
+        i = i + 1;
+    }
+    output / denominator
+}
+
+/// Blob base fee for a block with the given excess blob gas.
+/// `update_fraction` is fork dependent, see `BLOB_BASE_FEE_UPDATE_FRACTION_CANCUN` and `BLOB_BASE_FEE_UPDATE_FRACTION_PRAGUE`.
+pub fn blob_base_fee(excess_blob_gas: u64, update_fraction: u64) -> U256 {
+    fake_exponential(
+        MIN_BASE_FEE_PER_BLOB_GAS.into(),
+        excess_blob_gas.into(),
+        update_fraction.into(),
+    )
+}
+
+/// A set of information describing an externally-originating message call
+/// carrying blobs. Contract creation is not allowed for this transaction type.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Eip4844Transaction {
+    /// Simple replay attack protection
+    pub(crate) chain_id: u64,
+    /// Nonce.
+    pub(crate) nonce: U256,
+    /// Max fee per gas.
+    pub(crate) max_fee_per_gas: U256,
+    /// Max priority fee per gas.
+    pub(crate) max_priority_fee_per_gas: U256,
+    /// Gas paid up front for transaction execution.
+    pub(crate) gas: U256,
+    /// Action, always a call for this transaction type.
+    pub(crate) action: Action,
+    /// Transfered value.
+    pub(crate) value: U256,
+    /// Transaction data.
+    pub(crate) data: Bytes,
+    /// Access list.
+    pub(crate) access_list: AccessList,
+    /// Max fee per blob gas.
+    pub(crate) max_fee_per_blob_gas: U256,
+    /// Versioned hashes of the blobs carried by the transaction.
+    pub(crate) blob_versioned_hashes: Vec<H256>,
+}
+
+impl Eip4844Transaction {
+    const fn payload_size(&self) -> usize { 11 }
+
+    /// Append object with a without signature into RLP stream
+    fn rlp_append_unsigned_transaction(&self, s: &mut RlpStream) {
+        s.append(&(TxType::Type3 as u8));
+        s.begin_list(self.payload_size());
+        self.rlp_append_payload(s);
+    }
+
+    /// Append transaction fields (without signature) into RLP stream
+    fn rlp_append_payload(&self, s: &mut RlpStream) {
+        s.append(&self.chain_id);
+        s.append(&self.nonce);
+        s.append(&self.max_priority_fee_per_gas);
+        s.append(&self.max_fee_per_gas);
+        s.append(&self.gas);
+        s.append(&self.action);
+        s.append(&self.value);
+        s.append(&self.data);
+        s.append(&self.access_list);
+        s.append(&self.max_fee_per_blob_gas);
+        s.begin_list(self.blob_versioned_hashes.len());
+        for hash in self.blob_versioned_hashes.iter() {
+            s.append(hash);
+        }
+    }
+
+    pub fn max_fee_per_gas(&self) -> U256 { self.max_fee_per_gas }
+
+    pub fn max_priority_fee_per_gas(&self) -> U256 { self.max_priority_fee_per_gas }
+
+    pub fn access_list(&self) -> &AccessList { &self.access_list }
+
+    pub fn max_fee_per_blob_gas(&self) -> U256 { self.max_fee_per_blob_gas }
+
+    pub fn blob_versioned_hashes(&self) -> &[H256] { &self.blob_versioned_hashes }
+
+    /// Total blob gas consumed by the transaction.
+    pub fn blob_gas(&self) -> u64 { GAS_PER_BLOB * self.blob_versioned_hashes.len() as u64 }
+}
+
+impl TransactionShared for Eip4844Transaction {
+    fn nonce(&self) -> U256 { self.nonce }
+    fn action(&self) -> &Action { &self.action }
+    fn value(&self) -> U256 { self.value }
+    fn gas(&self) -> U256 { self.gas }
+    fn data(&self) -> &Bytes { &self.data }
+    /// The message hash of the transaction.
+    fn message_hash(&self, _chain_id: Option<u64>) -> H256 {
+        let mut stream = RlpStream::new();
+        self.rlp_append_unsigned_transaction(&mut stream);
+        keccak(stream.as_raw())
+    }
+}
+
+impl rlp::Decodable for Eip4844Transaction {
+    fn decode(d: &Rlp) -> Result<Self, DecoderError> { Ok(Self::decode_fields(&TxFields::typed(d, TxType::Type3)?)?) }
+}
+
+impl Eip4844Transaction {
+    pub(crate) fn decode_fields(list: &TxFields) -> Result<Self, TxDecodeError> {
+        // blob transactions can't be used to create contracts, so `to` must be an address
+        let to: Address = list.val_at(5, "to")?;
+        Ok(Eip4844Transaction {
+            chain_id: list.val_at(0, "chain_id")?,
+            nonce: list.val_at(1, "nonce")?,
+            max_priority_fee_per_gas: list.val_at(2, "max_priority_fee_per_gas")?,
+            max_fee_per_gas: list.val_at(3, "max_fee_per_gas")?,
+            gas: list.val_at(4, "gas")?,
+            action: Action::Call(to),
+            value: list.val_at(6, "value")?,
+            data: list.val_at(7, "data")?,
+            access_list: list.val_at(8, "access_list")?,
+            max_fee_per_blob_gas: list.val_at(9, "max_fee_per_blob_gas")?,
+            blob_versioned_hashes: list.list_at(10, "blob_versioned_hashes")?,
+        })
+    }
+}
+
+/// Signed transaction information without verified signature.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UnverifiedEip4844Transaction {
+    /// Plain Transaction.
+    unsigned: Eip4844Transaction,
+    /// The V field of the signature
+    v: u64,
+    /// The R field of the signature; helps describe the point on the curve.
+    r: U256,
+    /// The S field of the signature; helps describe the point on the curve.
+    s: U256,
+    /// Hash of the transaction
+    hash: H256,
+}
+
+impl Deref for UnverifiedEip4844Transaction {
+    type Target = Eip4844Transaction;
+
+    fn deref(&self) -> &Self::Target { &self.unsigned }
+}
+
+impl rlp::Decodable for UnverifiedEip4844Transaction {
+    fn decode(d: &Rlp) -> Result<Self, DecoderError> { Ok(Self::decode_tx(d)?) }
+}
+
+impl UnverifiedEip4844Transaction {
+    pub(crate) fn decode_tx(d: &Rlp) -> Result<Self, TxDecodeError> {
+        let list = TxFields::typed(d, TxType::Type3)?;
+        let unsigned = Eip4844Transaction::decode_fields(&list)?;
+        let hash = list.hash();
+        let offset = unsigned.payload_size();
+        let v = list.val_at(offset, "v")?;
+        if !Self::validate_v(v) {
+            return Err(list.error(offset, "v", DecoderError::Custom("invalid sig v")));
+        }
+        Ok(UnverifiedEip4844Transaction {
+            unsigned,
+            v,
+            r: list.val_at(offset + 1, "r")?,
+            s: list.val_at(offset + 2, "s")?,
+            hash,
+        })
+    }
+}
+
+impl rlp::Encodable for UnverifiedEip4844Transaction {
+    fn rlp_append(&self, s: &mut RlpStream) { self.rlp_append_sealed_transaction(s) }
+}
+
+impl SignedTransactionShared for UnverifiedEip4844Transaction {
+    fn rlp_append_sealed_transaction(&self, s: &mut RlpStream) {
+        s.append(&(TxType::Type3 as u8));
+        self.rlp_append_signed_payload(s);
+    }
+
+    fn set_hash(&mut self, hash: H256) { self.hash = hash; }
+}
+
+impl UnverifiedEip4844Transaction {
+    pub fn new(unsigned: Eip4844Transaction, r: U256, s: U256, v: u64, hash: H256) -> Result<Self, Error> {
+        if !Self::validate_v(v) {
+            return Err(Error::InvalidSignature("invalid sig v".into()));
+        }
+        Ok(UnverifiedEip4844Transaction {
+            unsigned,
+            r,
+            s,
+            v,
+            hash,
+        })
+    }
+
+    fn validate_v(v: u64) -> bool { (0..=1).contains(&v) }
+
+    /// tx list item count
+    fn payload_size(&self) -> usize { self.unsigned.payload_size() + 3 }
+
+    /// Append signed transaction list (without the type byte) into RLP stream
+    pub(crate) fn rlp_append_signed_payload(&self, s: &mut RlpStream) {
+        s.begin_list(self.payload_size());
+        self.unsigned.rlp_append_payload(s);
+        s.append(&self.v);
+        s.append(&self.r);
+        s.append(&self.s);
+    }
+
+    pub fn standard_v(&self) -> u8 {
+        self.v.try_into().expect("parity 0 or 1") // ensured that parity is 0 or 1 for tx type 3
+    }
+
+    pub fn r(&self) -> U256 { self.r }
+    pub fn s(&self) -> U256 { self.s }
+    pub fn v(&self) -> u64 { self.v }
+    pub fn hash(&self) -> H256 { self.hash }
+}
+
+/// Blobs with their KZG commitments and proofs, sent alongside a blob transaction over the network.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct BlobTransactionSidecar {
+    /// Blobs, `BYTES_PER_BLOB` bytes each.
+    pub(crate) blobs: Vec<Bytes>,
+    /// KZG commitments, one per blob.
+    pub(crate) commitments: Vec<Bytes>,
+    /// KZG proofs, one per blob.
+    pub(crate) proofs: Vec<Bytes>,
+}
+
+impl BlobTransactionSidecar {
+    pub fn new(blobs: Vec<Bytes>, commitments: Vec<Bytes>, proofs: Vec<Bytes>) -> Result<Self, Error> {
+        let sidecar = BlobTransactionSidecar {
+            blobs,
+            commitments,
+            proofs,
+        };
+        sidecar.validate_sizes()?;
+        Ok(sidecar)
+    }
+
+    pub fn blobs(&self) -> &[Bytes] { &self.blobs }
+
+    pub fn commitments(&self) -> &[Bytes] { &self.commitments }
+
+    pub fn proofs(&self) -> &[Bytes] { &self.proofs }
+
+    /// Versioned hashes of the commitments, in order.
+    pub fn versioned_hashes(&self) -> Vec<H256> { self.commitments.iter().map(|c| kzg_to_versioned_hash(c)).collect() }
+
+    /// Checks that the sidecar has one commitment and one proof per blob and that all items have the correct size.
+    fn validate_sizes(&self) -> Result<(), Error> {
+        if self.blobs.len() != self.commitments.len() || self.blobs.len() != self.proofs.len() {
+            return Err(Error::InvalidBlobSidecar(format!(
+                "{} blobs, {} commitments and {} proofs",
+                self.blobs.len(),
+                self.commitments.len(),
+                self.proofs.len()
+            )));
+        }
+        let check_size = |items: &[Bytes], size: usize, name: &str| match items.iter().position(|i| i.len() != size) {
+            Some(index) => Err(Error::InvalidBlobSidecar(format!(
+                "invalid {} size at index {}",
+                name, index
+            ))),
+            None => Ok(()),
+        };
+        check_size(&self.blobs, BYTES_PER_BLOB, "blob")?;
+        check_size(&self.commitments, BYTES_PER_COMMITMENT, "commitment")?;
+        check_size(&self.proofs, BYTES_PER_PROOF, "proof")
+    }
+
+    /// Verifies the KZG proofs of all blobs against their commitments.
+    #[cfg(feature = "kzg")]
+    pub fn verify_kzg_proofs(&self, settings: &KzgSettings) -> Result<(), Error> {
+        let kzg_err = |e: c_kzg::Error| Error::InvalidKzgProof(format!("{:?}", e));
+        let blobs = self
+            .blobs
+            .iter()
+            .map(|b| Blob::from_bytes(b))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(kzg_err)?;
+        let commitments = self
+            .commitments
+            .iter()
+            .map(|c| Bytes48::from_bytes(c))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(kzg_err)?;
+        let proofs = self
+            .proofs
+            .iter()
+            .map(|p| Bytes48::from_bytes(p))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(kzg_err)?;
+        if KzgProof::verify_blob_kzg_proof_batch(&blobs, &commitments, &proofs, settings).map_err(kzg_err)? {
+            Ok(())
+        } else {
+            Err(Error::InvalidKzgProof("proof does not match blob".into()))
+        }
+    }
+
+    fn rlp_append_items(s: &mut RlpStream, items: &[Bytes]) {
+        s.begin_list(items.len());
+        for item in items.iter() {
+            s.append(item);
+        }
+    }
+}
+
+/// Blob transaction in the network form `0x03 || rlp([tx_payload_body, blobs, commitments, proofs])`,
+/// as used by `eth_sendRawTransaction` and transaction gossip.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UnverifiedEip4844TransactionWithSidecar {
+    /// Signed transaction in the consensus form.
+    transaction: UnverifiedEip4844Transaction,
+    /// Blobs data.
+    sidecar: BlobTransactionSidecar,
+}
+
+impl Deref for UnverifiedEip4844TransactionWithSidecar {
+    type Target = UnverifiedEip4844Transaction;
+
+    fn deref(&self) -> &Self::Target { &self.transaction }
+}
+
+impl rlp::Decodable for UnverifiedEip4844TransactionWithSidecar {
+    fn decode(d: &Rlp) -> Result<Self, DecoderError> { Ok(Self::decode_tx(d)?) }
+}
+
+/// Encodes the raw network form, see `encode_raw`.
+impl rlp::Encodable for UnverifiedEip4844TransactionWithSidecar {
+    fn rlp_append(&self, s: &mut RlpStream) { s.append_raw(&self.encode_raw(), 1); }
+}
+
+impl UnverifiedEip4844TransactionWithSidecar {
+    /// Decodes the network form `0x03 || rlp([tx_payload_body, blobs, commitments, proofs])`,
+    /// rejecting a sidecar whose commitments do not match the versioned hashes.
+    pub(crate) fn decode_tx(d: &Rlp) -> Result<Self, TxDecodeError> {
+        let list = TxFields::typed(d, TxType::Type3)?;
+        if list.item_count()? != 4 {
+            return Err(list.envelope_error(DecoderError::RlpIncorrectListLen));
+        }
+        // the consensus form of the transaction is the type byte followed by the inner list
+        let (inner, inner_offset) = list.at(0, "tx_payload_body")?;
+        let mut consensus = vec![TxType::Type3 as u8];
+        consensus.extend_from_slice(inner.as_raw());
+        let transaction = UnverifiedEip4844Transaction::decode_tx(&Rlp::new(&consensus)).map_err(|mut e| {
+            // offsets are relative to the consensus form
+            e.offset = e.offset.map(|offset| offset - 1 + inner_offset);
+            e
+        })?;
+        let sidecar = BlobTransactionSidecar {
+            blobs: list.list_at(1, "blobs")?,
+            commitments: list.list_at(2, "commitments")?,
+            proofs: list.list_at(3, "proofs")?,
+        };
+        let tx = UnverifiedEip4844TransactionWithSidecar { transaction, sidecar };
+        tx.validate_versioned_hashes()
+            .map_err(|_| list.envelope_error(DecoderError::Custom("invalid blob sidecar")))?;
+        Ok(tx)
+    }
+
+    /// Raw network form as taken by `eth_sendRawTransaction`.
+    pub fn encode_raw(&self) -> Vec<u8> {
+        let mut s = RlpStream::new_list(4);
+        self.transaction.rlp_append_signed_payload(&mut s);
+        BlobTransactionSidecar::rlp_append_items(&mut s, &self.sidecar.blobs);
+        BlobTransactionSidecar::rlp_append_items(&mut s, &self.sidecar.commitments);
+        BlobTransactionSidecar::rlp_append_items(&mut s, &self.sidecar.proofs);
+        let mut raw = vec![TxType::Type3 as u8];
+        raw.extend_from_slice(&s.out());
+        raw
+    }
+
+    /// Attaches a sidecar to a blob transaction, checking that the commitments match the versioned hashes.
+    pub fn new(transaction: UnverifiedEip4844Transaction, sidecar: BlobTransactionSidecar) -> Result<Self, Error> {
+        let tx = UnverifiedEip4844TransactionWithSidecar { transaction, sidecar };
+        tx.validate_versioned_hashes()?;
+        Ok(tx)
+    }
+
+    pub fn transaction(&self) -> &UnverifiedEip4844Transaction { &self.transaction }
+
+    pub fn sidecar(&self) -> &BlobTransactionSidecar { &self.sidecar }
+
+    /// Splits into the consensus form of the transaction and its sidecar.
+    pub fn into_parts(self) -> (UnverifiedEip4844Transaction, BlobTransactionSidecar) {
+        (self.transaction, self.sidecar)
+    }
+
+    /// Checks that every versioned hash of the transaction equals `0x01 || sha256(commitment)[1..]`.
+    pub fn validate_versioned_hashes(&self) -> Result<(), Error> {
+        self.sidecar.validate_sizes()?;
+        let hashes = &self.transaction.blob_versioned_hashes;
+        if hashes.len() != self.sidecar.commitments.len() {
+            return Err(Error::InvalidBlobSidecar(format!(
+                "{} versioned hashes but {} commitments",
+                hashes.len(),
+                self.sidecar.commitments.len()
+            )));
+        }
+        match hashes
+            .iter()
+            .zip(self.sidecar.commitments.iter())
+            .position(|(hash, commitment)| *hash != kzg_to_versioned_hash(commitment))
+        {
+            Some(index) => Err(Error::InvalidBlobSidecar(format!(
+                "versioned hash mismatch at index {}",
+                index
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks versioned hashes and verifies the KZG proofs of the sidecar.
+    #[cfg(feature = "kzg")]
+    pub fn validate_blobs(&self, settings: &KzgSettings) -> Result<(), Error> {
+        self.validate_versioned_hashes()?;
+        self.sidecar.verify_kzg_proofs(settings)
+    }
+}
@@ -0,0 +1,263 @@
+//! Eip 7702 (set code) transaction encoding/decoding and specific checks
+
+use super::AccessList;
+use super::SignedTransactionShared;
+use super::{h256_from_u256, Action, Bytes, TransactionShared, TxFields, TxType};
+use crate::{Error, TxDecodeError};
+use ethereum_types::{Address, H256, U256};
+use ethkey::{Authorization, Secret, Signature};
+use hash::keccak;
+use rlp::{self, DecoderError, Rlp, RlpStream};
+use std::{convert::TryInto, ops::Deref};
+
+/// Signed EIP-7702 authorization tuple `(chain_id, address, nonce, y_parity, r, s)`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SignedAuthorization {
+    pub authorization: Authorization,
+    pub y_parity: u8,
+    pub r: U256,
+    pub s: U256,
+}
+
+impl SignedAuthorization {
+    /// Signs the authorization with the authority secret.
+    pub fn sign(authorization: Authorization, secret: &Secret) -> Result<Self, Error> {
+        let sig = authorization.sign(secret)?;
+        Ok(SignedAuthorization {
+            authorization,
+            y_parity: sig.v(),
+            r: sig.r().into(),
+            s: sig.s().into(),
+        })
+    }
+
+    /// Construct a signature object from the tuple.
+    pub fn signature(&self) -> Signature {
+        Signature::from_rsv(&h256_from_u256(self.r), &h256_from_u256(self.s), self.y_parity)
+    }
+
+    /// Recovers the account which signed the authorization.
+    pub fn authority(&self) -> Result<Address, ethkey::Error> {
+        self.authorization.recover_authority(&self.signature())
+    }
+}
+
+impl rlp::Encodable for SignedAuthorization {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(6);
+        s.append(&self.authorization.chain_id);
+        s.append(&self.authorization.address);
+        s.append(&self.authorization.nonce);
+        s.append(&self.y_parity);
+        s.append(&self.r);
+        s.append(&self.s);
+    }
+}
+
+impl rlp::Decodable for SignedAuthorization {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        if rlp.item_count()? != 6 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+        Ok(SignedAuthorization {
+            authorization: Authorization {
+                chain_id: rlp.val_at(0)?,
+                address: rlp.val_at(1)?,
+                nonce: rlp.val_at(2)?,
+            },
+            y_parity: rlp.val_at(3)?,
+            r: rlp.val_at(4)?,
+            s: rlp.val_at(5)?,
+        })
+    }
+}
+
+/// A set of information describing an externally-originating message call
+/// which also sets code of the authorizing accounts. Contract creation is not allowed for this transaction type.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Eip7702Transaction {
+    /// Simple replay attack protection
+    pub(crate) chain_id: u64,
+    /// Nonce.
+    pub(crate) nonce: U256,
+    /// Max fee per gas.
+    pub(crate) max_fee_per_gas: U256,
+    /// Max priority fee per gas.
+    pub(crate) max_priority_fee_per_gas: U256,
+    /// Gas paid up front for transaction execution.
+    pub(crate) gas: U256,
+    /// Action, always a call for this transaction type.
+    pub(crate) action: Action,
+    /// Transfered value.
+    pub(crate) value: U256,
+    /// Transaction data.
+    pub(crate) data: Bytes,
+    /// Access list.
+    pub(crate) access_list: AccessList,
+    /// Authorizations to set code of the signing accounts.
+    pub(crate) authorization_list: Vec<SignedAuthorization>,
+}
+
+impl Eip7702Transaction {
+    const fn payload_size(&self) -> usize { 10 }
+
+    /// Append object with a without signature into RLP stream
+    fn rlp_append_unsigned_transaction(&self, s: &mut RlpStream) {
+        s.append(&(TxType::Type4 as u8));
+        s.begin_list(self.payload_size());
+        self.rlp_append_payload(s);
+    }
+
+    /// Append transaction fields (without signature) into RLP stream
+    fn rlp_append_payload(&self, s: &mut RlpStream) {
+        s.append(&self.chain_id);
+        s.append(&self.nonce);
+        s.append(&self.max_priority_fee_per_gas);
+        s.append(&self.max_fee_per_gas);
+        s.append(&self.gas);
+        s.append(&self.action);
+        s.append(&self.value);
+        s.append(&self.data);
+        s.append(&self.access_list);
+        s.begin_list(self.authorization_list.len());
+        for authorization in self.authorization_list.iter() {
+            s.append(authorization);
+        }
+    }
+
+    pub fn max_fee_per_gas(&self) -> U256 { self.max_fee_per_gas }
+
+    pub fn max_priority_fee_per_gas(&self) -> U256 { self.max_priority_fee_per_gas }
+
+    pub fn access_list(&self) -> &AccessList { &self.access_list }
+
+    pub fn authorization_list(&self) -> &[SignedAuthorization] { &self.authorization_list }
+}
+
+impl TransactionShared for Eip7702Transaction {
+    fn nonce(&self) -> U256 { self.nonce }
+    fn action(&self) -> &Action { &self.action }
+    fn value(&self) -> U256 { self.value }
+    fn gas(&self) -> U256 { self.gas }
+    fn data(&self) -> &Bytes { &self.data }
+    /// The message hash of the transaction.
+    fn message_hash(&self, _chain_id: Option<u64>) -> H256 {
+        let mut stream = RlpStream::new();
+        self.rlp_append_unsigned_transaction(&mut stream);
+        keccak(stream.as_raw())
+    }
+}
+
+impl rlp::Decodable for Eip7702Transaction {
+    fn decode(d: &Rlp) -> Result<Self, DecoderError> { Ok(Self::decode_fields(&TxFields::typed(d, TxType::Type4)?)?) }
+}
+
+impl Eip7702Transaction {
+    pub(crate) fn decode_fields(list: &TxFields) -> Result<Self, TxDecodeError> {
+        // set code transactions can't be used to create contracts, so `to` must be an address
+        let to: Address = list.val_at(5, "to")?;
+        Ok(Eip7702Transaction {
+            chain_id: list.val_at(0, "chain_id")?,
+            nonce: list.val_at(1, "nonce")?,
+            max_priority_fee_per_gas: list.val_at(2, "max_priority_fee_per_gas")?,
+            max_fee_per_gas: list.val_at(3, "max_fee_per_gas")?,
+            gas: list.val_at(4, "gas")?,
+            action: Action::Call(to),
+            value: list.val_at(6, "value")?,
+            data: list.val_at(7, "data")?,
+            access_list: list.val_at(8, "access_list")?,
+            authorization_list: list.list_at(9, "authorization_list")?,
+        })
+    }
+}
+
+/// Signed transaction information without verified signature.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UnverifiedEip7702Transaction {
+    /// Plain Transaction.
+    unsigned: Eip7702Transaction,
+    /// The V field of the signature
+    v: u64,
+    /// The R field of the signature; helps describe the point on the curve.
+    r: U256,
+    /// The S field of the signature; helps describe the point on the curve.
+    s: U256,
+    /// Hash of the transaction
+    hash: H256,
+}
+
+impl Deref for UnverifiedEip7702Transaction {
+    type Target = Eip7702Transaction;
+
+    fn deref(&self) -> &Self::Target { &self.unsigned }
+}
+
+impl rlp::Decodable for UnverifiedEip7702Transaction {
+    fn decode(d: &Rlp) -> Result<Self, DecoderError> { Ok(Self::decode_tx(d)?) }
+}
+
+impl UnverifiedEip7702Transaction {
+    pub(crate) fn decode_tx(d: &Rlp) -> Result<Self, TxDecodeError> {
+        let list = TxFields::typed(d, TxType::Type4)?;
+        let unsigned = Eip7702Transaction::decode_fields(&list)?;
+        let hash = list.hash();
+        let offset = unsigned.payload_size();
+        let v = list.val_at(offset, "v")?;
+        if !Self::validate_v(v) {
+            return Err(list.error(offset, "v", DecoderError::Custom("invalid sig v")));
+        }
+        Ok(UnverifiedEip7702Transaction {
+            unsigned,
+            v,
+            r: list.val_at(offset + 1, "r")?,
+            s: list.val_at(offset + 2, "s")?,
+            hash,
+        })
+    }
+}
+
+impl rlp::Encodable for UnverifiedEip7702Transaction {
+    fn rlp_append(&self, s: &mut RlpStream) { self.rlp_append_sealed_transaction(s) }
+}
+
+impl SignedTransactionShared for UnverifiedEip7702Transaction {
+    fn rlp_append_sealed_transaction(&self, s: &mut RlpStream) {
+        s.append(&(TxType::Type4 as u8));
+        s.begin_list(self.payload_size());
+        self.unsigned.rlp_append_payload(s);
+        s.append(&self.v);
+        s.append(&self.r);
+        s.append(&self.s);
+    }
+
+    fn set_hash(&mut self, hash: H256) { self.hash = hash; }
+}
+
+impl UnverifiedEip7702Transaction {
+    pub fn new(unsigned: Eip7702Transaction, r: U256, s: U256, v: u64, hash: H256) -> Result<Self, Error> {
+        if !Self::validate_v(v) {
+            return Err(Error::InvalidSignature("invalid sig v".into()));
+        }
+        Ok(UnverifiedEip7702Transaction {
+            unsigned,
+            r,
+            s,
+            v,
+            hash,
+        })
+    }
+
+    fn validate_v(v: u64) -> bool { (0..=1).contains(&v) }
+
+    /// tx list item count
+    fn payload_size(&self) -> usize { self.unsigned.payload_size() + 3 }
+
+    pub fn standard_v(&self) -> u8 {
+        self.v.try_into().expect("parity 0 or 1") // ensured that parity is 0 or 1 for tx type 4
+    }
+
+    pub fn r(&self) -> U256 { self.r }
+    pub fn s(&self) -> U256 { self.s }
+    pub fn v(&self) -> u64 { self.v }
+    pub fn hash(&self) -> H256 { self.hash }
+}
@@ -0,0 +1,167 @@
+//! Intrinsic gas of a transaction, charged before any execution, by fork
+
+use super::{AccessList, Action, TransactionWrapper, UnverifiedTransactionWrapper};
+use crate::Error;
+
+/// Base gas of any transaction.
+pub const TX_GAS: u64 = 21_000;
+/// Additional gas of a contract creation transaction (EIP-2).
+pub const TX_CREATE_GAS: u64 = 32_000;
+/// Gas per zero byte of calldata.
+pub const TX_DATA_ZERO_GAS: u64 = 4;
+/// Gas per non-zero byte of calldata before Istanbul.
+pub const TX_DATA_NON_ZERO_GAS_FRONTIER: u64 = 68;
+/// Gas per non-zero byte of calldata from Istanbul (EIP-2028).
+pub const TX_DATA_NON_ZERO_GAS_EIP2028: u64 = 16;
+/// Gas per address of an access list (EIP-2930).
+pub const ACCESS_LIST_ADDRESS_GAS: u64 = 2_400;
+/// Gas per storage key of an access list (EIP-2930).
+pub const ACCESS_LIST_STORAGE_KEY_GAS: u64 = 1_900;
+/// Gas per 32 bytes word of initcode (EIP-3860).
+pub const INITCODE_WORD_GAS: u64 = 2;
+/// Gas per authorization of a set code transaction (EIP-7702).
+pub const PER_EMPTY_ACCOUNT_COST: u64 = 25_000;
+/// Gas per calldata token of the calldata floor (EIP-7623).
+pub const TOTAL_COST_FLOOR_PER_TOKEN: u64 = 10;
+/// Calldata tokens per non-zero byte, a zero byte is one token (EIP-7623).
+pub const TOKENS_PER_NON_ZERO_BYTE: u64 = 4;
+
+/// Ethereum hard forks, in activation order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Fork {
+    /// Genesis rules
+    Frontier,
+    /// EIP-2, contract creation costs 53000 gas
+    Homestead,
+    /// EIP-150 gas cost changes
+    TangerineWhistle,
+    /// EIP-155 replay protection, EIP-158 state clearing
+    SpuriousDragon,
+    /// Metropolis first part
+    Byzantium,
+    /// Metropolis second part
+    Constantinople,
+    /// Constantinople without EIP-1283
+    Petersburg,
+    /// EIP-2028 cheaper calldata
+    Istanbul,
+    /// Difficulty bomb delay
+    MuirGlacier,
+    /// EIP-2930 access lists
+    Berlin,
+    /// EIP-1559 fee market
+    London,
+    /// Difficulty bomb delay
+    ArrowGlacier,
+    /// Difficulty bomb delay
+    GrayGlacier,
+    /// The Merge
+    Paris,
+    /// EIP-3860 initcode metering
+    Shanghai,
+    /// EIP-4844 blob transactions
+    Cancun,
+    /// EIP-7702 set code transactions, EIP-7623 calldata floor
+    Prague,
+    /// EIP-7825 transaction gas limit cap
+    Osaka,
+}
+
+impl Fork {
+    /// Latest fork known to this crate.
+    pub const LATEST: Fork = Fork::Osaka;
+
+    fn tx_data_non_zero_gas(self) -> u64 {
+        if self >= Fork::Istanbul {
+            TX_DATA_NON_ZERO_GAS_EIP2028
+        } else {
+            TX_DATA_NON_ZERO_GAS_FRONTIER
+        }
+    }
+}
+
+/// Computes intrinsic gas of a transaction with the given fields under `fork` rules,
+/// the EIP-7623 calldata floor included.
+pub fn intrinsic_gas(
+    data: &[u8],
+    action: &Action,
+    access_list: Option<&AccessList>,
+    authorization_count: usize,
+    fork: Fork,
+) -> u64 {
+    let is_create = *action == Action::Create;
+    let zero_bytes = data.iter().filter(|b| **b == 0).count() as u64;
+    let non_zero_bytes = data.len() as u64 - zero_bytes;
+
+    let mut gas = TX_GAS;
+    if is_create && fork >= Fork::Homestead {
+        gas += TX_CREATE_GAS;
+    }
+    gas += zero_bytes * TX_DATA_ZERO_GAS + non_zero_bytes * fork.tx_data_non_zero_gas();
+    if fork >= Fork::Berlin {
+        if let Some(access_list) = access_list {
+            for item in access_list.0.iter() {
+                gas += ACCESS_LIST_ADDRESS_GAS + item.storage_keys.len() as u64 * ACCESS_LIST_STORAGE_KEY_GAS;
+            }
+        }
+    }
+    if is_create && fork >= Fork::Shanghai {
+        gas += (data.len() as u64 + 31) / 32 * INITCODE_WORD_GAS;
+    }
+    if fork >= Fork::Prague {
+        gas += authorization_count as u64 * PER_EMPTY_ACCOUNT_COST;
+        gas = gas.max(calldata_floor_gas(zero_bytes, non_zero_bytes));
+    }
+    gas
+}
+
+/// EIP-7623 minimal gas used by a transaction, depending on its calldata only.
+fn calldata_floor_gas(zero_bytes: u64, non_zero_bytes: u64) -> u64 {
+    TX_GAS + (zero_bytes + non_zero_bytes * TOKENS_PER_NON_ZERO_BYTE) * TOTAL_COST_FLOOR_PER_TOKEN
+}
+
+impl TransactionWrapper {
+    /// Intrinsic gas of the transaction under `fork` rules.
+    pub fn intrinsic_gas(&self, fork: Fork) -> u64 {
+        let shared = self.shared();
+        let authorization_count = match self {
+            TransactionWrapper::Eip7702(tx) => tx.authorization_list().len(),
+            _ => 0,
+        };
+        intrinsic_gas(
+            shared.data(),
+            shared.action(),
+            self.access_list(),
+            authorization_count,
+            fork,
+        )
+    }
+
+    /// Checks the gas limit of the transaction covers its intrinsic gas under `fork` rules.
+    pub fn check_intrinsic_gas(&self, fork: Fork) -> Result<(), Error> {
+        let minimal = self.intrinsic_gas(fork).into();
+        let got = self.shared().gas();
+        if got < minimal {
+            return Err(Error::InsufficientGas { minimal, got });
+        }
+        Ok(())
+    }
+}
+
+impl UnverifiedTransactionWrapper {
+    /// Intrinsic gas of the transaction under `fork` rules.
+    pub fn intrinsic_gas(&self, fork: Fork) -> u64 {
+        let unsigned = self.unsigned();
+        let authorization_count = match self {
+            UnverifiedTransactionWrapper::Eip7702(tx) => tx.authorization_list().len(),
+            _ => 0,
+        };
+        intrinsic_gas(
+            unsigned.data(),
+            unsigned.action(),
+            self.access_list(),
+            authorization_count,
+            fork,
+        )
+    }
+}
@@ -0,0 +1,133 @@
+//! Append-only journal of locally submitted pending transactions
+
+use super::{Condition, PendingTransaction, SignedTransaction, UnverifiedTransactionWrapper};
+use ethereum_types::{Address, U256};
+use rlp::{Rlp, RlpStream};
+use std::{collections::HashMap,
+          fs::{self, File, OpenOptions},
+          io::{self, Write},
+          path::{Path, PathBuf}};
+
+/// Journal file of pending transactions, each record being the RLP list of the raw signed
+/// transaction and its optional condition. A later record of the same sender and nonce
+/// supersedes an earlier one.
+#[derive(Debug)]
+pub struct TransactionJournal {
+    path: PathBuf,
+    file: File,
+}
+
+impl TransactionJournal {
+    /// Opens the journal at `path` for appending, creating it if missing. A record truncated
+    /// by a crash is cut off, records appended after it would not be read back otherwise.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let bytes = fs::read(&path)?;
+        let mut complete = 0;
+        while let Some(len) = record_len(&bytes[complete..]) {
+            complete += len;
+        }
+        if complete < bytes.len() {
+            file.set_len(complete as u64)?;
+            file.sync_data()?;
+        }
+        Ok(TransactionJournal { path, file })
+    }
+
+    pub fn path(&self) -> &Path { &self.path }
+
+    /// Appends the transaction and syncs it to disk.
+    pub fn insert(&mut self, tx: &PendingTransaction) -> io::Result<()> {
+        self.file.write_all(&encode_record(tx))?;
+        self.file.sync_data()
+    }
+
+    /// Reads the journal, keeping the latest record of each sender and nonce. Records with
+    /// a nonce below the one `account_nonce` gives for their sender are dropped, as well as
+    /// records which don't decode and a record truncated by a crash.
+    pub fn load<F>(&self, account_nonce: F) -> io::Result<Vec<PendingTransaction>>
+    where
+        F: Fn(&Address) -> Option<U256>,
+    {
+        let bytes = fs::read(&self.path)?;
+        let mut txs: Vec<Option<PendingTransaction>> = Vec::new();
+        let mut latest: HashMap<(Address, U256), usize> = HashMap::new();
+        let mut offset = 0;
+        while let Some(len) = record_len(&bytes[offset..]) {
+            let record = decode_record(&bytes[offset..offset + len]);
+            offset += len;
+
+            let tx = match record {
+                Some(tx) => tx,
+                None => continue,
+            };
+            let key = (tx.sender, tx.unsigned().nonce());
+            if account_nonce(&key.0).map_or(false, |nonce| key.1 < nonce) {
+                continue;
+            }
+            if let Some(index) = latest.insert(key, txs.len()) {
+                txs[index] = None;
+            }
+            txs.push(Some(tx));
+        }
+        Ok(txs.into_iter().flatten().collect())
+    }
+
+    /// Replaces the journal content with the transactions, as the pool holds them.
+    pub fn rotate<'a, I>(&mut self, txs: I) -> io::Result<()>
+    where
+        I: IntoIterator<Item = &'a PendingTransaction>,
+    {
+        let new_path = self.path.with_extension("new");
+        {
+            let mut new_file = File::create(&new_path)?;
+            for tx in txs {
+                new_file.write_all(&encode_record(tx))?;
+            }
+            new_file.sync_all()?;
+        }
+        fs::rename(&new_path, &self.path)?;
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        Ok(())
+    }
+
+    /// Rewrites the journal without superseded and stale records, returns the number of
+    /// records kept.
+    pub fn compact<F>(&mut self, account_nonce: F) -> io::Result<usize>
+    where
+        F: Fn(&Address) -> Option<U256>,
+    {
+        let txs = self.load(account_nonce)?;
+        self.rotate(&txs)?;
+        Ok(txs.len())
+    }
+}
+
+fn encode_record(tx: &PendingTransaction) -> Vec<u8> {
+    let mut s = RlpStream::new_list(2);
+    s.append(&tx.transaction.encode_raw());
+    match tx.condition {
+        Some(ref condition) => s.begin_list(1).append(condition),
+        None => s.begin_list(0),
+    };
+    s.out().to_vec()
+}
+
+/// Length of the first record, `None` if it is truncated or there is none.
+fn record_len(bytes: &[u8]) -> Option<usize> {
+    if bytes.is_empty() {
+        return None;
+    }
+    let info = Rlp::new(bytes).payload_info().ok()?;
+    Some(info.header_len + info.value_len).filter(|len| *len <= bytes.len())
+}
+
+fn decode_record(bytes: &[u8]) -> Option<PendingTransaction> {
+    let record = Rlp::new(bytes);
+    let raw: Vec<u8> = record.val_at(0).ok()?;
+    let condition: Vec<Condition> = record.list_at(1).ok()?;
+    let tx: UnverifiedTransactionWrapper = rlp::decode(&raw).ok()?;
+    let signed = SignedTransaction::new(tx).ok()?;
+    Some(PendingTransaction::new(signed, condition.into_iter().next()))
+}
@@ -2,8 +2,8 @@
 //
 //! Legacy transaction encoding/decoding and specific checks
 
-use super::{Action, Bytes, TransactionShared};
-use crate::{Error, SignedTransactionShared};
+use super::{Action, Bytes, TransactionShared, TxFields};
+use crate::{Error, SignedTransactionShared, TxDecodeError};
 use ethereum_types::{H256, U256};
 use hash::keccak;
 use rlp::{self, DecoderError, Rlp, RlpStream};
@@ -72,13 +72,19 @@ impl TransactionShared for LegacyTransaction {
 
 impl rlp::Decodable for LegacyTransaction {
     fn decode(d: &Rlp) -> Result<Self, DecoderError> {
+        Ok(Self::decode_fields(&TxFields::new(Rlp::new(d.as_raw()), None, 0))?)
+    }
+}
+
+impl LegacyTransaction {
+    fn decode_fields(list: &TxFields) -> Result<Self, TxDecodeError> {
         Ok(LegacyTransaction {
-            nonce: d.val_at(0)?,
-            gas_price: d.val_at(1)?,
-            gas: d.val_at(2)?,
-            action: d.val_at(3)?,
-            value: d.val_at(4)?,
-            data: d.val_at(5)?,
+            nonce: list.val_at(0, "nonce")?,
+            gas_price: list.val_at(1, "gas_price")?,
+            gas: list.val_at(2, "gas")?,
+            action: list.val_at(3, "action")?,
+            value: list.val_at(4, "value")?,
+            data: list.val_at(5, "data")?,
         })
     }
 }
@@ -91,7 +97,7 @@ pub struct UnverifiedLegacyTransaction {
     /// The V field of the signature; the LS bit described which half of the curve our point falls
     /// in. The MS bits describe which chain this transaction is for. If 27/28, its for all chains.
     /// normally fixed with chain_id for Eip155
-    network_v: u64,
+    network_v: U256,
     /// The R field of the signature; helps describe the point on the curve.
     r: U256,
     /// The S field of the signature; helps describe the point on the curve.
@@ -107,16 +113,21 @@ impl Deref for UnverifiedLegacyTransaction {
 }
 
 impl rlp::Decodable for UnverifiedLegacyTransaction {
-    fn decode(d: &Rlp) -> Result<Self, DecoderError> {
-        if d.item_count()? != 9 {
-            return Err(DecoderError::RlpIncorrectListLen);
+    fn decode(d: &Rlp) -> Result<Self, DecoderError> { Ok(Self::decode_tx(d)?) }
+}
+
+impl UnverifiedLegacyTransaction {
+    pub(crate) fn decode_tx(d: &Rlp) -> Result<Self, TxDecodeError> {
+        let list = TxFields::new(Rlp::new(d.as_raw()), None, 0);
+        if list.item_count()? != 9 {
+            return Err(list.envelope_error(DecoderError::RlpIncorrectListLen));
         }
         let hash = keccak(d.as_raw());
         Ok(UnverifiedLegacyTransaction {
-            unsigned: LegacyTransaction::decode(d)?,
-            network_v: d.val_at(6)?,
-            r: d.val_at(7)?,
-            s: d.val_at(8)?,
+            unsigned: LegacyTransaction::decode_fields(&list)?,
+            network_v: list.val_at(6, "v")?,
+            r: list.val_at(7, "r")?,
+            s: list.val_at(8, "s")?,
             hash,
         })
     }
@@ -127,6 +138,19 @@ impl rlp::Encodable for UnverifiedLegacyTransaction {
 }
 
 impl SignedTransactionShared for UnverifiedLegacyTransaction {
+    fn rlp_append_sealed_transaction(&self, s: &mut RlpStream) {
+        s.begin_list(9);
+        s.append(&self.nonce);
+        s.append(&self.gas_price);
+        s.append(&self.gas);
+        s.append(&self.action);
+        s.append(&self.value);
+        s.append(&self.data);
+        s.append(&self.network_v);
+        s.append(&self.r);
+        s.append(&self.s);
+    }
+
     fn set_hash(&mut self, hash: H256) { self.hash = hash; }
 }
 
@@ -157,7 +181,7 @@ impl UnverifiedLegacyTransaction {
         unsigned: LegacyTransaction,
         r: U256,
         s: U256,
-        network_v: u64,
+        network_v: U256,
         hash: H256,
     ) -> Result<Self, Error> {
         Ok(UnverifiedLegacyTransaction {
@@ -169,45 +193,51 @@ impl UnverifiedLegacyTransaction {
         })
     }
 
-    /// Append object with a signature into RLP stream
-    pub(crate) fn rlp_append_sealed_transaction(&self, s: &mut RlpStream) {
-        s.begin_list(9);
-        s.append(&self.nonce);
-        s.append(&self.gas_price);
-        s.append(&self.gas);
-        s.append(&self.action);
-        s.append(&self.value);
-        s.append(&self.data);
-        s.append(&self.network_v);
-        s.append(&self.r);
-        s.append(&self.s);
-    }
-
-    pub fn standard_v(&self) -> u8 { eip155_methods::check_replay_protection(self.network_v) }
+    pub fn standard_v(&self) -> Result<u8, ethkey::Error> { eip155_methods::check_replay_protection(self.network_v) }
 
-    fn to_network_v(v: u64, chain_id: Option<u64>) -> u64 { eip155_methods::add_chain_replay_protection(v, chain_id) }
+    fn to_network_v(v: u64, chain_id: Option<u64>) -> U256 { eip155_methods::add_chain_replay_protection(v, chain_id) }
 
     pub fn r(&self) -> U256 { self.r }
     pub fn s(&self) -> U256 { self.s }
-    pub fn v(&self) -> u64 { self.network_v }
+    pub fn v(&self) -> U256 { self.network_v }
     pub fn hash(&self) -> H256 { self.hash }
 }
 
 /// Replay protection logic for v part of transaction's signature
 pub mod eip155_methods {
-    /// Adds chain id into v
-    pub fn add_chain_replay_protection(v: u64, chain_id: Option<u64>) -> u64 {
-        v + if let Some(n) = chain_id { 35 + n * 2 } else { 27 }
+    use ethereum_types::U256;
+    use std::convert::TryFrom;
+
+    /// Adds chain id into v.
+    /// Computed in `U256` as `35 + chain_id * 2` overflows `u64` for chain ids above 2^63.
+    pub fn add_chain_replay_protection(v: u64, chain_id: Option<u64>) -> U256 {
+        U256::from(v)
+            + if let Some(n) = chain_id {
+                U256::from(n) * 2 + 35
+            } else {
+                U256::from(27)
+            }
+    }
+
+    /// Returns chain id encoded into v by EIP-155,
+    /// `None` if v is not EIP-155 encoded or the encoded chain id does not fit into `u64`.
+    pub fn chain_id_from_v(v: U256) -> Option<u64> {
+        if v > U256::from(36) {
+            u64::try_from((v - 35) / 2).ok()
+        } else {
+            None
+        }
     }
 
     /// Returns refined v
-    /// 0 if `v` would have been 27 under "Electrum" notation, 1 if 28 or 4 if invalid.
-    pub fn check_replay_protection(v: u64) -> u8 {
+    /// 0 if `v` would have been 27 under "Electrum" notation, 1 if 28.
+    /// Fails if `v` is neither 27/28 nor EIP-155 encoded with a chain id fitting into `u64`.
+    pub fn check_replay_protection(v: U256) -> Result<u8, ethkey::Error> {
         match v {
-            v if v == 27 => 0,
-            v if v == 28 => 1,
-            v if v > 36 => ((v - 1) % 2) as u8,
-            _ => 4,
+            v if v == U256::from(27) => Ok(0),
+            v if v == U256::from(28) => Ok(1),
+            v if chain_id_from_v(v).is_some() => Ok(((v - 1) % 2).low_u64() as u8),
+            _ => Err(ethkey::Error::InvalidSignature),
         }
     }
 }
@@ -0,0 +1,377 @@
+//! Nonce reservation shared by the processes signing for the same accounts
+
+use crate::LeaseExpired;
+use ethereum_types::{Address, U256};
+use fs2::FileExt;
+use rlp::{Rlp, RlpStream};
+use std::{collections::{BTreeMap, BTreeSet},
+          fs::{self, File, OpenOptions},
+          io::{self, Write},
+          path::{Path, PathBuf},
+          sync::atomic::{AtomicU32, Ordering},
+          time::{Duration, SystemTime, UNIX_EPOCH}};
+
+/// Time a reservation is held by default before its nonce may be reclaimed.
+pub const DEFAULT_NONCE_LEASE: Duration = Duration::from_secs(600);
+
+/// Reservations made by this process, telling apart reservations of the same nonce.
+static RESERVATIONS: AtomicU32 = AtomicU32::new(0);
+
+/// Holder of a reserved nonce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Lease {
+    /// Id of the process in the high bits, number of the reservation in the process in the low ones.
+    owner: u64,
+    /// Unix time in milliseconds from which the nonce may be reclaimed.
+    expires: u64,
+}
+
+/// Nonces of a sender as the manager tracks them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct SenderNonces {
+    /// Nonce after the highest one handed out.
+    next: U256,
+    /// Nonces handed out and neither committed nor released yet.
+    reserved: BTreeMap<U256, Lease>,
+    /// Nonces below `next` released by an abandoned signing, handed out again first.
+    released: BTreeSet<U256>,
+}
+
+impl SenderNonces {
+    fn reserve(&mut self, lease: Lease) -> U256 {
+        let nonce = match self.released.iter().next().cloned() {
+            Some(nonce) => {
+                self.released.remove(&nonce);
+                nonce
+            },
+            None => {
+                self.next = self.next + 1;
+                self.next - 1
+            },
+        };
+        self.reserved.insert(nonce, lease);
+        nonce
+    }
+
+    /// Removes the reservation of the nonce if `owner` still holds it.
+    fn commit(&mut self, nonce: U256, owner: u64) -> bool {
+        if self.reserved.get(&nonce).map(|lease| lease.owner) != Some(owner) {
+            return false;
+        }
+        self.reserved.remove(&nonce);
+        true
+    }
+
+    /// Extends the lease of the nonce if `owner` still holds it.
+    fn renew(&mut self, nonce: U256, owner: u64, expires: u64) -> bool {
+        match self.reserved.get_mut(&nonce) {
+            Some(lease) if lease.owner == owner => {
+                lease.expires = expires;
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn release(&mut self, nonce: U256, owner: u64) {
+        if !self.commit(nonce, owner) {
+            return;
+        }
+        self.released.insert(nonce);
+        while !self.next.is_zero() && self.released.remove(&(self.next - 1)) {
+            self.next = self.next - 1;
+        }
+    }
+
+    fn reconcile(&mut self, chain_nonce: U256) {
+        self.reserved = self.reserved.split_off(&chain_nonce);
+        if self.reserved.is_empty() {
+            self.next = chain_nonce;
+            self.released.clear();
+        } else {
+            self.next = self.next.max(chain_nonce);
+            self.released = self.released.split_off(&chain_nonce);
+        }
+    }
+
+    /// Releases the nonces with a lease expired at `now`, their worker having died.
+    fn reclaim(&mut self, now: u64) {
+        let expired: Vec<_> = self
+            .reserved
+            .iter()
+            .filter(|(_, lease)| lease.expires <= now)
+            .map(|(nonce, lease)| (*nonce, lease.owner))
+            .collect();
+        for (nonce, owner) in expired {
+            self.release(nonce, owner);
+        }
+    }
+}
+
+/// Hands out nonces to the workers signing for the same senders, possibly from several
+/// processes. The state lives in a file, every operation holding an exclusive advisory
+/// lock on a `.lock` file next to it, so that no nonce is handed out twice. The state is
+/// written to a `.new` file first and renamed over the old one, a crash leaves either.
+///
+/// A nonce is reserved until its transaction is committed, or released if signing is
+/// abandoned. A released nonce is handed out again before new ones, so no gap is left.
+/// A reservation is leased, once the lease expires `reserve` and `reconcile` reclaim
+/// its nonce, as its worker may have died without releasing it. A worker renews the lease
+/// right before sending its transaction, the nonce may be used by another one otherwise.
+#[derive(Debug, Clone)]
+pub struct NonceManager {
+    path: PathBuf,
+    lock_path: PathBuf,
+    lease: Duration,
+}
+
+impl NonceManager {
+    /// Opens the state file at `path`, it is created on the first change.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let lock_path = path.with_extension("lock");
+        OpenOptions::new().create(true).write(true).open(&lock_path)?;
+        Ok(NonceManager {
+            path,
+            lock_path,
+            lease: DEFAULT_NONCE_LEASE,
+        })
+    }
+
+    /// Sets the time reservations are held, `DEFAULT_NONCE_LEASE` otherwise. It should exceed the
+    /// time of signing and sending a transaction.
+    pub fn with_lease(mut self, lease: Duration) -> Self {
+        self.lease = lease;
+        self
+    }
+
+    pub fn path(&self) -> &Path { &self.path }
+
+    /// Reserves the next nonce of the sender, nonces of expired reservations first. A sender
+    /// unknown to the manager starts at nonce zero, `reconcile` it with the chain first.
+    pub fn reserve(&self, sender: Address) -> io::Result<NonceReservation> {
+        let now = unix_millis();
+        let lease = Lease {
+            owner: (u64::from(std::process::id()) << 32) | u64::from(RESERVATIONS.fetch_add(1, Ordering::Relaxed)),
+            expires: self.expires(now),
+        };
+        let nonce = self.update(|senders| {
+            let nonces = senders.entry(sender).or_default();
+            nonces.reclaim(now);
+            nonces.reserve(lease)
+        })?;
+        Ok(NonceReservation {
+            manager: self,
+            sender,
+            nonce,
+            owner: lease.owner,
+            done: false,
+        })
+    }
+
+    /// Nonce after the highest one handed out to the sender.
+    pub fn next_nonce(&self, sender: &Address) -> io::Result<Option<U256>> {
+        self.update(|senders| senders.get(sender).map(|nonces| nonces.next))
+    }
+
+    /// Nonces of the sender reserved and neither committed nor released yet.
+    pub fn reserved(&self, sender: &Address) -> io::Result<Vec<U256>> {
+        self.update(|senders| {
+            senders
+                .get(sender)
+                .map_or_else(Vec::new, |nonces| nonces.reserved.keys().cloned().collect())
+        })
+    }
+
+    /// Aligns the sender with its pending account nonce on chain, expired reservations being
+    /// reclaimed first. Nonces below it are forgotten, a reservation below it being stale.
+    /// New nonces start at it if no nonce is reserved any more, at it at least otherwise, as
+    /// the transactions of the nonces reserved above it are yet to be sent.
+    pub fn reconcile(&self, sender: Address, chain_nonce: U256) -> io::Result<()> {
+        let now = unix_millis();
+        self.update(|senders| {
+            let nonces = senders.entry(sender).or_default();
+            nonces.reclaim(now);
+            nonces.reconcile(chain_nonce);
+        })
+    }
+
+    fn commit(&self, sender: &Address, nonce: U256, owner: u64) -> io::Result<()> {
+        let held = self.update(|senders| {
+            senders
+                .get_mut(sender)
+                .map_or(false, |nonces| nonces.commit(nonce, owner))
+        })?;
+        lease_result(held, sender, nonce)
+    }
+
+    fn renew(&self, sender: &Address, nonce: U256, owner: u64) -> io::Result<()> {
+        let expires = self.expires(unix_millis());
+        let held = self.update(|senders| {
+            senders
+                .get_mut(sender)
+                .map_or(false, |nonces| nonces.renew(nonce, owner, expires))
+        })?;
+        lease_result(held, sender, nonce)
+    }
+
+    fn release(&self, sender: &Address, nonce: U256, owner: u64) -> io::Result<()> {
+        self.update(|senders| {
+            if let Some(nonces) = senders.get_mut(sender) {
+                nonces.release(nonce, owner);
+            }
+        })
+    }
+
+    fn expires(&self, now: u64) -> u64 { now.saturating_add(self.lease.as_millis() as u64) }
+
+    /// Runs `f` on the state with the lock file locked, writes the state back if `f` changed it.
+    fn update<F, R>(&self, f: F) -> io::Result<R>
+    where
+        F: FnOnce(&mut BTreeMap<Address, SenderNonces>) -> R,
+    {
+        // a file of its own, as threads sharing one would share its lock
+        let lock = OpenOptions::new().create(true).write(true).open(&self.lock_path)?;
+        lock.lock_exclusive()?;
+        let result = self.update_locked(f);
+        lock.unlock()?;
+        result
+    }
+
+    fn update_locked<F, R>(&self, f: F) -> io::Result<R>
+    where
+        F: FnOnce(&mut BTreeMap<Address, SenderNonces>) -> R,
+    {
+        let bytes = match fs::read(&self.path) {
+            Ok(bytes) => bytes,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err),
+        };
+        let mut senders = decode_state(&bytes)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("invalid nonce state: {}", err)))?;
+        let before = senders.clone();
+        let result = f(&mut senders);
+        if senders != before {
+            let new_path = self.path.with_extension("new");
+            {
+                let mut new_file = File::create(&new_path)?;
+                new_file.write_all(&encode_state(&senders))?;
+                new_file.sync_all()?;
+            }
+            fs::rename(&new_path, &self.path)?;
+        }
+        Ok(result)
+    }
+}
+
+/// `LeaseExpired` error if the reservation is no longer held.
+fn lease_result(held: bool, sender: &Address, nonce: U256) -> io::Result<()> {
+    if held {
+        Ok(())
+    } else {
+        Err(io::Error::new(io::ErrorKind::Other, LeaseExpired {
+            sender: *sender,
+            nonce,
+        }))
+    }
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+fn encode_state(senders: &BTreeMap<Address, SenderNonces>) -> Vec<u8> {
+    let mut s = RlpStream::new_list(senders.len());
+    for (sender, nonces) in senders {
+        s.begin_list(4).append(sender).append(&nonces.next);
+        s.begin_list(nonces.reserved.len());
+        for (nonce, lease) in &nonces.reserved {
+            s.begin_list(3)
+                .append(nonce)
+                .append(&lease.owner)
+                .append(&lease.expires);
+        }
+        s.append_list(&nonces.released.iter().cloned().collect::<Vec<_>>());
+    }
+    s.out().to_vec()
+}
+
+fn decode_state(bytes: &[u8]) -> Result<BTreeMap<Address, SenderNonces>, rlp::DecoderError> {
+    if bytes.is_empty() {
+        return Ok(BTreeMap::new());
+    }
+    let state = Rlp::new(bytes);
+    if !state.is_list() {
+        return Err(rlp::DecoderError::RlpExpectedToBeList);
+    }
+    let info = state.payload_info()?;
+    if info.header_len + info.value_len != bytes.len() {
+        return Err(rlp::DecoderError::RlpInconsistentLengthAndData);
+    }
+    let mut senders = BTreeMap::new();
+    for entry in state.iter() {
+        let nonces = SenderNonces {
+            next: entry.val_at(1)?,
+            reserved: entry
+                .at(2)?
+                .iter()
+                .map(|reservation| {
+                    let lease = Lease {
+                        owner: reservation.val_at(1)?,
+                        expires: reservation.val_at(2)?,
+                    };
+                    Ok((reservation.val_at(0)?, lease))
+                })
+                .collect::<Result<_, rlp::DecoderError>>()?,
+            released: entry.list_at::<U256>(3)?.into_iter().collect(),
+        };
+        senders.insert(entry.val_at(0)?, nonces);
+    }
+    Ok(senders)
+}
+
+/// Nonce reserved for a transaction of the sender. Dropping it without `commit` releases
+/// the nonce, as when signing fails or is abandoned. Once its lease expired, the nonce may
+/// be reclaimed and handed out again, `renew` and `commit` then fail with a `LeaseExpired`
+/// error and `release` leaves the nonce to its new holder.
+#[derive(Debug)]
+pub struct NonceReservation<'a> {
+    manager: &'a NonceManager,
+    sender: Address,
+    nonce: U256,
+    owner: u64,
+    done: bool,
+}
+
+impl<'a> NonceReservation<'a> {
+    pub fn sender(&self) -> Address { self.sender }
+
+    pub fn nonce(&self) -> U256 { self.nonce }
+
+    /// Extends the lease, to be called right before sending the transaction. Fails with a
+    /// `LeaseExpired` error if the nonce was reclaimed, the transaction must not be sent then.
+    pub fn renew(&self) -> io::Result<()> { self.manager.renew(&self.sender, self.nonce, self.owner) }
+
+    /// Marks the nonce as used, once its transaction is signed and sent. Fails with a
+    /// `LeaseExpired` error if the nonce was reclaimed meanwhile.
+    pub fn commit(mut self) -> io::Result<()> {
+        self.done = true;
+        self.manager.commit(&self.sender, self.nonce, self.owner)
+    }
+
+    /// Gives the nonce back, to be handed out again.
+    pub fn release(mut self) -> io::Result<()> {
+        self.done = true;
+        self.manager.release(&self.sender, self.nonce, self.owner)
+    }
+}
+
+impl<'a> Drop for NonceReservation<'a> {
+    fn drop(&mut self) {
+        if !self.done {
+            let _ = self.manager.release(&self.sender, self.nonce, self.owner);
+        }
+    }
+}
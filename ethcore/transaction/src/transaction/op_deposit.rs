@@ -0,0 +1,122 @@
+//! OP-Stack deposit transaction (type 0x7E) encoding/decoding
+
+use super::SignedTransactionShared;
+use super::{Action, Bytes, TransactionShared, TxFields, TxType};
+use crate::TxDecodeError;
+use ethereum_types::{Address, H256, U256};
+use rlp::{self, DecoderError, Rlp, RlpStream};
+
+/// L1 originated transaction included by the sequencer. It carries no signature,
+/// the sender is set explicitly by the rollup derivation.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct DepositTransaction {
+    /// Uniquely identifies the source of the deposit.
+    pub(crate) source_hash: H256,
+    /// Sender of the deposit.
+    pub(crate) from: Address,
+    /// Action, can be either call or contract create.
+    pub(crate) action: Action,
+    /// ETH value to mint on L2.
+    pub(crate) mint: U256,
+    /// Transfered value.
+    pub(crate) value: U256,
+    /// Gas limit of the L2 transaction.
+    pub(crate) gas: U256,
+    /// Whether the transaction is exempt from the L2 gas limit.
+    pub(crate) is_system_tx: bool,
+    /// Transaction data.
+    pub(crate) data: Bytes,
+    /// Hash of the transaction
+    pub(crate) hash: H256,
+}
+
+impl DepositTransaction {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        source_hash: H256,
+        from: Address,
+        action: Action,
+        mint: U256,
+        value: U256,
+        gas: U256,
+        is_system_tx: bool,
+        data: Bytes,
+    ) -> Self {
+        DepositTransaction {
+            source_hash,
+            from,
+            action,
+            mint,
+            value,
+            gas,
+            is_system_tx,
+            data,
+            hash: H256::zero(),
+        }
+        .compute_hash()
+    }
+
+    const fn payload_size(&self) -> usize { 8 }
+
+    pub fn source_hash(&self) -> H256 { self.source_hash }
+
+    pub fn from(&self) -> Address { self.from }
+
+    pub fn mint(&self) -> U256 { self.mint }
+
+    pub fn is_system_tx(&self) -> bool { self.is_system_tx }
+
+    pub fn hash(&self) -> H256 { self.hash }
+}
+
+impl TransactionShared for DepositTransaction {
+    fn nonce(&self) -> U256 { U256::zero() }
+    fn action(&self) -> &Action { &self.action }
+    fn value(&self) -> U256 { self.value }
+    fn gas(&self) -> U256 { self.gas }
+    fn data(&self) -> &Bytes { &self.data }
+    /// Deposits are not signed, so this is the transaction hash.
+    fn message_hash(&self, _chain_id: Option<u64>) -> H256 { self.hash }
+}
+
+impl rlp::Decodable for DepositTransaction {
+    fn decode(d: &Rlp) -> Result<Self, DecoderError> { Ok(Self::decode_tx(d)?) }
+}
+
+impl DepositTransaction {
+    pub(crate) fn decode_tx(d: &Rlp) -> Result<Self, TxDecodeError> {
+        let list = TxFields::typed(d, TxType::Deposit)?;
+        Ok(DepositTransaction {
+            source_hash: list.val_at(0, "source_hash")?,
+            from: list.val_at(1, "from")?,
+            action: list.val_at(2, "action")?,
+            mint: list.val_at(3, "mint")?,
+            value: list.val_at(4, "value")?,
+            gas: list.val_at(5, "gas")?,
+            is_system_tx: list.val_at(6, "is_system_tx")?,
+            data: list.val_at(7, "data")?,
+            hash: list.hash(),
+        })
+    }
+}
+
+impl rlp::Encodable for DepositTransaction {
+    fn rlp_append(&self, s: &mut RlpStream) { self.rlp_append_sealed_transaction(s) }
+}
+
+impl SignedTransactionShared for DepositTransaction {
+    fn rlp_append_sealed_transaction(&self, s: &mut RlpStream) {
+        s.append(&(TxType::Deposit as u8));
+        s.begin_list(self.payload_size());
+        s.append(&self.source_hash);
+        s.append(&self.from);
+        s.append(&self.action);
+        s.append(&self.mint);
+        s.append(&self.value);
+        s.append(&self.gas);
+        s.append(&self.is_system_tx);
+        s.append(&self.data);
+    }
+
+    fn set_hash(&mut self, hash: H256) { self.hash = hash; }
+}
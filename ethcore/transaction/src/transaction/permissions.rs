@@ -0,0 +1,108 @@
+//! Per sender permissions to send transactions, as Parity permission contracts granted them
+
+use super::{Action, SignedTransaction};
+use crate::Error;
+use ethereum_types::Address;
+use std::collections::HashMap;
+
+/// Kind of transaction a sender may be allowed to send.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionKind {
+    /// Value transfer or contract call
+    Call,
+    /// Contract creation
+    Create,
+    /// Private transaction, the call or creation kind is required as well
+    Private,
+}
+
+/// Kinds of transactions allowed to a sender.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Permissions {
+    pub call: bool,
+    pub create: bool,
+    pub private: bool,
+}
+
+impl Permissions {
+    pub const NONE: Permissions = Permissions {
+        call: false,
+        create: false,
+        private: false,
+    };
+    pub const CALL: Permissions = Permissions {
+        call: true,
+        create: false,
+        private: false,
+    };
+    pub const ALL: Permissions = Permissions {
+        call: true,
+        create: true,
+        private: true,
+    };
+
+    pub fn allows(&self, kind: TransactionKind) -> bool {
+        match kind {
+            TransactionKind::Call => self.call,
+            TransactionKind::Create => self.create,
+            TransactionKind::Private => self.private,
+        }
+    }
+}
+
+/// Decides which transactions a sender may send.
+pub trait TransactionPermissions {
+    fn is_allowed(&self, sender: &Address, kind: TransactionKind) -> bool;
+
+    /// Checks the sender may send the transaction, as a private one if `private`.
+    fn verify(&self, tx: &SignedTransaction, private: bool) -> Result<(), Error> {
+        let kind = match tx.unsigned().action() {
+            Action::Call(_) => TransactionKind::Call,
+            Action::Create => TransactionKind::Create,
+        };
+        if !self.is_allowed(&tx.sender, kind) || (private && !self.is_allowed(&tx.sender, TransactionKind::Private)) {
+            return Err(Error::NotAllowed);
+        }
+        Ok(())
+    }
+}
+
+/// Same permissions for every sender.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StaticPermissions(pub Permissions);
+
+impl TransactionPermissions for StaticPermissions {
+    fn is_allowed(&self, _sender: &Address, kind: TransactionKind) -> bool { self.0.allows(kind) }
+}
+
+/// Permissions granted per sender, senders missing from the map get the default ones.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AllowMapPermissions {
+    senders: HashMap<Address, Permissions>,
+    default: Permissions,
+}
+
+impl AllowMapPermissions {
+    /// Map refusing any transaction of senders it doesn't know.
+    pub fn new() -> Self { AllowMapPermissions::default() }
+
+    pub fn with_default(default: Permissions) -> Self {
+        AllowMapPermissions {
+            senders: HashMap::new(),
+            default,
+        }
+    }
+
+    pub fn allow(&mut self, sender: Address, permissions: Permissions) { self.senders.insert(sender, permissions); }
+
+    /// Gives the sender the default permissions back, returns the ones it had.
+    pub fn revoke(&mut self, sender: &Address) -> Option<Permissions> { self.senders.remove(sender) }
+
+    pub fn permissions(&self, sender: &Address) -> Permissions {
+        self.senders.get(sender).cloned().unwrap_or(self.default)
+    }
+}
+
+impl TransactionPermissions for AllowMapPermissions {
+    fn is_allowed(&self, sender: &Address, kind: TransactionKind) -> bool { self.permissions(sender).allows(kind) }
+}
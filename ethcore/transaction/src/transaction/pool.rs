@@ -0,0 +1,311 @@
+//! In-memory pool of local transactions with per sender nonce queues
+
+use super::{tx_builders::MIN_BLOB_REPLACEMENT_BUMP_PERCENT, BlockNumber, PendingTransaction,
+            UnverifiedTransactionWrapper};
+use crate::Error;
+use ethereum_types::{Address, H256, U256};
+use std::{cmp::Reverse,
+          collections::{BTreeMap, BinaryHeap, HashMap}};
+
+/// Limits and replacement rule of a `TransactionPool`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolOptions {
+    /// Max number of transactions in the pool.
+    pub max_count: usize,
+    /// Max number of transactions of a single sender.
+    pub max_per_sender: usize,
+    /// Percentage a replacement must raise both the max fee and the max priority fee by.
+    pub fee_bump_percent: u64,
+}
+
+impl Default for PoolOptions {
+    fn default() -> Self {
+        PoolOptions {
+            max_count: 8192,
+            max_per_sender: 16,
+            fee_bump_percent: 10,
+        }
+    }
+}
+
+/// Pool of transactions waiting for inclusion, ordered by effective tip.
+///
+/// Transactions of a sender are ready from its account nonce on, up to the first missing nonce.
+/// Until `set_account_nonce` is called for a sender, its lowest queued nonce is taken instead.
+#[derive(Debug, Default)]
+pub struct TransactionPool {
+    options: PoolOptions,
+    base_fee: Option<U256>,
+    /// Transactions of each sender by nonce.
+    queues: HashMap<Address, BTreeMap<U256, PendingTransaction>>,
+    /// Sender and nonce of each transaction by hash.
+    hashes: HashMap<H256, (Address, U256)>,
+    /// Account nonces of senders.
+    nonces: HashMap<Address, U256>,
+}
+
+impl TransactionPool {
+    pub fn new(options: PoolOptions) -> Self {
+        TransactionPool {
+            options,
+            ..Default::default()
+        }
+    }
+
+    pub fn len(&self) -> usize { self.hashes.len() }
+
+    pub fn is_empty(&self) -> bool { self.hashes.is_empty() }
+
+    pub fn base_fee(&self) -> Option<U256> { self.base_fee }
+
+    pub fn get(&self, hash: &H256) -> Option<&PendingTransaction> {
+        let (sender, nonce) = self.hashes.get(hash)?;
+        self.queues.get(sender)?.get(nonce)
+    }
+
+    /// Tip per gas the block producer earns at the current base fee.
+    pub fn effective_tip(&self, tx: &PendingTransaction) -> U256 {
+        tx.effective_gas_price(self.base_fee)
+            .saturating_sub(self.base_fee.unwrap_or_default())
+    }
+
+    /// Adds the transaction, returns the transactions it replaced or evicted.
+    ///
+    /// A transaction with the nonce of a queued one replaces it only if it raises both fees
+    /// by the fee bump. A blob transaction raises its blob fee too, all by at least
+    /// `MIN_BLOB_REPLACEMENT_BUMP_PERCENT`. A full pool evicts the last queued transaction
+    /// with the lowest tip if the new one pays a higher tip.
+    pub fn import(&mut self, tx: PendingTransaction) -> Result<Vec<PendingTransaction>, Error> {
+        if self.hashes.contains_key(&tx.tx_hash()) {
+            return Err(Error::AlreadyImported);
+        }
+        let sender = tx.sender;
+        let nonce = tx.unsigned().nonce();
+        if self
+            .nonces
+            .get(&sender)
+            .map_or(false, |account_nonce| nonce < *account_nonce)
+        {
+            return Err(Error::Old);
+        }
+        if let Some(base_fee) = self.base_fee {
+            let max_fee = tx.max_fee_per_gas();
+            if max_fee < base_fee {
+                return Err(Error::InsufficientGasPrice {
+                    minimal: base_fee,
+                    got: max_fee,
+                });
+            }
+        }
+
+        let queue = self.queues.get(&sender);
+        if let Some(old) = queue.and_then(|queue| queue.get(&nonce)) {
+            if !self.is_replacement(old, &tx) {
+                return Err(Error::TooCheapToReplace);
+            }
+            return Ok(self.insert(tx).into_iter().collect());
+        }
+        if queue.map_or(0, BTreeMap::len) >= self.options.max_per_sender {
+            return Err(Error::LimitReached);
+        }
+        let mut dropped = Vec::new();
+        if self.len() >= self.options.max_count {
+            match self.worst_last() {
+                Some((worst_sender, worst_nonce, worst_tip)) if worst_tip < self.effective_tip(&tx) => {
+                    dropped.extend(self.remove_at(&worst_sender, &worst_nonce));
+                },
+                _ => return Err(Error::LimitReached),
+            }
+        }
+        self.insert(tx);
+        Ok(dropped)
+    }
+
+    /// EIP-1559 replacement rule, both the max fee and the max priority fee are bumped. A blob
+    /// transaction is replaced by a blob transaction only, as in the geth blob pool.
+    fn is_replacement(&self, old: &PendingTransaction, new: &PendingTransaction) -> bool {
+        let blob_fees = match (&old.transaction.transaction, &new.transaction.transaction) {
+            (UnverifiedTransactionWrapper::Eip4844(old), UnverifiedTransactionWrapper::Eip4844(new)) => {
+                Some((old.max_fee_per_blob_gas(), new.max_fee_per_blob_gas()))
+            },
+            (UnverifiedTransactionWrapper::Eip4844(_), _) | (_, UnverifiedTransactionWrapper::Eip4844(_)) => {
+                return false
+            },
+            _ => None,
+        };
+        let bump_percent = match blob_fees {
+            Some(_) => self.options.fee_bump_percent.max(MIN_BLOB_REPLACEMENT_BUMP_PERCENT),
+            None => self.options.fee_bump_percent,
+        };
+        let bumped = |fee: U256| fee.saturating_mul((100 + bump_percent).into()) / 100;
+        new.max_fee_per_gas() >= bumped(old.max_fee_per_gas())
+            && new.max_priority_fee_per_gas() >= bumped(old.max_priority_fee_per_gas())
+            && blob_fees.map_or(true, |(old_fee, new_fee)| new_fee >= bumped(old_fee))
+    }
+
+    /// Sender, nonce and tip of the last queued transaction with the lowest tip.
+    fn worst_last(&self) -> Option<(Address, U256, U256)> {
+        self.queues
+            .iter()
+            .filter_map(|(sender, queue)| queue.iter().next_back().map(|(nonce, tx)| (*sender, *nonce, tx)))
+            .map(|(sender, nonce, tx)| (sender, nonce, self.effective_tip(tx)))
+            .min_by_key(|(_, _, tip)| *tip)
+    }
+
+    fn insert(&mut self, tx: PendingTransaction) -> Option<PendingTransaction> {
+        let sender = tx.sender;
+        let nonce = tx.unsigned().nonce();
+        self.hashes.insert(tx.tx_hash(), (sender, nonce));
+        let old = self.queues.entry(sender).or_default().insert(nonce, tx);
+        if let Some(ref old) = old {
+            self.hashes.remove(&old.tx_hash());
+        }
+        old
+    }
+
+    fn remove_at(&mut self, sender: &Address, nonce: &U256) -> Option<PendingTransaction> {
+        let queue = self.queues.get_mut(sender)?;
+        let tx = queue.remove(nonce)?;
+        if queue.is_empty() {
+            self.queues.remove(sender);
+        }
+        self.hashes.remove(&tx.tx_hash());
+        Some(tx)
+    }
+
+    /// Removes the transactions of the sender from `nonce` on.
+    fn remove_from(&mut self, sender: &Address, nonce: &U256) -> Vec<PendingTransaction> {
+        let queue = match self.queues.get_mut(sender) {
+            Some(queue) => queue,
+            None => return Vec::new(),
+        };
+        let removed = queue.split_off(nonce);
+        if queue.is_empty() {
+            self.queues.remove(sender);
+        }
+        removed
+            .into_iter()
+            .map(|(_, tx)| {
+                self.hashes.remove(&tx.tx_hash());
+                tx
+            })
+            .collect()
+    }
+
+    pub fn remove(&mut self, hash: &H256) -> Option<PendingTransaction> {
+        let (sender, nonce) = *self.hashes.get(hash)?;
+        self.remove_at(&sender, &nonce)
+    }
+
+    /// Sets the account nonce of the sender, drops and returns its transactions below it.
+    pub fn set_account_nonce(&mut self, sender: Address, nonce: U256) -> Vec<PendingTransaction> {
+        self.nonces.insert(sender, nonce);
+        let queue = match self.queues.get_mut(&sender) {
+            Some(queue) => queue,
+            None => return Vec::new(),
+        };
+        let kept = queue.split_off(&nonce);
+        let culled = std::mem::replace(queue, kept);
+        if queue.is_empty() {
+            self.queues.remove(&sender);
+        }
+        culled
+            .into_iter()
+            .map(|(_, tx)| {
+                self.hashes.remove(&tx.tx_hash());
+                tx
+            })
+            .collect()
+    }
+
+    /// Sets the base fee of the next block. Evicts and returns transactions with a lower
+    /// max fee, along with the following transactions of their senders.
+    pub fn set_base_fee(&mut self, base_fee: U256) -> Vec<PendingTransaction> {
+        self.base_fee = Some(base_fee);
+        let underpriced: Vec<_> = self
+            .queues
+            .iter()
+            .filter_map(|(sender, queue)| {
+                queue
+                    .iter()
+                    .find(|(_, tx)| tx.max_fee_per_gas() < base_fee)
+                    .map(|(nonce, _)| (*sender, *nonce))
+            })
+            .collect();
+        underpriced
+            .into_iter()
+            .flat_map(|(sender, nonce)| self.remove_from(&sender, &nonce))
+            .collect()
+    }
+
+    /// First nonce of the sender queue, its account nonce if known.
+    fn first_nonce(&self, sender: &Address) -> Option<U256> {
+        self.nonces
+            .get(sender)
+            .cloned()
+            .or_else(|| self.queues.get(sender).and_then(|queue| queue.keys().next().cloned()))
+    }
+
+    /// Transactions of the sender with consecutive nonces from its first one.
+    fn ready_queue(&self, sender: &Address) -> Vec<&PendingTransaction> {
+        let (first, queue) = match (self.first_nonce(sender), self.queues.get(sender)) {
+            (Some(first), Some(queue)) => (first, queue),
+            _ => return Vec::new(),
+        };
+        queue
+            .range(first..)
+            .zip(0u64..)
+            .take_while(|((nonce, _), index)| **nonce == first + *index)
+            .map(|((_, tx), _)| tx)
+            .collect()
+    }
+
+    /// Nonce the next transaction of the sender should use, the first missing one.
+    pub fn next_nonce(&self, sender: &Address) -> Option<U256> {
+        let first = self.first_nonce(sender)?;
+        Some(first + self.ready_queue(sender).len())
+    }
+
+    /// Transactions of the sender queued after a nonce gap.
+    pub fn future(&self, sender: &Address) -> Vec<&PendingTransaction> {
+        match (self.next_nonce(sender), self.queues.get(sender)) {
+            (Some(next), Some(queue)) => queue.range(next..).map(|(_, tx)| tx).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Transactions ready for a block with the given number and timestamp, by descending
+    /// effective tip with nonces of each sender in order, senders with the same tip by
+    /// ascending address. A sender queue stops at the first transaction with an unmet condition.
+    pub fn pending(&self, block: BlockNumber, timestamp: u64) -> Vec<&PendingTransaction> {
+        let mut senders: Vec<_> = self.queues.keys().collect();
+        senders.sort();
+        let queues: Vec<Vec<&PendingTransaction>> = senders
+            .into_iter()
+            .map(|sender| {
+                self.ready_queue(sender)
+                    .into_iter()
+                    .take_while(|tx| {
+                        tx.condition
+                            .as_ref()
+                            .map_or(true, |c| c.is_met(block, timestamp, self.base_fee))
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut heads: BinaryHeap<_> = queues
+            .iter()
+            .enumerate()
+            .filter_map(|(index, queue)| queue.first().map(|tx| (self.effective_tip(tx), Reverse(index), 0)))
+            .collect();
+        let mut pending = Vec::with_capacity(queues.iter().map(Vec::len).sum());
+        while let Some((_, Reverse(index), position)) = heads.pop() {
+            let queue = &queues[index];
+            pending.push(queue[position]);
+            if let Some(next) = queue.get(position + 1) {
+                heads.push((self.effective_tip(next), Reverse(index), position + 1));
+            }
+        }
+        pending
+    }
+}
@@ -0,0 +1,340 @@
+//! Registry of EIP-2718 typed transactions, allowing transaction types to be defined outside of this crate
+//!
+//! A custom type is decoded by its registered decoder and encoded, hashed and verified through
+//! `CustomTypedTransaction`. Unsigned transactions are out of scope: `TransactionWrapper` and its
+//! builder cover the types of this crate only, so the crate defining a custom type builds and signs
+//! it itself before wrapping it into `UnverifiedTransactionWrapper::Custom`.
+
+use super::{AccessList, ArbitrumTransaction, DepositTransaction, TransactionShared, TxType,
+            UnverifiedCip64Transaction, UnverifiedEip1559Transaction, UnverifiedEip2930Transaction,
+            UnverifiedEip4844Transaction, UnverifiedEip7702Transaction, UnverifiedLegacyTransaction,
+            UnverifiedTransactionWrapper, UnverifiedZkSyncEip712Transaction};
+use crate::{Error, TxDecodeError};
+use ethereum_types::{Address, H256, U256};
+use rlp::{DecoderError, Rlp, RlpStream};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+/// Highest type byte allowed by EIP-2718, bytes from 0xc0 start an RLP list of a legacy transaction.
+pub const MAX_TX_TYPE: u8 = 0x7f;
+
+/// How the sender of a custom transaction is determined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SenderPolicy {
+    /// Sender is recovered from the signature of `message_hash`, `v` is the signature parity.
+    Signature { r: U256, s: U256, v: u8 },
+    /// Sender is carried by the transaction and set by the protocol, like for deposits.
+    Implied(Address),
+}
+
+/// Typed transaction defined outside of this crate.
+pub trait CustomTypedTransaction: TransactionShared + fmt::Debug + Send + Sync {
+    /// EIP-2718 type byte.
+    fn type_byte(&self) -> u8;
+    /// Append object with a signature into RLP stream, starting with the type byte.
+    fn rlp_append_sealed_transaction(&self, s: &mut RlpStream);
+    /// Hash of the transaction.
+    fn hash(&self) -> H256;
+    /// Sender of the transaction.
+    fn sender_policy(&self) -> SenderPolicy;
+    /// Chain id the transaction is bound to, `None` if it is valid on any chain.
+    fn chain_id(&self) -> Option<u64> { None }
+    /// Max fee per gas, or gas price for transactions without a priority fee.
+    fn max_fee_per_gas(&self) -> U256 { U256::zero() }
+    /// Max priority fee per gas, defaults to `max_fee_per_gas` as for transactions without a priority fee.
+    fn max_priority_fee_per_gas(&self) -> U256 { self.max_fee_per_gas() }
+    /// Access list, if the transaction carries one.
+    fn access_list(&self) -> Option<&AccessList> { None }
+}
+
+/// Custom typed transaction held by `UnverifiedTransactionWrapper::Custom`.
+#[derive(Debug, Clone)]
+pub struct CustomTransaction(Arc<dyn CustomTypedTransaction>);
+
+impl CustomTransaction {
+    pub fn new<T: CustomTypedTransaction + 'static>(tx: T) -> Self { CustomTransaction(Arc::new(tx)) }
+
+    pub fn inner(&self) -> &dyn CustomTypedTransaction { self.0.as_ref() }
+
+    pub fn type_byte(&self) -> u8 { self.0.type_byte() }
+
+    pub fn hash(&self) -> H256 { self.0.hash() }
+
+    pub fn sender_policy(&self) -> SenderPolicy { self.0.sender_policy() }
+
+    pub(crate) fn rlp_append_sealed_transaction(&self, s: &mut RlpStream) { self.0.rlp_append_sealed_transaction(s) }
+
+    fn encoded(&self) -> Vec<u8> {
+        let mut stream = RlpStream::new();
+        self.rlp_append_sealed_transaction(&mut stream);
+        stream.out().to_vec()
+    }
+}
+
+impl PartialEq for CustomTransaction {
+    fn eq(&self, other: &Self) -> bool { self.type_byte() == other.type_byte() && self.encoded() == other.encoded() }
+}
+
+impl Eq for CustomTransaction {}
+
+impl TransactionShared for CustomTransaction {
+    fn nonce(&self) -> U256 { self.0.nonce() }
+    fn action(&self) -> &super::Action { self.0.action() }
+    fn value(&self) -> U256 { self.0.value() }
+    fn gas(&self) -> U256 { self.0.gas() }
+    fn data(&self) -> &super::Bytes { self.0.data() }
+    fn message_hash(&self, chain_id: Option<u64>) -> H256 { self.0.message_hash(chain_id) }
+}
+
+/// Decoder of a typed transaction envelope, the type byte included.
+pub trait TypedTransactionDecoder: Send + Sync {
+    fn decode(&self, d: &Rlp) -> Result<UnverifiedTransactionWrapper, TxDecodeError>;
+}
+
+impl<F> TypedTransactionDecoder for F
+where
+    F: Fn(&Rlp) -> Result<UnverifiedTransactionWrapper, TxDecodeError> + Send + Sync,
+{
+    fn decode(&self, d: &Rlp) -> Result<UnverifiedTransactionWrapper, TxDecodeError> { self(d) }
+}
+
+/// How strictly a transaction encoding is checked while decoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeMode {
+    /// Accepts whatever the field decoders accept, ignoring trailing bytes and extra list items.
+    Lenient,
+    /// Accepts only the canonical encoding, so the hash of a decoded transaction equals the hash of its re-encoding.
+    Strict,
+}
+
+impl Default for DecodeMode {
+    fn default() -> Self { DecodeMode::Lenient }
+}
+
+/// Decoders of typed transactions by type byte. The default registry contains all the types of this crate.
+#[derive(Clone)]
+pub struct TypedTransactionRegistry {
+    decoders: BTreeMap<u8, Arc<dyn TypedTransactionDecoder>>,
+    /// Types added with `register`, the only ones which can be unregistered.
+    custom: BTreeSet<u8>,
+}
+
+impl Default for TypedTransactionRegistry {
+    fn default() -> Self {
+        let mut registry = TypedTransactionRegistry::empty();
+        for (tx_type, decoder) in builtin_decoders() {
+            registry.decoders.insert(tx_type as u8, Arc::new(decoder));
+        }
+        registry
+    }
+}
+
+impl fmt::Debug for TypedTransactionRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TypedTransactionRegistry")
+            .field("types", &self.decoders.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl TypedTransactionRegistry {
+    /// Registry without any typed transaction, only legacy transactions are decoded.
+    pub fn empty() -> Self {
+        TypedTransactionRegistry {
+            decoders: BTreeMap::new(),
+            custom: BTreeSet::new(),
+        }
+    }
+
+    /// Registers the decoder of a type byte. Already registered types can't be replaced.
+    pub fn register<D: TypedTransactionDecoder + 'static>(&mut self, tx_type: u8, decoder: D) -> Result<(), Error> {
+        if tx_type == 0 || tx_type >= MAX_TX_TYPE {
+            return Err(Error::InvalidTxType(tx_type));
+        }
+        if self.decoders.contains_key(&tx_type) {
+            return Err(Error::TxTypeAlreadyRegistered(tx_type));
+        }
+        self.decoders.insert(tx_type, Arc::new(decoder));
+        self.custom.insert(tx_type);
+        Ok(())
+    }
+
+    /// Removes the decoder of a type byte, returns whether it was registered. Only types added with
+    /// `register` can be removed, the types of this crate are kept.
+    pub fn unregister(&mut self, tx_type: u8) -> Result<bool, Error> {
+        if self.decoders.contains_key(&tx_type) && !self.custom.contains(&tx_type) {
+            return Err(Error::BuiltinTxType(tx_type));
+        }
+        self.custom.remove(&tx_type);
+        Ok(self.decoders.remove(&tx_type).is_some())
+    }
+
+    pub fn is_registered(&self, tx_type: u8) -> bool { self.decoders.contains_key(&tx_type) }
+
+    /// Decodes a legacy transaction or a typed transaction of a registered type,
+    /// either as a raw envelope or wrapped into a byte string.
+    pub fn decode(&self, d: &Rlp) -> Result<UnverifiedTransactionWrapper, TxDecodeError> {
+        self.decode_with_mode(d, DecodeMode::Lenient)
+    }
+
+    /// Decodes a transaction like `decode`, rejecting non canonical encodings in strict mode.
+    pub fn decode_with_mode(&self, d: &Rlp, mode: DecodeMode) -> Result<UnverifiedTransactionWrapper, TxDecodeError> {
+        let tx = self.decode_lenient(d)?;
+        if mode == DecodeMode::Strict {
+            check_canonical(d, &tx)?;
+        }
+        Ok(tx)
+    }
+
+    fn decode_lenient(&self, d: &Rlp) -> Result<UnverifiedTransactionWrapper, TxDecodeError> {
+        if super::is_wrapped_typed_transaction(d) {
+            let envelope = Rlp::new(d.data()?);
+            if !super::is_typed_transaction(&envelope) {
+                return Err(DecoderError::Custom("invalid typed tx envelope").into());
+            }
+            return self.decode_lenient(&envelope);
+        }
+        if !super::is_typed_transaction(d) {
+            return Ok(UnverifiedTransactionWrapper::Legacy(
+                UnverifiedLegacyTransaction::decode_tx(d)?,
+            ));
+        }
+        // first byte is tx version
+        let tx_type = d.as_raw()[0];
+        match self.decoders.get(&tx_type) {
+            Some(decoder) => decoder.decode(d).map_err(|e| e.with_tx_type(tx_type)),
+            None => Err(TxDecodeError::new(DecoderError::Custom("unsupported tx version")).with_tx_type(tx_type)),
+        }
+    }
+}
+
+/// Checks that `d` is exactly the encoding of the decoded `tx`.
+fn check_canonical(d: &Rlp, tx: &UnverifiedTransactionWrapper) -> Result<(), TxDecodeError> {
+    let wrapped = super::is_wrapped_typed_transaction(d);
+    let mut stream = RlpStream::new();
+    if wrapped {
+        tx.rlp_append_block_item(&mut stream);
+    } else {
+        tx.rlp_append_sealed_transaction(&mut stream);
+    }
+    let canonical = stream.out();
+    if canonical[..] == d.as_raw()[..] {
+        return Ok(());
+    }
+
+    // look for the difference to report a meaningful error
+    let mut raw = d.as_raw();
+    if wrapped {
+        if d.payload_info()?.total() != raw.len() {
+            return Err(DecoderError::RlpInconsistentLengthAndData.into());
+        }
+        raw = d.data()?;
+    }
+    let tx_type = raw.first().cloned().filter(|tx_type| *tx_type < MAX_TX_TYPE);
+    let error = |rlp: DecoderError| TxDecodeError {
+        tx_type,
+        ..TxDecodeError::new(rlp)
+    };
+    let list = tx_list(raw);
+    if list.payload_info()?.total() != list.as_raw().len() {
+        return Err(error(DecoderError::RlpInconsistentLengthAndData));
+    }
+    let canonical = tx.encode_raw();
+    let canonical = tx_list(&canonical);
+    if list.item_count()? != canonical.item_count()? {
+        return Err(error(DecoderError::RlpIncorrectListLen));
+    }
+    let mut error = error(DecoderError::Custom("non canonical tx encoding"));
+    for (index, (item, canonical_item)) in list.iter().zip(canonical.iter()).enumerate() {
+        if item.as_raw() != canonical_item.as_raw() {
+            error.index = Some(index);
+            error.offset = list
+                .at_with_offset(index)
+                .ok()
+                .map(|(_, offset)| offset + tx_type.map_or(0, |_| 1));
+            break;
+        }
+    }
+    Err(error)
+}
+
+/// Fields list of a legacy transaction or of a typed transaction envelope.
+fn tx_list(raw: &[u8]) -> Rlp {
+    match raw.first() {
+        Some(tx_type) if *tx_type < MAX_TX_TYPE => Rlp::new(&raw[1..]),
+        _ => Rlp::new(raw),
+    }
+}
+
+type BuiltinDecoder = fn(&Rlp) -> Result<UnverifiedTransactionWrapper, TxDecodeError>;
+
+fn builtin_decoders() -> Vec<(TxType, BuiltinDecoder)> {
+    use UnverifiedTransactionWrapper as W;
+
+    let arbitrum: BuiltinDecoder = |d| Ok(W::Arbitrum(ArbitrumTransaction::decode_tx(d)?));
+    vec![
+        (TxType::Type1, |d| {
+            Ok(W::Eip2930(UnverifiedEip2930Transaction::decode_tx(d)?))
+        }),
+        (TxType::Type2, |d| {
+            Ok(W::Eip1559(UnverifiedEip1559Transaction::decode_tx(d)?))
+        }),
+        (TxType::Type3, |d| {
+            Ok(W::Eip4844(UnverifiedEip4844Transaction::decode_tx(d)?))
+        }),
+        (TxType::Type4, |d| {
+            Ok(W::Eip7702(UnverifiedEip7702Transaction::decode_tx(d)?))
+        }),
+        (TxType::ArbitrumDeposit, arbitrum),
+        (TxType::ArbitrumUnsigned, arbitrum),
+        (TxType::ArbitrumContract, arbitrum),
+        (TxType::ArbitrumRetry, arbitrum),
+        (TxType::ArbitrumSubmitRetryable, arbitrum),
+        (TxType::ArbitrumInternal, arbitrum),
+        (TxType::ZkSyncEip712, |d| {
+            Ok(W::ZkSyncEip712(UnverifiedZkSyncEip712Transaction::decode_tx(d)?))
+        }),
+        (TxType::Cip64, |d| {
+            Ok(W::Cip64(UnverifiedCip64Transaction::decode_tx(d)?))
+        }),
+        (TxType::Deposit, |d| Ok(W::Deposit(DepositTransaction::decode_tx(d)?))),
+    ]
+}
+
+/// Registry used by the `Decodable` implementation of `UnverifiedTransactionWrapper`.
+static GLOBAL_REGISTRY: RwLock<Option<TypedTransactionRegistry>> = RwLock::new(None);
+
+/// Registers the decoder of a type byte in the registry used by `rlp::decode`.
+pub fn register_typed_transaction<D: TypedTransactionDecoder + 'static>(tx_type: u8, decoder: D) -> Result<(), Error> {
+    let mut registry = GLOBAL_REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+    registry
+        .get_or_insert_with(TypedTransactionRegistry::default)
+        .register(tx_type, decoder)
+}
+
+/// Removes the decoder of a type byte from the registry used by `rlp::decode`, returns whether it was registered.
+/// The types of this crate can't be removed.
+pub fn unregister_typed_transaction(tx_type: u8) -> Result<bool, Error> {
+    let mut registry = GLOBAL_REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+    registry
+        .get_or_insert_with(TypedTransactionRegistry::default)
+        .unregister(tx_type)
+}
+
+/// Decodes a transaction with the registry used by `rlp::decode`.
+pub(crate) fn decode_with_global_registry(
+    d: &Rlp,
+    mode: DecodeMode,
+) -> Result<UnverifiedTransactionWrapper, TxDecodeError> {
+    {
+        let registry = GLOBAL_REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+        if let Some(registry) = registry.as_ref() {
+            return registry.decode_with_mode(d, mode);
+        }
+    }
+    let mut registry = GLOBAL_REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+    registry
+        .get_or_insert_with(TypedTransactionRegistry::default)
+        .decode_with_mode(d, mode)
+}
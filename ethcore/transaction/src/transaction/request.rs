@@ -0,0 +1,316 @@
+//! Transaction request completed by fillers over a provider
+
+use super::{AccessList, Action, Bytes, ChainRules, SignedAuthorization, TransactionWrapper, TransactionWrapperBuilder,
+            TxType};
+use crate::FillError;
+use ethereum_types::{Address, H256, U256};
+use std::collections::HashMap;
+
+/// Transaction as `eth_sendTransaction` takes it, every field optional.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransactionRequest {
+    pub from: Option<Address>,
+    /// Recipient, a contract creation if `None`
+    pub to: Option<Address>,
+    pub value: Option<U256>,
+    pub data: Option<Bytes>,
+    pub nonce: Option<U256>,
+    pub gas: Option<U256>,
+    pub gas_price: Option<U256>,
+    pub max_fee_per_gas: Option<U256>,
+    pub max_priority_fee_per_gas: Option<U256>,
+    pub chain_id: Option<u64>,
+    pub tx_type: Option<TxType>,
+    pub access_list: Option<AccessList>,
+    pub max_fee_per_blob_gas: Option<U256>,
+    pub blob_versioned_hashes: Option<Vec<H256>>,
+    pub authorization_list: Option<Vec<SignedAuthorization>>,
+}
+
+impl TransactionRequest {
+    /// Fills the missing fields with the default fillers.
+    pub fn fill(self, provider: &dyn Provider) -> Result<Self, FillError> {
+        FillPipeline::default().fill(self, provider)
+    }
+
+    /// Unsigned transaction of the filled request, to be signed with its `chain_id`.
+    pub fn build(self) -> Result<TransactionWrapper, FillError> {
+        let tx_type = self.tx_type.ok_or(FillError::MissingField("type"))?;
+        let nonce = self.nonce.ok_or(FillError::MissingField("nonce"))?;
+        let gas = self.gas.ok_or(FillError::MissingField("gas"))?;
+        let action = self.to.map_or(Action::Create, Action::Call);
+        let mut builder = TransactionWrapperBuilder::new(
+            tx_type,
+            nonce,
+            gas,
+            action,
+            self.value.unwrap_or_default(),
+            self.data.unwrap_or_default(),
+        );
+        if let Some(chain_id) = self.chain_id {
+            builder = builder.with_chain_id(chain_id);
+        }
+        if let Some(gas_price) = self.gas_price {
+            builder = builder.with_gas_price(gas_price);
+        }
+        if let (Some(max_fee), Some(max_priority_fee)) = (self.max_fee_per_gas, self.max_priority_fee_per_gas) {
+            builder = builder.with_priority_fee_per_gas(max_fee, max_priority_fee);
+        }
+        if let Some(access_list) = self.access_list {
+            builder = builder.with_access_list(access_list);
+        }
+        if let Some(max_fee_per_blob_gas) = self.max_fee_per_blob_gas {
+            builder = builder.with_blobs(max_fee_per_blob_gas, self.blob_versioned_hashes.unwrap_or_default());
+        }
+        if let Some(authorization_list) = self.authorization_list {
+            builder = builder.with_authorization_list(authorization_list);
+        }
+        Ok(builder.build()?)
+    }
+}
+
+/// Chain state the fillers query, over JSON-RPC or from memory.
+pub trait Provider {
+    fn chain_id(&self) -> Result<u64, String>;
+
+    /// Validity rules of the next block.
+    fn chain_rules(&self) -> Result<ChainRules, String>;
+
+    /// Nonce of the next transaction of the address, pending ones included.
+    fn transaction_count(&self, address: &Address) -> Result<U256, String>;
+
+    fn estimate_gas(&self, request: &TransactionRequest) -> Result<U256, String>;
+
+    /// Gas price of a legacy transaction.
+    fn gas_price(&self) -> Result<U256, String>;
+
+    fn max_priority_fee_per_gas(&self) -> Result<U256, String>;
+
+    /// Base fee of the latest block, `None` before London.
+    fn base_fee(&self) -> Result<Option<U256>, String>;
+
+    /// Blob base fee of the latest block, `None` before Cancun.
+    fn blob_base_fee(&self) -> Result<Option<U256>, String>;
+}
+
+/// Sets fields of a request the caller left empty.
+pub trait TxFiller {
+    fn fill(&self, request: &mut TransactionRequest, provider: &dyn Provider) -> Result<(), FillError>;
+}
+
+/// Sets the chain id of the provider.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChainIdFiller;
+
+impl TxFiller for ChainIdFiller {
+    fn fill(&self, request: &mut TransactionRequest, provider: &dyn Provider) -> Result<(), FillError> {
+        if request.chain_id.is_none() {
+            request.chain_id = Some(provider.chain_id().map_err(FillError::Provider)?);
+        }
+        Ok(())
+    }
+}
+
+/// Sets the nonce to the transaction count of the sender.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NonceFiller;
+
+impl TxFiller for NonceFiller {
+    fn fill(&self, request: &mut TransactionRequest, provider: &dyn Provider) -> Result<(), FillError> {
+        if request.nonce.is_none() {
+            let from = request.from.ok_or(FillError::MissingField("from"))?;
+            request.nonce = Some(provider.transaction_count(&from).map_err(FillError::Provider)?);
+        }
+        Ok(())
+    }
+}
+
+/// Picks the transaction type from the fields set and the types the chain supports.
+///
+/// Authorizations make a set code transaction and blobs a blob one. A gas price without
+/// EIP-1559 fees keeps a legacy transaction, an EIP-2930 one with an access list. Otherwise
+/// an EIP-1559 transaction is chosen if the chain supports it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TxTypeFiller;
+
+impl TxFiller for TxTypeFiller {
+    fn fill(&self, request: &mut TransactionRequest, provider: &dyn Provider) -> Result<(), FillError> {
+        if request.tx_type.is_some() {
+            return Ok(());
+        }
+        let rules = provider.chain_rules().map_err(FillError::Provider)?;
+        let has_dynamic_fee = request.max_fee_per_gas.is_some() || request.max_priority_fee_per_gas.is_some();
+        let tx_type = if request.authorization_list.is_some() {
+            TxType::Type4
+        } else if request.blob_versioned_hashes.is_some() {
+            TxType::Type3
+        } else if !has_dynamic_fee && (request.gas_price.is_some() || !rules.allows_tx_type(TxType::Type2)) {
+            if request.access_list.is_some() && rules.allows_tx_type(TxType::Type1) {
+                TxType::Type1
+            } else {
+                TxType::Legacy
+            }
+        } else {
+            TxType::Type2
+        };
+        if !rules.allows_tx_type(tx_type) {
+            return Err(FillError::UnsupportedTxType(tx_type as u8));
+        }
+        request.tx_type = Some(tx_type);
+        Ok(())
+    }
+}
+
+/// Sets the fees of the transaction type, the max fee per gas being twice the base fee plus
+/// the priority fee and the max fee per blob gas twice the blob base fee.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FeeFiller;
+
+impl TxFiller for FeeFiller {
+    fn fill(&self, request: &mut TransactionRequest, provider: &dyn Provider) -> Result<(), FillError> {
+        let tx_type = request.tx_type.ok_or(FillError::MissingField("type"))?;
+        match tx_type {
+            TxType::Legacy | TxType::Type1 => {
+                if request.gas_price.is_none() {
+                    request.gas_price = Some(provider.gas_price().map_err(FillError::Provider)?);
+                }
+            },
+            TxType::Type2 | TxType::Type3 | TxType::Type4 | TxType::Cip64 | TxType::ZkSyncEip712 => {
+                let max_priority_fee = match request.max_priority_fee_per_gas {
+                    Some(fee) => fee,
+                    None => provider.max_priority_fee_per_gas().map_err(FillError::Provider)?,
+                };
+                request.max_priority_fee_per_gas = Some(max_priority_fee);
+                if request.max_fee_per_gas.is_none() {
+                    let base_fee = provider
+                        .base_fee()
+                        .map_err(FillError::Provider)?
+                        .ok_or(FillError::UnsupportedTxType(tx_type as u8))?;
+                    request.max_fee_per_gas = Some(base_fee.saturating_mul(2.into()).saturating_add(max_priority_fee));
+                }
+                if tx_type == TxType::Type3 && request.max_fee_per_blob_gas.is_none() {
+                    let blob_base_fee = provider
+                        .blob_base_fee()
+                        .map_err(FillError::Provider)?
+                        .ok_or(FillError::UnsupportedTxType(tx_type as u8))?;
+                    request.max_fee_per_blob_gas = Some(blob_base_fee.saturating_mul(2.into()));
+                }
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+}
+
+/// Sets the gas limit to the provider estimate.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GasFiller;
+
+impl TxFiller for GasFiller {
+    fn fill(&self, request: &mut TransactionRequest, provider: &dyn Provider) -> Result<(), FillError> {
+        if request.gas.is_none() {
+            request.gas = Some(provider.estimate_gas(request).map_err(FillError::Provider)?);
+        }
+        Ok(())
+    }
+}
+
+/// Fillers run in order over a request.
+pub struct FillPipeline {
+    fillers: Vec<Box<dyn TxFiller>>,
+}
+
+impl FillPipeline {
+    /// Pipeline without any filler.
+    pub fn new() -> Self { FillPipeline { fillers: Vec::new() } }
+
+    pub fn with_filler<F: TxFiller + 'static>(mut self, filler: F) -> Self {
+        self.fillers.push(Box::new(filler));
+        self
+    }
+
+    pub fn fill(
+        &self,
+        mut request: TransactionRequest,
+        provider: &dyn Provider,
+    ) -> Result<TransactionRequest, FillError> {
+        for filler in &self.fillers {
+            filler.fill(&mut request, provider)?;
+        }
+        Ok(request)
+    }
+}
+
+impl Default for FillPipeline {
+    /// Chain id, nonce, transaction type, fees and gas limit fillers.
+    fn default() -> Self {
+        FillPipeline::new()
+            .with_filler(ChainIdFiller)
+            .with_filler(NonceFiller)
+            .with_filler(TxTypeFiller)
+            .with_filler(FeeFiller)
+            .with_filler(GasFiller)
+    }
+}
+
+/// Provider answering from memory, for tests.
+#[derive(Debug, Clone)]
+pub struct MockProvider {
+    pub rules: ChainRules,
+    /// Transaction counts by address, zero for missing ones
+    pub nonces: HashMap<Address, U256>,
+    pub gas_estimate: U256,
+    pub gas_price: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub base_fee: Option<U256>,
+    pub blob_base_fee: Option<U256>,
+}
+
+impl MockProvider {
+    /// Provider of a chain with the rules, fees of 1 gwei and a gas estimate of 21000.
+    pub fn new(rules: ChainRules) -> Self {
+        let gwei = U256::from(1_000_000_000u64);
+        MockProvider {
+            nonces: HashMap::new(),
+            gas_estimate: super::TX_GAS.into(),
+            gas_price: gwei,
+            max_priority_fee_per_gas: gwei,
+            base_fee: if rules.allows_tx_type(TxType::Type2) {
+                Some(gwei)
+            } else {
+                None
+            },
+            blob_base_fee: if rules.allows_tx_type(TxType::Type3) {
+                Some(1.into())
+            } else {
+                None
+            },
+            rules,
+        }
+    }
+
+    pub fn with_nonce(mut self, address: Address, nonce: U256) -> Self {
+        self.nonces.insert(address, nonce);
+        self
+    }
+}
+
+impl Provider for MockProvider {
+    fn chain_id(&self) -> Result<u64, String> { Ok(self.rules.chain_id) }
+
+    fn chain_rules(&self) -> Result<ChainRules, String> { Ok(self.rules.clone()) }
+
+    fn transaction_count(&self, address: &Address) -> Result<U256, String> {
+        Ok(self.nonces.get(address).cloned().unwrap_or_default())
+    }
+
+    fn estimate_gas(&self, _request: &TransactionRequest) -> Result<U256, String> { Ok(self.gas_estimate) }
+
+    fn gas_price(&self) -> Result<U256, String> { Ok(self.gas_price) }
+
+    fn max_priority_fee_per_gas(&self) -> Result<U256, String> { Ok(self.max_priority_fee_per_gas) }
+
+    fn base_fee(&self) -> Result<Option<U256>, String> { Ok(self.base_fee) }
+
+    fn blob_base_fee(&self) -> Result<Option<U256>, String> { Ok(self.blob_base_fee) }
+}
@@ -0,0 +1,60 @@
+//! Release of pending transactions once their condition is met
+
+use super::{BlockNumber, PendingTransaction};
+use ethereum_types::{Address, H256, U256};
+use std::collections::HashMap;
+
+/// Holds pending transactions until a new head meets their condition. A transaction is also
+/// held while one of the same sender with a lower nonce is, as it could not be mined first.
+#[derive(Debug, Default)]
+pub struct TransactionScheduler {
+    waiting: Vec<PendingTransaction>,
+}
+
+impl TransactionScheduler {
+    pub fn new() -> Self { TransactionScheduler::default() }
+
+    /// Holds the transaction, one without condition is released on the next head.
+    pub fn schedule(&mut self, tx: PendingTransaction) { self.waiting.push(tx); }
+
+    /// Stops holding the transaction, returns it if it was held.
+    pub fn cancel(&mut self, hash: &H256) -> Option<PendingTransaction> {
+        let index = self.waiting.iter().position(|tx| tx.tx_hash() == *hash)?;
+        Some(self.waiting.remove(index))
+    }
+
+    pub fn len(&self) -> usize { self.waiting.len() }
+
+    pub fn is_empty(&self) -> bool { self.waiting.is_empty() }
+
+    pub fn waiting(&self) -> &[PendingTransaction] { &self.waiting }
+
+    /// Releases the transactions with a condition met at the new head, in scheduling order,
+    /// but the ones behind a lower nonce of their sender still held.
+    pub fn on_new_head(
+        &mut self,
+        block: BlockNumber,
+        timestamp: u64,
+        base_fee: Option<U256>,
+    ) -> Vec<PendingTransaction> {
+        let is_met = |tx: &PendingTransaction| {
+            tx.condition
+                .as_ref()
+                .map_or(true, |condition| condition.is_met(block, timestamp, base_fee))
+        };
+        let mut lowest_held: HashMap<Address, U256> = HashMap::new();
+        for tx in self.waiting.iter().filter(|tx| !is_met(tx)) {
+            let nonce = tx.unsigned().nonce();
+            let lowest = lowest_held.entry(tx.sender).or_insert(nonce);
+            *lowest = (*lowest).min(nonce);
+        }
+        let (released, waiting): (Vec<_>, Vec<_>) = self.waiting.drain(..).partition(|tx| {
+            is_met(tx)
+                && lowest_held
+                    .get(&tx.sender)
+                    .map_or(true, |lowest| tx.unsigned().nonce() < *lowest)
+        });
+        self.waiting = waiting;
+        released
+    }
+}
@@ -1,8 +1,21 @@
 //! Transaction builders
-use super::{AccessList, Action, Bytes, Eip1559Transaction, Eip2930Transaction, LegacyTransaction, TransactionWrapper,
-            TxType, U256};
+use super::{hash_bytecode, AccessList, Action, Address, Bytes, Cip64Transaction, Eip1559Transaction,
+            Eip2930Transaction, Eip4844Transaction, Eip7702Transaction, Fork, LegacyTransaction, PaymasterParams,
+            SignedAuthorization, SignedTransaction, TransactionWrapper, TxType, UnverifiedTransactionWrapper,
+            ZkSyncEip712Transaction, DEFAULT_GAS_PER_PUBDATA_LIMIT, H256, TX_GAS, U256};
 use std::fmt;
 
+/// Min fee bump of a replacement transaction, in percent, as geth requires.
+pub const MIN_REPLACEMENT_BUMP_PERCENT: u64 = 10;
+/// Min fee bump of a replacement blob transaction, in percent, as the geth blob pool requires.
+pub const MIN_BLOB_REPLACEMENT_BUMP_PERCENT: u64 = 100;
+
+/// Raises the fee by `percent`, rounding up.
+pub fn bump_fee(fee: U256, percent: u64) -> U256 {
+    let bumped = fee.saturating_mul((100 + percent).into());
+    bumped / 100 + if (bumped % 100).is_zero() { 0 } else { 1 }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum TxBuilderError {
     /// Invalid tx type
@@ -13,6 +26,22 @@ pub enum TxBuilderError {
     NoFeePerGasSet,
     /// Chain id must be set for tx type >= 1
     NoChainIdSet,
+    /// No max fee per blob gas set
+    NoBlobFeeSet,
+    /// Blob transaction must carry at least one blob versioned hash
+    NoBlobVersionedHashes,
+    /// Contract creation is not allowed for this tx type
+    ContractCreationNotAllowed,
+    /// Set code transaction must carry at least one authorization
+    NoAuthorizationList,
+    /// No fee currency set for fee currency tx type
+    NoFeeCurrencySet,
+    /// No sender set for zkSync tx type
+    NoZkSyncSenderSet,
+    /// Factory dependency at the index is not a valid zkSync bytecode
+    InvalidFactoryDep(usize),
+    /// Gas limit is below intrinsic gas of the transaction
+    InsufficientGas { minimal: U256, got: U256 },
 }
 
 impl fmt::Display for TxBuilderError {
@@ -22,6 +51,18 @@ impl fmt::Display for TxBuilderError {
             TxBuilderError::NoGasPriceSet => "No gas price set".into(),
             TxBuilderError::NoFeePerGasSet => "No gas fee or priority fee per gas set".into(),
             TxBuilderError::NoChainIdSet => "Chain id must be set".into(),
+            TxBuilderError::NoBlobFeeSet => "No max fee per blob gas set".into(),
+            TxBuilderError::NoBlobVersionedHashes => "No blob versioned hashes set".into(),
+            TxBuilderError::ContractCreationNotAllowed => "Contract creation is not allowed for this tx type".into(),
+            TxBuilderError::NoAuthorizationList => "No authorization list set".into(),
+            TxBuilderError::NoFeeCurrencySet => "No fee currency set".into(),
+            TxBuilderError::NoZkSyncSenderSet => "No zkSync sender set".into(),
+            TxBuilderError::InvalidFactoryDep(index) => {
+                format!("Invalid factory dependency bytecode at index {}", index)
+            },
+            TxBuilderError::InsufficientGas { minimal, got } => {
+                format!("Gas limit below intrinsic gas. Min={}, Given={}", minimal, got)
+            },
         };
         f.write_fmt(format_args!("Transaction builder error ({})", msg))
     }
@@ -39,6 +80,15 @@ pub struct TransactionWrapperBuilder {
     value: U256,
     data: Bytes,
     access_list: Option<AccessList>,
+    max_fee_per_blob_gas: Option<U256>,
+    blob_versioned_hashes: Vec<H256>,
+    authorization_list: Vec<SignedAuthorization>,
+    fee_currency: Option<Address>,
+    zksync_from: Option<Address>,
+    gas_per_pubdata: Option<U256>,
+    factory_deps: Vec<Bytes>,
+    paymaster_params: Option<PaymasterParams>,
+    fork: Option<Fork>,
 }
 
 impl TransactionWrapperBuilder {
@@ -55,6 +105,15 @@ impl TransactionWrapperBuilder {
             value,
             data,
             access_list: None,
+            max_fee_per_blob_gas: None,
+            blob_versioned_hashes: Vec::new(),
+            authorization_list: Vec::new(),
+            fee_currency: None,
+            zksync_from: None,
+            gas_per_pubdata: None,
+            factory_deps: Vec::new(),
+            paymaster_params: None,
+            fork: None,
         }
     }
 
@@ -79,7 +138,139 @@ impl TransactionWrapperBuilder {
         self
     }
 
+    pub fn with_blobs(mut self, max_fee_per_blob_gas: U256, blob_versioned_hashes: Vec<H256>) -> Self {
+        self.max_fee_per_blob_gas = Some(max_fee_per_blob_gas);
+        self.blob_versioned_hashes = blob_versioned_hashes;
+        self
+    }
+
+    pub fn with_authorization_list(mut self, authorization_list: Vec<SignedAuthorization>) -> Self {
+        self.authorization_list = authorization_list;
+        self
+    }
+
+    pub fn with_fee_currency(mut self, fee_currency: Address) -> Self {
+        self.fee_currency = Some(fee_currency);
+        self
+    }
+
+    /// Sets zkSync specific params, `gas_per_pubdata` defaults to `DEFAULT_GAS_PER_PUBDATA_LIMIT`
+    pub fn with_zksync_params(
+        mut self,
+        from: Address,
+        gas_per_pubdata: Option<U256>,
+        factory_deps: Vec<Bytes>,
+        paymaster_params: Option<PaymasterParams>,
+    ) -> Self {
+        self.zksync_from = Some(from);
+        self.gas_per_pubdata = gas_per_pubdata;
+        self.factory_deps = factory_deps;
+        self.paymaster_params = paymaster_params;
+        self
+    }
+
+    /// Makes `build` refuse a gas limit below intrinsic gas of the transaction under `fork` rules
+    pub fn with_fork(mut self, fork: Fork) -> Self {
+        self.fork = Some(fork);
+        self
+    }
+
+    /// Replacement of the transaction with the same content and fees raised by `bump_percent`,
+    /// at least by `MIN_REPLACEMENT_BUMP_PERCENT`. Blob transactions have all their fees raised
+    /// by at least `MIN_BLOB_REPLACEMENT_BUMP_PERCENT`.
+    pub fn speed_up(tx: &SignedTransaction, bump_percent: u64) -> Result<TransactionWrapper, TxBuilderError> {
+        Self::replacement_of(tx, bump_percent)?.build()
+    }
+
+    /// Replacement of the transaction by a zero value transfer of 21000 gas to its sender,
+    /// with fees raised as by `speed_up`. A set code transaction is replaced by an EIP-1559 one
+    /// and a blob transaction keeps its blobs, the blob pool only replacing blob transactions.
+    pub fn cancel(tx: &SignedTransaction, bump_percent: u64) -> Result<TransactionWrapper, TxBuilderError> {
+        let mut builder = Self::replacement_of(tx, bump_percent)?;
+        if builder.tx_type == TxType::Type4 {
+            builder.tx_type = TxType::Type2;
+            builder.authorization_list = Vec::new();
+        }
+        builder.gas = TX_GAS.into();
+        builder.action = Action::Call(tx.sender);
+        builder.value = U256::zero();
+        builder.data = Bytes::new();
+        builder.access_list = None;
+        builder.factory_deps = Vec::new();
+        builder.build()
+    }
+
+    /// Builder of a copy of the transaction with bumped fees.
+    fn replacement_of(tx: &UnverifiedTransactionWrapper, bump_percent: u64) -> Result<Self, TxBuilderError> {
+        let tx_type = match tx {
+            UnverifiedTransactionWrapper::Legacy(_) => TxType::Legacy,
+            UnverifiedTransactionWrapper::Eip2930(_) => TxType::Type1,
+            UnverifiedTransactionWrapper::Eip1559(_) => TxType::Type2,
+            UnverifiedTransactionWrapper::Eip4844(_) => TxType::Type3,
+            UnverifiedTransactionWrapper::Eip7702(_) => TxType::Type4,
+            UnverifiedTransactionWrapper::Cip64(_) => TxType::Cip64,
+            UnverifiedTransactionWrapper::ZkSyncEip712(_) => TxType::ZkSyncEip712,
+            UnverifiedTransactionWrapper::Deposit(_)
+            | UnverifiedTransactionWrapper::Arbitrum(_)
+            | UnverifiedTransactionWrapper::Custom(_) => return Err(TxBuilderError::InvalidTxType),
+        };
+        let unsigned = tx.unsigned();
+        let mut builder = TransactionWrapperBuilder::new(
+            tx_type,
+            unsigned.nonce(),
+            unsigned.gas(),
+            unsigned.action().clone(),
+            unsigned.value(),
+            unsigned.data().clone(),
+        );
+        builder.chain_id = tx.chain_id();
+        builder.access_list = tx.access_list().cloned();
+
+        let bump_percent = bump_percent.max(MIN_REPLACEMENT_BUMP_PERCENT);
+        match tx {
+            UnverifiedTransactionWrapper::Legacy(_) | UnverifiedTransactionWrapper::Eip2930(_) => {
+                builder.gas_price = Some(bump_fee(tx.gas_price(), bump_percent));
+            },
+            UnverifiedTransactionWrapper::Eip4844(blob_tx) => {
+                let bump_percent = bump_percent.max(MIN_BLOB_REPLACEMENT_BUMP_PERCENT);
+                builder.max_fee_per_gas = Some(bump_fee(tx.max_fee_per_gas(), bump_percent));
+                builder.max_priority_fee_per_gas = Some(bump_fee(tx.max_priority_fee_per_gas(), bump_percent));
+                builder.max_fee_per_blob_gas = Some(bump_fee(blob_tx.max_fee_per_blob_gas(), bump_percent));
+                builder.blob_versioned_hashes = blob_tx.blob_versioned_hashes().to_vec();
+            },
+            _ => {
+                builder.max_fee_per_gas = Some(bump_fee(tx.max_fee_per_gas(), bump_percent));
+                builder.max_priority_fee_per_gas = Some(bump_fee(tx.max_priority_fee_per_gas(), bump_percent));
+            },
+        }
+        match tx {
+            UnverifiedTransactionWrapper::Eip7702(tx) => builder.authorization_list = tx.authorization_list().to_vec(),
+            UnverifiedTransactionWrapper::Cip64(tx) => builder.fee_currency = Some(tx.fee_currency()),
+            UnverifiedTransactionWrapper::ZkSyncEip712(tx) => {
+                builder.zksync_from = Some(tx.from());
+                builder.gas_per_pubdata = Some(tx.gas_per_pubdata());
+                builder.factory_deps = tx.factory_deps().to_vec();
+                builder.paymaster_params = tx.paymaster_params().cloned();
+            },
+            _ => {},
+        }
+        Ok(builder)
+    }
+
     pub fn build(self) -> Result<TransactionWrapper, TxBuilderError> {
+        let fork = self.fork;
+        let tx = self.build_tx()?;
+        if let Some(fork) = fork {
+            let minimal = U256::from(tx.intrinsic_gas(fork));
+            let got = tx.shared().gas();
+            if got < minimal {
+                return Err(TxBuilderError::InsufficientGas { minimal, got });
+            }
+        }
+        Ok(tx)
+    }
+
+    fn build_tx(self) -> Result<TransactionWrapper, TxBuilderError> {
         match self.tx_type {
             TxType::Legacy => Ok(TransactionWrapper::Legacy(LegacyTransaction {
                 nonce: self.nonce,
@@ -118,7 +309,87 @@ impl TransactionWrapperBuilder {
                     AccessList::default()
                 },
             })),
-            TxType::Invalid => Err(TxBuilderError::InvalidTxType),
+            TxType::Type3 => {
+                if self.action == Action::Create {
+                    return Err(TxBuilderError::ContractCreationNotAllowed);
+                }
+                if self.blob_versioned_hashes.is_empty() {
+                    return Err(TxBuilderError::NoBlobVersionedHashes);
+                }
+                Ok(TransactionWrapper::Eip4844(Eip4844Transaction {
+                    chain_id: self.chain_id.ok_or(TxBuilderError::NoChainIdSet)?,
+                    nonce: self.nonce,
+                    max_fee_per_gas: self.max_fee_per_gas.ok_or(TxBuilderError::NoFeePerGasSet)?,
+                    max_priority_fee_per_gas: self.max_priority_fee_per_gas.ok_or(TxBuilderError::NoFeePerGasSet)?,
+                    gas: self.gas,
+                    action: self.action,
+                    value: self.value,
+                    data: self.data,
+                    access_list: self.access_list.unwrap_or_default(),
+                    max_fee_per_blob_gas: self.max_fee_per_blob_gas.ok_or(TxBuilderError::NoBlobFeeSet)?,
+                    blob_versioned_hashes: self.blob_versioned_hashes,
+                }))
+            },
+            TxType::Type4 => {
+                if self.action == Action::Create {
+                    return Err(TxBuilderError::ContractCreationNotAllowed);
+                }
+                if self.authorization_list.is_empty() {
+                    return Err(TxBuilderError::NoAuthorizationList);
+                }
+                Ok(TransactionWrapper::Eip7702(Eip7702Transaction {
+                    chain_id: self.chain_id.ok_or(TxBuilderError::NoChainIdSet)?,
+                    nonce: self.nonce,
+                    max_fee_per_gas: self.max_fee_per_gas.ok_or(TxBuilderError::NoFeePerGasSet)?,
+                    max_priority_fee_per_gas: self.max_priority_fee_per_gas.ok_or(TxBuilderError::NoFeePerGasSet)?,
+                    gas: self.gas,
+                    action: self.action,
+                    value: self.value,
+                    data: self.data,
+                    access_list: self.access_list.unwrap_or_default(),
+                    authorization_list: self.authorization_list,
+                }))
+            },
+            TxType::Cip64 => Ok(TransactionWrapper::Cip64(Cip64Transaction {
+                chain_id: self.chain_id.ok_or(TxBuilderError::NoChainIdSet)?,
+                nonce: self.nonce,
+                max_fee_per_gas: self.max_fee_per_gas.ok_or(TxBuilderError::NoFeePerGasSet)?,
+                max_priority_fee_per_gas: self.max_priority_fee_per_gas.ok_or(TxBuilderError::NoFeePerGasSet)?,
+                gas: self.gas,
+                action: self.action,
+                value: self.value,
+                data: self.data,
+                access_list: self.access_list.unwrap_or_default(),
+                fee_currency: self.fee_currency.ok_or(TxBuilderError::NoFeeCurrencySet)?,
+            })),
+            TxType::ZkSyncEip712 => {
+                if let Some(index) = self.factory_deps.iter().position(|dep| hash_bytecode(dep).is_err()) {
+                    return Err(TxBuilderError::InvalidFactoryDep(index));
+                }
+                Ok(TransactionWrapper::ZkSyncEip712(ZkSyncEip712Transaction {
+                    chain_id: self.chain_id.ok_or(TxBuilderError::NoChainIdSet)?,
+                    nonce: self.nonce,
+                    max_fee_per_gas: self.max_fee_per_gas.ok_or(TxBuilderError::NoFeePerGasSet)?,
+                    max_priority_fee_per_gas: self.max_priority_fee_per_gas.ok_or(TxBuilderError::NoFeePerGasSet)?,
+                    gas: self.gas,
+                    action: self.action,
+                    value: self.value,
+                    data: self.data,
+                    from: self.zksync_from.ok_or(TxBuilderError::NoZkSyncSenderSet)?,
+                    gas_per_pubdata: self
+                        .gas_per_pubdata
+                        .unwrap_or_else(|| DEFAULT_GAS_PER_PUBDATA_LIMIT.into()),
+                    factory_deps: self.factory_deps,
+                    paymaster_params: self.paymaster_params,
+                }))
+            },
+            TxType::ArbitrumDeposit
+            | TxType::ArbitrumUnsigned
+            | TxType::ArbitrumContract
+            | TxType::ArbitrumRetry
+            | TxType::ArbitrumSubmitRetryable
+            | TxType::ArbitrumInternal
+            | TxType::Deposit => Err(TxBuilderError::InvalidTxType),
         }
     }
 }
@@ -0,0 +1,169 @@
+//! Pre-broadcast verification of a transaction against local limits and the sender account
+
+use super::{BanList, ChainRules, Fork, SignedTransaction, UnverifiedTransactionWrapper};
+use crate::Error;
+use ethereum_types::U256;
+use unexpected::OutOfBounds;
+
+/// State of the sender account the transaction is checked against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountDetails {
+    pub nonce: U256,
+    pub balance: U256,
+}
+
+/// Checks a transaction before it is broadcast, each failure is reported with the matching
+/// `Error` variant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionVerifier {
+    /// Minimal effective gas price, compared to the max fee per gas if the base fee is unknown.
+    pub minimal_gas_price: U256,
+    pub block_gas_limit: U256,
+    /// Max size of the RLP encoded transaction.
+    pub max_tx_size: usize,
+    /// Max gas limit of a single transaction.
+    pub tx_gas_limit: Option<U256>,
+    /// Base fee of the pending block, the max fee per gas must cover it.
+    pub base_fee: Option<U256>,
+    /// Chain rules checked along with the signature, any chain id is accepted without them.
+    pub rules: Option<ChainRules>,
+    pub account: Option<AccountDetails>,
+    pub ban_list: Option<BanList>,
+}
+
+impl TransactionVerifier {
+    pub fn new(minimal_gas_price: U256, block_gas_limit: U256, max_tx_size: usize) -> Self {
+        TransactionVerifier {
+            minimal_gas_price,
+            block_gas_limit,
+            max_tx_size,
+            tx_gas_limit: None,
+            base_fee: None,
+            rules: None,
+            account: None,
+            ban_list: None,
+        }
+    }
+
+    pub fn with_tx_gas_limit(mut self, tx_gas_limit: U256) -> Self {
+        self.tx_gas_limit = Some(tx_gas_limit);
+        self
+    }
+
+    pub fn with_base_fee(mut self, base_fee: U256) -> Self {
+        self.base_fee = Some(base_fee);
+        self
+    }
+
+    pub fn with_rules(mut self, rules: ChainRules) -> Self {
+        self.rules = Some(rules);
+        self
+    }
+
+    pub fn with_account(mut self, nonce: U256, balance: U256) -> Self {
+        self.account = Some(AccountDetails { nonce, balance });
+        self
+    }
+
+    pub fn with_ban_list(mut self, ban_list: BanList) -> Self {
+        self.ban_list = Some(ban_list);
+        self
+    }
+
+    /// Fork the intrinsic gas is computed for, the latest one without chain rules.
+    fn fork(&self) -> Fork { self.rules.as_ref().map_or(Fork::LATEST, |rules| rules.fork) }
+
+    /// Runs every check but those of the sender, cheap ones first.
+    pub fn verify(&self, tx: &UnverifiedTransactionWrapper) -> Result<(), Error> {
+        if let Some(ref ban_list) = self.ban_list {
+            ban_list.verify(tx)?;
+        }
+        if tx.encode_raw().len() > self.max_tx_size {
+            return Err(Error::TooBig);
+        }
+
+        let gas = tx.unsigned().gas();
+        if gas > self.block_gas_limit {
+            return Err(Error::GasLimitExceeded {
+                limit: self.block_gas_limit,
+                got: gas,
+            });
+        }
+        let minimal_gas = U256::from(tx.intrinsic_gas(self.fork()));
+        if let Some(tx_gas_limit) = self.tx_gas_limit {
+            if gas > tx_gas_limit {
+                return Err(Error::InvalidGasLimit(OutOfBounds {
+                    min: Some(minimal_gas),
+                    max: Some(tx_gas_limit),
+                    found: gas,
+                }));
+            }
+        }
+        if gas < minimal_gas {
+            return Err(Error::InsufficientGas {
+                minimal: minimal_gas,
+                got: gas,
+            });
+        }
+
+        if let Some(base_fee) = self.base_fee {
+            let max_fee = tx.max_fee_per_gas();
+            if max_fee < base_fee {
+                return Err(Error::InsufficientGasPrice {
+                    minimal: base_fee,
+                    got: max_fee,
+                });
+            }
+        }
+        let gas_price = tx.effective_gas_price(self.base_fee);
+        if gas_price < self.minimal_gas_price {
+            return Err(Error::InsufficientGasPrice {
+                minimal: self.minimal_gas_price,
+                got: gas_price,
+            });
+        }
+
+        match &self.rules {
+            Some(rules) => rules.verify_basic(tx)?,
+            None => tx.verify_basic(true, tx.chain_id(), false)?,
+        }
+
+        if let Some(account) = self.account {
+            if tx.unsigned().nonce() < account.nonce {
+                return Err(Error::Old);
+            }
+            let cost = tx.max_cost();
+            if account.balance < cost {
+                return Err(Error::InsufficientBalance {
+                    balance: account.balance,
+                    cost,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs every check, those of the recovered sender included.
+    pub fn verify_signed(&self, tx: &SignedTransaction) -> Result<(), Error> {
+        if let Some(ref ban_list) = self.ban_list {
+            ban_list.verify_sender(&tx.sender)?;
+        }
+        self.verify(tx)
+    }
+}
+
+impl UnverifiedTransactionWrapper {
+    /// Max amount the sender pays for the transaction: value, gas at the max fee and blob gas
+    /// at the max blob fee.
+    pub fn max_cost(&self) -> U256 {
+        let unsigned = self.unsigned();
+        let mut cost = unsigned
+            .gas()
+            .saturating_mul(self.max_fee_per_gas())
+            .saturating_add(unsigned.value());
+        if let UnverifiedTransactionWrapper::Eip4844(tx) = self {
+            cost = cost.saturating_add(U256::from(tx.blob_gas()).saturating_mul(tx.max_fee_per_blob_gas()));
+        }
+        cost
+    }
+}
@@ -0,0 +1,350 @@
+//! zkSync Era EIP-712 transaction (type 0x71) encoding/decoding and signing hash
+
+use super::SignedTransactionShared;
+use super::{h256_from_u256, Action, Bytes, TransactionShared, TxFields, TxType};
+use crate::{Error, TxDecodeError};
+use ethereum_types::{Address, H256, U256};
+use hash::keccak;
+use rlp::{self, DecoderError, Rlp, RlpStream};
+use sha2::{Digest, Sha256};
+use std::ops::Deref;
+
+/// Default limit of the gas paid per pubdata byte.
+pub const DEFAULT_GAS_PER_PUBDATA_LIMIT: u64 = 50_000;
+
+const EIP712_DOMAIN_TYPE: &str = "EIP712Domain(string name,string version,uint256 chainId)";
+const EIP712_TRANSACTION_TYPE: &str = "Transaction(uint256 txType,uint256 from,uint256 to,uint256 gasLimit,uint256 gasPerPubdataByteLimit,uint256 maxFeePerGas,uint256 maxPriorityFeePerGas,uint256 paymaster,uint256 nonce,uint256 value,bytes data,bytes32[] factoryDeps,bytes paymasterInput)";
+
+/// Computes the zkSync hash of a contract bytecode, used for factory dependencies.
+/// The bytecode must be an odd number of 32 byte words, less than 2^16 of them.
+pub fn hash_bytecode(bytecode: &[u8]) -> Result<H256, Error> {
+    if bytecode.len() % 32 != 0 {
+        return Err(Error::InvalidBytecode(format!(
+            "length {} is not a multiple of 32",
+            bytecode.len()
+        )));
+    }
+    let length_in_words = bytecode.len() / 32;
+    if length_in_words % 2 == 0 {
+        return Err(Error::InvalidBytecode(format!(
+            "even number of words {}",
+            length_in_words
+        )));
+    }
+    if length_in_words > u16::MAX as usize {
+        return Err(Error::InvalidBytecode(format!("too many words {}", length_in_words)));
+    }
+    let mut hash = H256::zero();
+    hash.as_bytes_mut().copy_from_slice(&Sha256::digest(bytecode));
+    hash.as_bytes_mut()[0] = 1;
+    hash.as_bytes_mut()[1] = 0;
+    hash.as_bytes_mut()[2..4].copy_from_slice(&(length_in_words as u16).to_be_bytes());
+    Ok(hash)
+}
+
+/// Paymaster which pays the fee of the transaction.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct PaymasterParams {
+    pub paymaster: Address,
+    pub paymaster_input: Bytes,
+}
+
+/// zkSync Era transaction signed over an EIP-712 struct hash.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ZkSyncEip712Transaction {
+    /// Simple replay attack protection
+    pub(crate) chain_id: u64,
+    /// Nonce.
+    pub(crate) nonce: U256,
+    /// Max fee per gas.
+    pub(crate) max_fee_per_gas: U256,
+    /// Max priority fee per gas.
+    pub(crate) max_priority_fee_per_gas: U256,
+    /// Gas paid up front for transaction execution.
+    pub(crate) gas: U256,
+    /// Action, can be either call or contract create.
+    pub(crate) action: Action,
+    /// Transfered value.
+    pub(crate) value: U256,
+    /// Transaction data.
+    pub(crate) data: Bytes,
+    /// Account initiating the transaction.
+    pub(crate) from: Address,
+    /// Max gas paid per pubdata byte.
+    pub(crate) gas_per_pubdata: U256,
+    /// Bytecodes of contracts deployed by the transaction.
+    pub(crate) factory_deps: Vec<Bytes>,
+    /// Paymaster sponsoring the transaction.
+    pub(crate) paymaster_params: Option<PaymasterParams>,
+}
+
+impl ZkSyncEip712Transaction {
+    const fn payload_size(&self) -> usize { 16 }
+
+    pub fn max_fee_per_gas(&self) -> U256 { self.max_fee_per_gas }
+
+    pub fn max_priority_fee_per_gas(&self) -> U256 { self.max_priority_fee_per_gas }
+
+    pub fn from(&self) -> Address { self.from }
+
+    pub fn gas_per_pubdata(&self) -> U256 { self.gas_per_pubdata }
+
+    pub fn factory_deps(&self) -> &[Bytes] { &self.factory_deps }
+
+    pub fn paymaster_params(&self) -> Option<&PaymasterParams> { self.paymaster_params.as_ref() }
+
+    fn domain_separator(&self) -> H256 {
+        let mut encoded = Vec::with_capacity(4 * 32);
+        encoded.extend_from_slice(keccak(EIP712_DOMAIN_TYPE).as_bytes());
+        encoded.extend_from_slice(keccak("zkSync").as_bytes());
+        encoded.extend_from_slice(keccak("2").as_bytes());
+        encoded.extend_from_slice(h256_from_u256(self.chain_id.into()).as_bytes());
+        keccak(encoded)
+    }
+
+    fn struct_hash(&self) -> H256 {
+        let address_word = |address: &Address| H256::from(*address);
+        let to = match self.action {
+            Action::Create => Address::zero(),
+            Action::Call(ref to) => *to,
+        };
+        let (paymaster, paymaster_input) = match self.paymaster_params {
+            Some(ref params) => (params.paymaster, params.paymaster_input.as_slice()),
+            None => (Address::zero(), &[][..]),
+        };
+        let factory_deps: Vec<u8> = self
+            .factory_deps
+            .iter()
+            .flat_map(|dep| {
+                hash_bytecode(dep)
+                    .expect("factory deps are checked on build and decode; qed")
+                    .to_fixed_bytes()
+            })
+            .collect();
+        let words = [
+            keccak(EIP712_TRANSACTION_TYPE),
+            h256_from_u256((TxType::ZkSyncEip712 as u8).into()),
+            address_word(&self.from),
+            address_word(&to),
+            h256_from_u256(self.gas),
+            h256_from_u256(self.gas_per_pubdata),
+            h256_from_u256(self.max_fee_per_gas),
+            h256_from_u256(self.max_priority_fee_per_gas),
+            address_word(&paymaster),
+            h256_from_u256(self.nonce),
+            h256_from_u256(self.value),
+            keccak(&self.data),
+            keccak(factory_deps),
+            keccak(paymaster_input),
+        ];
+        keccak(words.iter().flat_map(|word| word.to_fixed_bytes()).collect::<Vec<u8>>())
+    }
+
+    /// Append transaction fields from `chain_id` on into RLP stream
+    fn rlp_append_zksync_fields(&self, s: &mut RlpStream, custom_signature: &[u8]) {
+        s.append(&self.chain_id);
+        s.append(&self.from);
+        s.append(&self.gas_per_pubdata);
+        s.begin_list(self.factory_deps.len());
+        for dep in self.factory_deps.iter() {
+            s.append(dep);
+        }
+        s.append(&custom_signature);
+        match self.paymaster_params {
+            Some(ref params) => {
+                s.begin_list(2);
+                s.append(&params.paymaster);
+                s.append(&params.paymaster_input);
+            },
+            None => {
+                s.begin_list(0);
+            },
+        }
+    }
+}
+
+impl TransactionShared for ZkSyncEip712Transaction {
+    fn nonce(&self) -> U256 { self.nonce }
+    fn action(&self) -> &Action { &self.action }
+    fn value(&self) -> U256 { self.value }
+    fn gas(&self) -> U256 { self.gas }
+    fn data(&self) -> &Bytes { &self.data }
+    /// The EIP-712 hash of the transaction.
+    fn message_hash(&self, _chain_id: Option<u64>) -> H256 {
+        let mut message = vec![0x19, 0x01];
+        message.extend_from_slice(self.domain_separator().as_bytes());
+        message.extend_from_slice(self.struct_hash().as_bytes());
+        keccak(message)
+    }
+}
+
+/// Signed zkSync transaction. The signature is validated by the `from` account,
+/// so it may be either an ECDSA signature of the account owner or a custom one.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UnverifiedZkSyncEip712Transaction {
+    /// Plain Transaction.
+    unsigned: ZkSyncEip712Transaction,
+    /// The V field of the signature, zero for custom signatures
+    v: u64,
+    /// The R field of the signature, zero for custom signatures
+    r: U256,
+    /// The S field of the signature, zero for custom signatures
+    s: U256,
+    /// Signature checked by the account
+    custom_signature: Bytes,
+    /// Hash of the transaction
+    hash: H256,
+}
+
+impl Deref for UnverifiedZkSyncEip712Transaction {
+    type Target = ZkSyncEip712Transaction;
+
+    fn deref(&self) -> &Self::Target { &self.unsigned }
+}
+
+impl rlp::Decodable for UnverifiedZkSyncEip712Transaction {
+    fn decode(d: &Rlp) -> Result<Self, DecoderError> { Ok(Self::decode_tx(d)?) }
+}
+
+impl UnverifiedZkSyncEip712Transaction {
+    pub(crate) fn decode_tx(d: &Rlp) -> Result<Self, TxDecodeError> {
+        let list = TxFields::typed(d, TxType::ZkSyncEip712)?;
+        let paymaster = list.nested(15, "paymaster_params")?;
+        let paymaster_params = match paymaster.item_count()? {
+            0 => None,
+            2 => Some(PaymasterParams {
+                paymaster: paymaster.val_at(0, "paymaster")?,
+                paymaster_input: paymaster.val_at(1, "paymaster_input")?,
+            }),
+            _ => return Err(list.error(15, "paymaster_params", DecoderError::RlpIncorrectListLen)),
+        };
+        let unsigned = ZkSyncEip712Transaction {
+            nonce: list.val_at(0, "nonce")?,
+            max_priority_fee_per_gas: list.val_at(1, "max_priority_fee_per_gas")?,
+            max_fee_per_gas: list.val_at(2, "max_fee_per_gas")?,
+            gas: list.val_at(3, "gas")?,
+            action: list.val_at(4, "action")?,
+            value: list.val_at(5, "value")?,
+            data: list.val_at(6, "data")?,
+            chain_id: list.val_at(10, "chain_id")?,
+            from: list.val_at(11, "from")?,
+            gas_per_pubdata: list.val_at(12, "gas_per_pubdata")?,
+            factory_deps: list.list_at(13, "factory_deps")?,
+            paymaster_params,
+        };
+        if unsigned.factory_deps.iter().any(|dep| hash_bytecode(dep).is_err()) {
+            return Err(list.error(13, "factory_deps", DecoderError::Custom("invalid bytecode")));
+        }
+        let r: U256 = list.val_at(8, "r")?;
+        let s: U256 = list.val_at(9, "s")?;
+        // v holds the chain id if the transaction is signed with a custom signature only
+        let v: u64 = list.val_at(7, "v")?;
+        if !(r.is_zero() && s.is_zero()) && !Self::validate_v(v) {
+            return Err(list.error(7, "v", DecoderError::Custom("invalid sig v")));
+        }
+        let mut tx = UnverifiedZkSyncEip712Transaction {
+            unsigned,
+            v,
+            r,
+            s,
+            custom_signature: list.val_at(14, "custom_signature")?,
+            hash: H256::zero(),
+        };
+        tx.hash = tx.compute_zksync_hash();
+        Ok(tx)
+    }
+}
+
+impl rlp::Encodable for UnverifiedZkSyncEip712Transaction {
+    fn rlp_append(&self, s: &mut RlpStream) { self.rlp_append_sealed_transaction(s) }
+}
+
+impl SignedTransactionShared for UnverifiedZkSyncEip712Transaction {
+    fn rlp_append_sealed_transaction(&self, s: &mut RlpStream) {
+        s.append(&(TxType::ZkSyncEip712 as u8));
+        s.begin_list(self.unsigned.payload_size());
+        s.append(&self.nonce);
+        s.append(&self.max_priority_fee_per_gas);
+        s.append(&self.max_fee_per_gas);
+        s.append(&self.gas);
+        s.append(&self.action);
+        s.append(&self.value);
+        s.append(&self.data);
+        s.append(&self.v);
+        s.append(&self.r);
+        s.append(&self.s);
+        self.unsigned.rlp_append_zksync_fields(s, &self.custom_signature);
+    }
+
+    /// zkSync transaction hash is `keccak(signed_hash || keccak(signature))`.
+    fn compute_hash(mut self) -> Self {
+        self.hash = self.compute_zksync_hash();
+        self
+    }
+
+    fn set_hash(&mut self, hash: H256) { self.hash = hash; }
+}
+
+impl UnverifiedZkSyncEip712Transaction {
+    /// Creates transaction signed with ECDSA by the account owner.
+    pub fn new(unsigned: ZkSyncEip712Transaction, r: U256, s: U256, v: u64, hash: H256) -> Result<Self, Error> {
+        if !Self::validate_v(v) {
+            return Err(Error::InvalidSignature("invalid sig v".into()));
+        }
+        let mut custom_signature = Vec::with_capacity(65);
+        custom_signature.extend_from_slice(h256_from_u256(r).as_bytes());
+        custom_signature.extend_from_slice(h256_from_u256(s).as_bytes());
+        custom_signature.push(v as u8 + 27);
+        Ok(UnverifiedZkSyncEip712Transaction {
+            unsigned,
+            v,
+            r,
+            s,
+            custom_signature,
+            hash,
+        })
+    }
+
+    /// Creates transaction with a signature checked by the `from` account contract.
+    pub fn with_custom_signature(unsigned: ZkSyncEip712Transaction, custom_signature: Bytes) -> Result<Self, Error> {
+        if custom_signature.is_empty() {
+            return Err(Error::InvalidSignature("empty custom signature".into()));
+        }
+        Ok(UnverifiedZkSyncEip712Transaction {
+            v: unsigned.chain_id,
+            unsigned,
+            r: U256::zero(),
+            s: U256::zero(),
+            custom_signature,
+            hash: H256::zero(),
+        }
+        .compute_hash())
+    }
+
+    fn validate_v(v: u64) -> bool { (0..=1).contains(&v) }
+
+    fn compute_zksync_hash(&self) -> H256 {
+        let mut message = self.unsigned.message_hash(None).to_fixed_bytes().to_vec();
+        message.extend_from_slice(keccak(&self.custom_signature).as_bytes());
+        keccak(message)
+    }
+
+    /// Whether the transaction carries a custom signature instead of an ECDSA one of the sender.
+    pub fn has_custom_signature(&self) -> bool { self.r.is_zero() && self.s.is_zero() }
+
+    /// Signature parity, zero for custom signatures.
+    pub fn standard_v(&self) -> u8 {
+        if self.has_custom_signature() {
+            0
+        } else {
+            self.v as u8 // ensured that parity is 0 or 1 for signed tx type 0x71
+        }
+    }
+
+    pub fn custom_signature(&self) -> &[u8] { &self.custom_signature }
+
+    pub fn r(&self) -> U256 { self.r }
+    pub fn s(&self) -> U256 { self.s }
+    pub fn v(&self) -> u64 { self.v }
+    pub fn hash(&self) -> H256 { self.hash }
+}
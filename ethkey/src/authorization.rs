@@ -0,0 +1,86 @@
+// Copyright 2015-2018 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use ethereum_types::{H256, U256};
+use rlp::RlpStream;
+use keccak::Keccak256;
+use {Address, Error, Message, public_to_address, recover, Secret, sign, Signature};
+
+/// Prefix of the EIP-7702 authorization message.
+pub const AUTHORIZATION_MAGIC: u8 = 0x05;
+
+/// EIP-7702 authorization: allows `address` code to be set for the signing account.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Authorization {
+	/// Chain the authorization is valid for, zero for any chain.
+	pub chain_id: U256,
+	/// Address of the code the account delegates to.
+	pub address: Address,
+	/// Nonce of the signing account.
+	pub nonce: u64,
+}
+
+impl Authorization {
+	pub fn new(chain_id: U256, address: Address, nonce: u64) -> Self {
+		Authorization { chain_id, address, nonce }
+	}
+
+	/// Message to sign: `keccak(0x05 || rlp([chain_id, address, nonce]))`.
+	pub fn message_hash(&self) -> Message {
+		let mut stream = RlpStream::new_list(3);
+		stream.append(&self.chain_id);
+		stream.append(&self.address);
+		stream.append(&self.nonce);
+		let mut message = vec![AUTHORIZATION_MAGIC];
+		message.extend_from_slice(&stream.out());
+		H256::from(message.keccak256())
+	}
+
+	/// Signs the authorization with the account secret.
+	pub fn sign(&self, secret: &Secret) -> Result<Signature, Error> {
+		sign(secret, &self.message_hash())
+	}
+
+	/// Recovers the address of the account which signed the authorization.
+	/// Signatures with high 's' are rejected as required by EIP-7702.
+	pub fn recover_authority(&self, signature: &Signature) -> Result<Address, Error> {
+		if !signature.is_valid() || !signature.is_low_s() {
+			return Err(Error::InvalidSignature);
+		}
+		let public = recover(signature, &self.message_hash())?;
+		Ok(public_to_address(&public))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+	use ethereum_types::U256;
+	use {Address, Generator, Random};
+	use super::Authorization;
+
+	#[test]
+	fn sign_and_recover_authority() {
+		let keypair = Random.generate().unwrap();
+		let delegate = Address::from_str("0x63c0c19a282a1b52b07dd5a65b58948a07dae32b").unwrap();
+		let authorization = Authorization::new(U256::one(), delegate, 7);
+		let signature = authorization.sign(keypair.secret()).unwrap();
+		assert_eq!(authorization.recover_authority(&signature).unwrap(), keypair.address());
+
+		let other = Authorization::new(U256::one(), delegate, 8);
+		assert_ne!(other.recover_authority(&signature).unwrap(), keypair.address());
+	}
+}
@@ -21,6 +21,7 @@ extern crate edit_distance;
 extern crate ethereum_types;
 extern crate mem;
 extern crate rand;
+extern crate rlp;
 extern crate rustc_hex;
 extern crate secp256k1;
 extern crate serde;
@@ -29,6 +30,7 @@ extern crate tiny_keccak;
 #[macro_use]
 extern crate serde_derive;
 
+mod authorization;
 mod error;
 mod keypair;
 mod keccak;
@@ -38,6 +40,7 @@ mod random;
 mod signature;
 mod secret;
 
+pub use self::authorization::{Authorization, AUTHORIZATION_MAGIC};
 pub use self::error::Error;
 pub use self::keypair::{KeyPair, public_to_address};
 pub use self::password::Password;
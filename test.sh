@@ -3,3 +3,4 @@
 # Running tests
 echo "Running Parity (fixed for MM2) Tests"
 cargo test -p ethcore-transaction -p ethkey
+cargo test -p ethcore-transaction --features kzg